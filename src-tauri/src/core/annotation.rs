@@ -1,9 +1,20 @@
 use image::GenericImageView;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 use crate::error::AppError;
-use crate::models::{AnnotationData, AnnotationFile};
-use crate::utils::get_annotation_file_path;
+use crate::models::{AnnotationData, AnnotationFile, ImageTag};
+use crate::utils::{backup_file_path, write_file_atomic};
+use super::annotation_db;
+use super::attributes::apply_schema as apply_attribute_schema;
+use super::history::record_edits;
+use super::image::perceptual_hash;
+use super::relations::normalize_relations;
+use super::schema::upgrade_annotation_file;
+use super::storage::{current_storage, AnnotationStorage};
+use super::tracking::record_track_ids;
 
 /// 保存标注数据
 #[tauri::command]
@@ -21,9 +32,7 @@ pub async fn save_annotations(
         });
     }
 
-    let image_bytes = std::fs::read(&image_path)?;
-    let img = image::load_from_memory(&image_bytes)?;
-    let (width, height) = img.dimensions();
+    let (width, height, image_hash) = image_info(image_path_obj)?;
 
     // 只保存文件名，不保存完整路径
     let file_name = image_path_obj
@@ -32,20 +41,7 @@ pub async fn save_annotations(
         .unwrap_or(&image_path)
         .to_string();
 
-    let now = chrono::Utc::now().to_rfc3339();
-    let annotation_file = AnnotationFile {
-        version: "1.0".to_string(),
-        image_path: file_name,
-        image_width: width,
-        image_height: height,
-        annotations,
-        created: now.clone(),
-        modified: now,
-    };
-
-    let json_data = serde_json::to_string_pretty(&annotation_file)?;
-    let annotation_file_path = get_annotation_file_path(&image_path);
-    std::fs::write(&annotation_file_path, json_data)?;
+    write_annotation_file(&image_path, file_name, width, height, image_hash, annotations)?;
 
     // println!("Annotations saved to: {}", annotation_file_path);
     Ok(())
@@ -60,44 +56,73 @@ pub async fn load_annotations(image_path: String) -> Result<Vec<AnnotationData>,
 
 /// 内部辅助函数：加载标注数据
 pub fn load_annotations_internal(image_path: &str) -> Result<Vec<AnnotationData>, AppError> {
-    let annotation_file_path = get_annotation_file_path(image_path);
+    Ok(load_annotation_file(image_path)?
+        .map(|file| file.annotations)
+        .unwrap_or_default())
+}
 
-    if !Path::new(&annotation_file_path).exists() {
+/// 内部辅助函数：加载完整的标注文件
+/// 标注文件不存在时返回None；旧版本格式会迁移到当前版本
+pub fn load_annotation_file(image_path: &str) -> Result<Option<AnnotationFile>, AppError> {
+    load_annotation_file_from(&current_storage(), image_path)
+}
+
+/// 从指定的存储方式加载完整的标注文件
+pub fn load_annotation_file_from(
+    storage: &AnnotationStorage,
+    image_path: &str,
+) -> Result<Option<AnnotationFile>, AppError> {
+    Ok(load_annotation_file_versioned(storage, image_path)?.map(|(file, _)| file))
+}
+
+/// 从指定的存储方式加载完整的标注文件，同时返回迁移前的格式版本（已是当前版本时为None）
+pub fn load_annotation_file_versioned(
+    storage: &AnnotationStorage,
+    image_path: &str,
+) -> Result<Option<(AnnotationFile, Option<String>)>, AppError> {
+    if let AnnotationStorage::Sqlite { database } = storage {
+        return annotation_db::load(Path::new(database), image_path)?
+            .map(|value| upgrade_annotation_file(value, image_path))
+            .transpose();
+    }
+
+    let annotation_file_path = storage.annotation_path(image_path)?;
+
+    if !annotation_file_path.exists() {
         // println!("No annotation file found: {}", annotation_file_path);
-        return Ok(Vec::new());
+        return Ok(None);
     }
 
-    let file_content = std::fs::read_to_string(&annotation_file_path)?;
-
-    // 尝试加载为AnnotationFile格式（包含元数据）
-    let annotation_data: Vec<AnnotationData> =
-        match serde_json::from_str::<AnnotationFile>(&file_content) {
-            Ok(annotation_file) => {
-                // println!(
-                //     "Loaded AnnotationFile format with {} annotations",
-                //     annotation_file.annotations.len()
-                // );
-                annotation_file.annotations
+    let value = match read_json_file(&annotation_file_path) {
+        Ok(value) => value,
+        Err(e) => {
+            // 标注文件损坏（如写入中途崩溃导致的截断JSON）时从 .bak 恢复
+            let backup_path = backup_file_path(&annotation_file_path);
+            if !backup_path.exists() {
+                return Err(e);
             }
-            Err(_) => {
-                // 如果失败，尝试直接加载为数组格式（旧格式兼容）
-                match serde_json::from_str::<Vec<AnnotationData>>(&file_content) {
-                    Ok(annotations) => {
-                        // println!("Loaded array format with {} annotations", annotations.len());
-                        annotations
-                    }
-                    Err(e) => {
-                        return Err(AppError {
-                            message: format!("Failed to parse annotation file: {}", e),
-                        });
-                    }
-                }
-            }
-        };
+            let value = read_json_file(&backup_path).map_err(|_| e)?;
 
-    // println!("Successfully loaded {} annotations", annotation_data.len());
+            let mut corrupt_path = annotation_file_path.clone().into_os_string();
+            corrupt_path.push(".corrupt");
+            std::fs::rename(&annotation_file_path, &corrupt_path)?;
+            write_file_atomic(&annotation_file_path, &std::fs::read(&backup_path)?, false)?;
+            // println!("Recovered corrupt annotation file from backup: {}", annotation_file_path.display());
+            value
+        }
+    };
+
+    upgrade_annotation_file(value, image_path).map(Some)
+}
 
-    Ok(annotation_data)
+/// 读取JSON文件
+fn read_json_file(path: &Path) -> Result<serde_json::Value, AppError> {
+    let file_content = String::from_utf8(std::fs::read(path)?).map_err(|e| AppError {
+        message: format!("Failed to parse annotation file: {}", e),
+    })?;
+    serde_json::from_str(&file_content).map_err(|e| AppError {
+        message: format!("Failed to parse annotation file: {}", e),
+    })
 }
 
 /// 内部辅助函数：保存标注数据
@@ -105,19 +130,9 @@ pub fn save_annotations_internal(
     image_path: &str,
     annotations: Vec<AnnotationData>,
 ) -> Result<(), AppError> {
-    // 读取图片尺寸
+    // 读取图片尺寸和感知哈希，无法读取图片时使用0作为占位
     let image_path_obj = Path::new(image_path);
-    let (width, height) = if image_path_obj.exists() {
-        match image::open(image_path_obj) {
-            Ok(img) => {
-                let (w, h) = img.dimensions();
-                (w, h)
-            }
-            Err(_) => (0, 0), // 如果无法读取图片，使用0作为占位
-        }
-    } else {
-        (0, 0)
-    };
+    let (width, height, image_hash) = image_info(image_path_obj).unwrap_or((0, 0, String::new()));
 
     // 只保存文件名，不保存完整路径
     let file_name = image_path_obj
@@ -126,19 +141,238 @@ pub fn save_annotations_internal(
         .unwrap_or(image_path)
         .to_string();
 
-    let now = chrono::Utc::now().to_rfc3339();
+    write_annotation_file(image_path, file_name, width, height, image_hash, annotations)
+}
+
+// 图片感知哈希缓存：图片路径 -> (修改时间, 文件大小, 哈希)
+lazy_static::lazy_static! {
+    static ref IMAGE_HASHES: Mutex<HashMap<PathBuf, (SystemTime, u64, String)>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 读取图片尺寸和感知哈希；图片自上次计算后没有修改（修改时间和大小相同）时只读取文件头中的尺寸，不解码整张图片
+fn image_info(image_path: &Path) -> Result<(u32, u32, String), AppError> {
+    let metadata = std::fs::metadata(image_path)?;
+    let modified = metadata.modified()?;
+    let cached = IMAGE_HASHES
+        .lock()
+        .unwrap()
+        .get(image_path)
+        .filter(|(time, len, _)| *time == modified && *len == metadata.len())
+        .map(|(_, _, hash)| hash.clone());
+    if let Some(hash) = cached {
+        if let Ok((width, height)) = image::image_dimensions(image_path) {
+            return Ok((width, height, hash));
+        }
+    }
+
+    let img = image::load_from_memory(&std::fs::read(image_path)?)?;
+    let (width, height) = img.dimensions();
+    let hash = perceptual_hash(&img);
+    IMAGE_HASHES.lock().unwrap().insert(
+        image_path.to_path_buf(),
+        (modified, metadata.len(), hash.clone()),
+    );
+    Ok((width, height, hash))
+}
+
+/// 写入标注文件，保留已有的图片级标签和视频来源（创建时间在save_annotation_file中保留）
+fn write_annotation_file(
+    image_path: &str,
+    file_name: String,
+    width: u32,
+    height: u32,
+    image_hash: String,
+    annotations: Vec<AnnotationData>,
+) -> Result<(), AppError> {
+    // 读取失败（文件损坏或来自更新的版本）时不覆盖
+    let storage = current_storage();
+    let existing = load_annotation_file_from(&storage, image_path)?;
+
     let annotation_file = AnnotationFile {
-        version: "1.0".to_string(),
-        image_path: file_name,
-        image_width: width,
-        image_height: height,
+        image_hash,
         annotations,
-        created: now.clone(),
-        modified: now,
+        tags: existing
+            .as_ref()
+            .map(|file| file.tags.clone())
+            .unwrap_or_default(),
+        source_video: existing.as_ref().and_then(|file| file.source_video.clone()),
+        ..AnnotationFile::new(file_name, width, height)
     };
 
-    let annotation_file_path = get_annotation_file_path(image_path);
-    let json_content = serde_json::to_string_pretty(&annotation_file)?;
-    std::fs::write(&annotation_file_path, json_content)?;
+    save_annotation_file_over(&storage, image_path, &annotation_file, existing.as_ref())
+}
+
+/// 内部辅助函数：写入完整的标注文件
+/// 按项目属性定义补全并检查属性，清除失效的分组和父标注引用，保留原有的创建时间和图片感知哈希，并记录每个标注的修改时间和修改者（见history模块）
+/// 已有的标注文件无法读取（损坏或来自更新的版本）时返回错误，不会覆盖
+pub fn save_annotation_file(
+    image_path: &str,
+    annotation_file: &AnnotationFile,
+) -> Result<(), AppError> {
+    let storage = current_storage();
+    let existing = load_annotation_file_from(&storage, image_path)?;
+    save_annotation_file_over(&storage, image_path, annotation_file, existing.as_ref())
+}
+
+/// 写入完整的标注文件，existing为调用方已读取的原标注文件（不再重复读取）
+fn save_annotation_file_over(
+    storage: &AnnotationStorage,
+    image_path: &str,
+    annotation_file: &AnnotationFile,
+    existing: Option<&AnnotationFile>,
+) -> Result<(), AppError> {
+    let mut annotation_file = annotation_file.clone();
+    if annotation_file.image_hash.is_empty() {
+        if let Some(existing) = existing {
+            annotation_file.image_hash = existing.image_hash.clone();
+        }
+    }
+    apply_attribute_schema(&mut annotation_file)?;
+    normalize_relations(&mut annotation_file);
+    record_edits(existing, &mut annotation_file);
+    save_annotation_file_to(storage, image_path, &annotation_file)
+}
+
+/// 按指定的存储方式写入完整的标注文件
+pub fn save_annotation_file_to(
+    storage: &AnnotationStorage,
+    image_path: &str,
+    annotation_file: &AnnotationFile,
+) -> Result<(), AppError> {
+    if let AnnotationStorage::Sqlite { database } = storage {
+        annotation_db::save(Path::new(database), image_path, annotation_file)?;
+        record_track_ids(image_path, annotation_file);
+        return Ok(());
+    }
+
+    let annotation_file_path = storage.annotation_path(image_path)?;
+    let json_content = serde_json::to_string_pretty(annotation_file)?;
+    // 镜像存储的标注目录可能还不存在
+    if let Some(parent) = annotation_file_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // 原子写入并保留上一版本为 .bak，崩溃或磁盘写满时不会留下截断的JSON
+    write_file_atomic(&annotation_file_path, json_content.as_bytes(), true)?;
+    record_track_ids(image_path, annotation_file);
     Ok(())
 }
+
+/// 指定的存储方式中是否有图片的标注
+pub fn annotation_file_exists_in(
+    storage: &AnnotationStorage,
+    image_path: &str,
+) -> Result<bool, AppError> {
+    match storage {
+        AnnotationStorage::Sqlite { database } => {
+            annotation_db::contains(Path::new(database), image_path)
+        }
+        _ => Ok(storage.annotation_path(image_path)?.exists()),
+    }
+}
+
+/// 从指定的存储方式中删除图片的标注
+pub fn remove_annotation_file_from(
+    storage: &AnnotationStorage,
+    image_path: &str,
+) -> Result<(), AppError> {
+    match storage {
+        AnnotationStorage::Sqlite { database } => {
+            annotation_db::remove(Path::new(database), image_path)
+        }
+        _ => {
+            let annotation_file_path = storage.annotation_path(image_path)?;
+            for path in [backup_file_path(&annotation_file_path), annotation_file_path] {
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 加载图片级标签
+#[tauri::command]
+pub async fn load_image_tags(image_path: String) -> Result<Vec<ImageTag>, AppError> {
+    Ok(load_annotation_file(&image_path)?
+        .map(|file| file.tags)
+        .unwrap_or_default())
+}
+
+/// 保存图片级标签（不改变框标注）
+#[tauri::command]
+pub async fn save_image_tags(image_path: String, tags: Vec<ImageTag>) -> Result<(), AppError> {
+    save_image_tags_internal(&image_path, tags)
+}
+
+/// 内部辅助函数：保存图片级标签
+pub fn save_image_tags_internal(image_path: &str, tags: Vec<ImageTag>) -> Result<(), AppError> {
+    let mut annotation_file = match load_annotation_file(image_path)? {
+        Some(file) => file,
+        None => {
+            let image_path_obj = Path::new(image_path);
+            let (width, height) = image::image_dimensions(image_path_obj).unwrap_or((0, 0));
+            AnnotationFile::new(
+                image_path_obj
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(image_path)
+                    .to_string(),
+                width,
+                height,
+            )
+        }
+    };
+
+    annotation_file.tags = tags;
+    annotation_file.modified = chrono::Utc::now().to_rfc3339();
+    save_annotation_file(image_path, &annotation_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_does_not_overwrite_unreadable_file() {
+        let dir = std::env::temp_dir().join(format!("oria-annotation-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("frame.jpg").to_string_lossy().to_string();
+        let annotation_path = dir.join("frame.json");
+        let annotation_file = AnnotationFile::new("frame.jpg".to_string(), 10, 10);
+
+        for content in [
+            r#"{"version": "99.0", "image_path": "frame.jpg", "annotations": [], "future": 1}"#,
+            "{ not json",
+        ] {
+            std::fs::write(&annotation_path, content).unwrap();
+            assert!(save_annotation_file(&image_path, &annotation_file).is_err());
+            assert_eq!(std::fs::read_to_string(&annotation_path).unwrap(), content);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn image_hash_is_recomputed_when_image_changes() {
+        let dir = std::env::temp_dir().join(format!("oria-annotation-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("frame.png");
+        let gradient = image::RgbImage::from_fn(32, 32, |x, _| image::Rgb([(x * 8) as u8; 3]));
+        gradient.save(&image_path).unwrap();
+
+        let first = image_info(&image_path).unwrap();
+        assert_eq!(image_info(&image_path).unwrap(), first);
+
+        let flipped = image::imageops::flip_horizontal(&gradient);
+        image::imageops::resize(&flipped, 48, 32, image::imageops::FilterType::Nearest)
+            .save(&image_path)
+            .unwrap();
+        let second = image_info(&image_path).unwrap();
+        assert_eq!((second.0, second.1), (48, 32));
+        assert_ne!(second.2, first.2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
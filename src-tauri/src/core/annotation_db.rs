@@ -0,0 +1,352 @@
+//! SQLite标注库
+//!
+//! 项目使用SQLite存储时，所有图片的标注记录保存在同一个数据库文件中：完整的AnnotationFile以JSON保存，
+//! 另外维护按标签、图片路径和状态查询的索引表。每次保存在一个事务中完成
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::scan::relative_path;
+use super::storage::{current_storage, AnnotationStorage};
+use crate::error::AppError;
+use crate::models::AnnotationFile;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS images (
+    image_key TEXT PRIMARY KEY,
+    status TEXT NOT NULL,
+    annotation_count INTEGER NOT NULL,
+    modified TEXT NOT NULL,
+    record TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS images_status ON images(status);
+CREATE TABLE IF NOT EXISTS image_labels (
+    image_key TEXT NOT NULL,
+    label TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (image_key, label)
+);
+CREATE INDEX IF NOT EXISTS image_labels_label ON image_labels(label);
+";
+
+// 当前打开的数据库连接（同一时间只使用一个项目的数据库）
+lazy_static::lazy_static! {
+    static ref CONNECTION: Mutex<Option<(PathBuf, Connection)>> = Mutex::new(None);
+}
+
+/// 使用指定数据库的连接，首次使用时创建数据库和表
+fn with_connection<T>(
+    database: &Path,
+    f: impl FnOnce(&mut Connection) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let mut guard = CONNECTION.lock().unwrap();
+    if !matches!(guard.as_ref(), Some((path, _)) if path == database) {
+        if let Some(parent) = database.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(database)?;
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        connection.execute_batch(SCHEMA)?;
+        *guard = Some((database.to_path_buf(), connection));
+    }
+    let (_, connection) = guard.as_mut().unwrap();
+    f(connection)
+}
+
+/// 数据库目录
+fn database_dir(database: &Path) -> &Path {
+    database
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// 图片在数据库中的键：数据库目录下的图片使用相对路径（/ 分隔），便于整个项目目录移动或共享
+fn image_key(database: &Path, image_path: &str) -> String {
+    let root = database_dir(database);
+    let path = Path::new(image_path);
+    if path.starts_with(root) {
+        relative_path(root, path)
+    } else {
+        image_path.to_string()
+    }
+}
+
+/// 图片标注状态：有待审核的插值标注为 review，没有任何标注和标签为 unlabeled，其余为 labeled
+fn annotation_status(annotation_file: &AnnotationFile) -> &'static str {
+    if annotation_file.annotations.iter().any(|a| a.interpolated) {
+        "review"
+    } else if annotation_file.annotations.is_empty() && annotation_file.tags.is_empty() {
+        "unlabeled"
+    } else {
+        "labeled"
+    }
+}
+
+/// 读取图片的标注记录（未经格式迁移的JSON）
+pub fn load(database: &Path, image_path: &str) -> Result<Option<serde_json::Value>, AppError> {
+    let key = image_key(database, image_path);
+    let record: Option<String> = with_connection(database, |connection| {
+        Ok(connection
+            .query_row(
+                "SELECT record FROM images WHERE image_key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    })?;
+
+    match record {
+        Some(record) => Ok(Some(serde_json::from_str(&record)?)),
+        None => Ok(None),
+    }
+}
+
+/// 图片是否有标注记录
+pub fn contains(database: &Path, image_path: &str) -> Result<bool, AppError> {
+    let key = image_key(database, image_path);
+    with_connection(database, |connection| {
+        Ok(connection
+            .query_row(
+                "SELECT 1 FROM images WHERE image_key = ?1",
+                params![key],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    })
+}
+
+/// 保存图片的标注记录，记录和标签索引在同一事务中更新
+pub fn save(
+    database: &Path,
+    image_path: &str,
+    annotation_file: &AnnotationFile,
+) -> Result<(), AppError> {
+    let key = image_key(database, image_path);
+    let record = serde_json::to_string(annotation_file)?;
+
+    let mut label_counts: BTreeMap<&str, i64> = BTreeMap::new();
+    for annotation in &annotation_file.annotations {
+        let label = annotation.label.trim();
+        if !label.is_empty() {
+            *label_counts.entry(label).or_insert(0) += 1;
+        }
+    }
+
+    with_connection(database, |connection| {
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO images (image_key, status, annotation_count, modified, record)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(image_key) DO UPDATE SET
+                 status = excluded.status,
+                 annotation_count = excluded.annotation_count,
+                 modified = excluded.modified,
+                 record = excluded.record",
+            params![
+                key,
+                annotation_status(annotation_file),
+                annotation_file.annotations.len() as i64,
+                annotation_file.modified,
+                record
+            ],
+        )?;
+        transaction.execute(
+            "DELETE FROM image_labels WHERE image_key = ?1",
+            params![key],
+        )?;
+        for (label, count) in &label_counts {
+            transaction.execute(
+                "INSERT INTO image_labels (image_key, label, count) VALUES (?1, ?2, ?3)",
+                params![key, label, count],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    })
+}
+
+/// 删除图片的标注记录
+pub fn remove(database: &Path, image_path: &str) -> Result<(), AppError> {
+    let key = image_key(database, image_path);
+    with_connection(database, |connection| {
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM image_labels WHERE image_key = ?1",
+            params![key],
+        )?;
+        transaction.execute("DELETE FROM images WHERE image_key = ?1", params![key])?;
+        transaction.commit()?;
+        Ok(())
+    })
+}
+
+/// 数据库中所有标注记录对应的图片路径
+pub fn image_paths(database: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let keys: Vec<String> = with_connection(database, |connection| {
+        let mut statement =
+            connection.prepare("SELECT image_key FROM images ORDER BY image_key")?;
+        let keys = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(keys)
+    })?;
+    let root = database_dir(database);
+    Ok(keys.into_iter().map(|key| root.join(key)).collect())
+}
+
+/// 当前使用的SQLite数据库
+fn active_database() -> Result<PathBuf, AppError> {
+    match current_storage() {
+        AnnotationStorage::Sqlite { database } => Ok(PathBuf::from(database)),
+        _ => Err(AppError {
+            message: "当前未使用SQLite标注存储".to_string(),
+        }),
+    }
+}
+
+/// 标注查询条件（条件之间为且的关系）
+#[derive(Debug, Default, Deserialize)]
+pub struct AnnotationQuery {
+    #[serde(default)]
+    pub label: Option<String>, // 包含该标签的图片
+    #[serde(default)]
+    pub status: Option<String>, // "labeled"、"unlabeled" 或 "review"
+    #[serde(default)]
+    pub image: Option<String>, // 图片路径包含的文本
+    #[serde(default)]
+    pub attributes: BTreeMap<String, serde_json::Value>, // 有标注的属性值与之相等
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// 查询到的图片标注概要
+#[derive(Debug, Serialize)]
+pub struct AnnotationRecordSummary {
+    pub image_path: String,
+    pub status: String,
+    pub annotation_count: u32,
+    pub labels: Vec<String>,
+    pub modified: String,
+}
+
+/// 标签统计
+#[derive(Debug, Serialize)]
+pub struct LabelCount {
+    pub label: String,
+    pub image_count: u32,
+    pub annotation_count: u32,
+}
+
+/// 按标签、状态、图片路径和标注属性查询SQLite标注库
+#[tauri::command]
+pub async fn query_annotations(
+    query: AnnotationQuery,
+) -> Result<Vec<AnnotationRecordSummary>, AppError> {
+    let database = active_database()?;
+    let root = database_dir(&database).to_path_buf();
+
+    let mut sql = "SELECT image_key, status, annotation_count, modified FROM images i WHERE 1 = 1"
+        .to_string();
+    let mut values: Vec<SqlValue> = Vec::new();
+    if let Some(label) = query.label {
+        values.push(SqlValue::Text(label));
+        sql += &format!(
+            " AND EXISTS (SELECT 1 FROM image_labels l WHERE l.image_key = i.image_key AND l.label = ?{})",
+            values.len()
+        );
+    }
+    if let Some(status) = query.status {
+        values.push(SqlValue::Text(status));
+        sql += &format!(" AND status = ?{}", values.len());
+    }
+    if let Some(image) = query.image {
+        values.push(SqlValue::Text(format!("%{}%", image)));
+        sql += &format!(" AND image_key LIKE ?{}", values.len());
+    }
+    for (name, value) in query.attributes {
+        // json_extract返回的布尔值为0/1
+        let value = match value {
+            serde_json::Value::Bool(flag) => SqlValue::Integer(flag as i64),
+            serde_json::Value::Number(number) => {
+                SqlValue::Real(number.as_f64().unwrap_or_default())
+            }
+            serde_json::Value::String(text) => SqlValue::Text(text),
+            other => SqlValue::Text(other.to_string()),
+        };
+        values.push(SqlValue::Text(name));
+        values.push(value);
+        sql += &format!(
+            " AND EXISTS (SELECT 1 FROM json_each(i.record, '$.annotations') a \
+             WHERE json_extract(a.value, '$.attributes.' || json_quote(?{})) = ?{})",
+            values.len() - 1,
+            values.len()
+        );
+    }
+    sql += " ORDER BY image_key";
+    if let Some(limit) = query.limit {
+        sql += &format!(" LIMIT {}", limit);
+    }
+
+    with_connection(&database, |connection| {
+        let mut statement = connection.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut label_statement = connection
+            .prepare("SELECT label FROM image_labels WHERE image_key = ?1 ORDER BY label")?;
+        let mut results = Vec::new();
+        for row in rows {
+            let (key, status, annotation_count, modified) = row?;
+            let labels = label_statement
+                .query_map(params![key], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            let image_path = if Path::new(&key).is_absolute() {
+                key
+            } else {
+                root.join(&key).to_string_lossy().to_string()
+            };
+            results.push(AnnotationRecordSummary {
+                image_path,
+                status,
+                annotation_count,
+                labels,
+                modified,
+            });
+        }
+        Ok(results)
+    })
+}
+
+/// 统计SQLite标注库中每个标签的图片数和标注数
+#[tauri::command]
+pub async fn annotation_label_counts() -> Result<Vec<LabelCount>, AppError> {
+    let database = active_database()?;
+    with_connection(&database, |connection| {
+        let mut statement = connection.prepare(
+            "SELECT label, COUNT(*), SUM(count) FROM image_labels GROUP BY label ORDER BY label",
+        )?;
+        let counts = statement
+            .query_map([], |row| {
+                Ok(LabelCount {
+                    label: row.get(0)?,
+                    image_count: row.get(1)?,
+                    annotation_count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(counts)
+    })
+}
@@ -0,0 +1,261 @@
+//! 标注属性
+//!
+//! 项目定义属性（布尔、枚举、数值、文本，可限定适用的类别并设置默认值），
+//! 每个标注在attributes中保存属性值。保存时补全默认值并按定义检查，导出时可按属性筛选标注
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use crate::error::AppError;
+use crate::models::{AnnotationData, AnnotationFile};
+
+/// 属性类型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AttributeKind {
+    Bool,
+    Enum {
+        options: Vec<String>,
+    },
+    Number {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    Text,
+}
+
+/// 属性定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeDefinition {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: AttributeKind,
+    /// 适用的类别名称，为空时适用于所有类别
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub classes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// 没有默认值时是否必须填写
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl AttributeDefinition {
+    /// 是否适用于该类别
+    pub fn applies_to(&self, label: &str) -> bool {
+        self.classes.is_empty() || self.classes.iter().any(|class| class == label)
+    }
+
+    /// 检查属性值，返回问题描述
+    pub fn check(&self, value: &Value) -> Option<String> {
+        match (&self.kind, value) {
+            (AttributeKind::Bool, Value::Bool(_)) => None,
+            (AttributeKind::Enum { options }, Value::String(option)) => (!options.contains(option))
+                .then(|| {
+                    format!(
+                        "属性 {} 的值 {} 不在可选值中（{}）",
+                        self.name,
+                        option,
+                        options.join("、")
+                    )
+                }),
+            (AttributeKind::Number { min, max }, Value::Number(number)) => {
+                let number = number.as_f64().unwrap_or_default();
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    Some(format!("属性 {} 的值 {} 超出范围", self.name, number))
+                } else {
+                    None
+                }
+            }
+            (AttributeKind::Text, Value::String(_)) => None,
+            _ => Some(format!("属性 {} 的值类型不正确: {}", self.name, value)),
+        }
+    }
+}
+
+/// COCO导出时attributes中已有的字段，不能作为属性名称（occluded只能定义为布尔属性）
+pub const RESERVED_ATTRIBUTE_NAMES: &[&str] = &["rotation", "track_id", "group_id", "parent_id"];
+
+// 当前项目的属性定义（为空时不检查）
+lazy_static::lazy_static! {
+    static ref ATTRIBUTE_SCHEMA: RwLock<Vec<AttributeDefinition>> = RwLock::new(Vec::new());
+}
+
+/// 设置当前的属性定义
+pub fn set_schema(schema: Vec<AttributeDefinition>) {
+    *ATTRIBUTE_SCHEMA.write().unwrap() = schema;
+}
+
+/// 获取当前的属性定义
+pub fn current_schema() -> Vec<AttributeDefinition> {
+    ATTRIBUTE_SCHEMA.read().unwrap().clone()
+}
+
+/// 检查属性定义本身，返回问题列表
+pub fn validate_schema(schema: &[AttributeDefinition]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut names = std::collections::HashSet::new();
+    for definition in schema {
+        if definition.name.trim().is_empty() {
+            problems.push("属性名称为空".to_string());
+        }
+        if !names.insert(definition.name.as_str()) {
+            problems.push(format!("属性名称重复: {}", definition.name));
+        }
+        if RESERVED_ATTRIBUTE_NAMES.contains(&definition.name.as_str()) {
+            problems.push(format!("属性名称 {} 为保留字段", definition.name));
+        }
+        if definition.name == "occluded" && !matches!(definition.kind, AttributeKind::Bool) {
+            problems.push("属性 occluded 只能是布尔类型".to_string());
+        }
+        if let AttributeKind::Enum { options } = &definition.kind {
+            if options.is_empty() {
+                problems.push(format!("枚举属性 {} 没有可选值", definition.name));
+            }
+        }
+        if let Some(problem) = definition
+            .default
+            .as_ref()
+            .and_then(|v| definition.check(v))
+        {
+            problems.push(format!("默认值无效: {}", problem));
+        }
+    }
+    problems
+}
+
+/// 按当前属性定义补全默认值并检查标注属性
+/// 没有定义属性时不做检查；有定义时不允许未定义或不适用于该类别的属性
+pub fn apply_schema(annotation_file: &mut AnnotationFile) -> Result<(), AppError> {
+    let schema = ATTRIBUTE_SCHEMA.read().unwrap();
+    if schema.is_empty() {
+        return Ok(());
+    }
+
+    for annotation in &mut annotation_file.annotations {
+        for definition in schema.iter().filter(|d| d.applies_to(&annotation.label)) {
+            if !annotation.attributes.contains_key(&definition.name) {
+                if let Some(default) = &definition.default {
+                    annotation
+                        .attributes
+                        .insert(definition.name.clone(), default.clone());
+                }
+            }
+        }
+
+        let problem = annotation.attributes.iter().find_map(|(name, value)| {
+            match schema.iter().find(|d| &d.name == name) {
+                None => Some(format!("未定义的属性: {}", name)),
+                Some(d) if !d.applies_to(&annotation.label) => {
+                    Some(format!("属性 {} 不适用于类别 {}", name, annotation.label))
+                }
+                Some(d) => d.check(value),
+            }
+        });
+        let missing = schema
+            .iter()
+            .find(|d| {
+                d.required
+                    && d.applies_to(&annotation.label)
+                    && !annotation.attributes.contains_key(&d.name)
+            })
+            .map(|d| format!("缺少必填属性: {}", d.name));
+
+        if let Some(problem) = problem.or(missing) {
+            return Err(AppError {
+                message: format!(
+                    "标注 {}（{}）的属性无效: {}",
+                    annotation.id, annotation.label, problem
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 导入的属性：定义了属性时只保留适用于该类别的已定义属性（如CVAT导出的keyframe会被忽略）
+pub fn retain_defined(attributes: &mut BTreeMap<String, Value>, label: &str) {
+    let schema = ATTRIBUTE_SCHEMA.read().unwrap();
+    if !schema.is_empty() {
+        attributes.retain(|name, _| {
+            schema
+                .iter()
+                .any(|d| &d.name == name && d.applies_to(label))
+        });
+    }
+}
+
+/// 标注筛选条件（用于导出和查询），条件之间为且的关系
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnotationFilter {
+    /// 只包含这些类别，为空时包含所有类别
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// 属性值必须相等
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Value>,
+}
+
+impl AnnotationFilter {
+    /// 标注是否满足筛选条件
+    pub fn matches(&self, annotation: &AnnotationData) -> bool {
+        (self.labels.is_empty() || self.labels.contains(&annotation.label))
+            && self
+                .attributes
+                .iter()
+                .all(|(name, value)| annotation.attributes.get(name) == Some(value))
+    }
+}
+
+/// 获取当前的属性定义
+#[tauri::command]
+pub async fn get_attribute_schema() -> Result<Vec<AttributeDefinition>, AppError> {
+    Ok(current_schema())
+}
+
+/// 设置当前的属性定义（不使用项目时）
+#[tauri::command]
+pub async fn set_attribute_schema(schema: Vec<AttributeDefinition>) -> Result<(), AppError> {
+    let problems = validate_schema(&schema);
+    if !problems.is_empty() {
+        return Err(AppError {
+            message: format!("属性定义无效: {}", problems.join("; ")),
+        });
+    }
+    set_schema(schema);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(name: &str, kind: AttributeKind) -> AttributeDefinition {
+        AttributeDefinition {
+            name: name.to_string(),
+            kind,
+            classes: Vec::new(),
+            default: None,
+            required: false,
+        }
+    }
+
+    #[test]
+    fn reserved_attribute_names_are_rejected() {
+        for name in RESERVED_ATTRIBUTE_NAMES {
+            assert_eq!(
+                validate_schema(&[definition(name, AttributeKind::Bool)]).len(),
+                1
+            );
+        }
+        assert!(validate_schema(&[definition("occluded", AttributeKind::Bool)]).is_empty());
+        assert_eq!(
+            validate_schema(&[definition("occluded", AttributeKind::Text)]).len(),
+            1
+        );
+    }
+}
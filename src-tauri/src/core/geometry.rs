@@ -0,0 +1,575 @@
+//! 标注几何工具
+//!
+//! 标注坐标约定：x, y 为未旋转时的左上角，rotation 为绕框中心的弧度
+
+#[cfg(feature = "onnx")]
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::models::AnnotationData;
+
+/// 标注框中心点
+pub fn annotation_center(annotation: &AnnotationData) -> (f64, f64) {
+    (
+        annotation.x + annotation.width / 2.0,
+        annotation.y + annotation.height / 2.0,
+    )
+}
+
+/// 标注框四个角点（左上、右上、右下、左下），旋转框按旋转后的位置返回
+pub fn rotated_corners(annotation: &AnnotationData) -> [(f64, f64); 4] {
+    let (cx, cy) = annotation_center(annotation);
+    let hw = annotation.width / 2.0;
+    let hh = annotation.height / 2.0;
+    let (sin_r, cos_r) = annotation.rotation.unwrap_or(0.0).sin_cos();
+
+    [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+        .map(|(lx, ly)| (cx + lx * cos_r - ly * sin_r, cy + lx * sin_r + ly * cos_r))
+}
+
+/// 标注的水平外接框 (x_min, y_min, x_max, y_max)
+pub fn annotation_bounds(annotation: &AnnotationData) -> (f64, f64, f64, f64) {
+    let points: Vec<(f64, f64)> = match &annotation.points {
+        Some(points) if annotation.annotation_type == "polygon" && !points.is_empty() => {
+            points.iter().map(|p| (p[0], p[1])).collect()
+        }
+        _ => rotated_corners(annotation).to_vec(),
+    };
+
+    points.iter().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(x_min, y_min, x_max, y_max), (x, y)| {
+            (x_min.min(*x), y_min.min(*y), x_max.max(*x), y_max.max(*y))
+        },
+    )
+}
+
+/// 多边形面积（鞋带公式）
+pub fn polygon_area(points: &[[f64; 2]]) -> f64 {
+    let twice_area: f64 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+/// 两个标注外接框的交并比
+pub fn bounds_iou(a: &AnnotationData, b: &AnnotationData) -> f64 {
+    let (ax0, ay0, ax1, ay1) = annotation_bounds(a);
+    let (bx0, by0, bx1, by1) = annotation_bounds(b);
+
+    let intersection = (ax1.min(bx1) - ax0.max(bx0)).max(0.0) * (ay1.min(by1) - ay0.max(by0)).max(0.0);
+    let union = (ax1 - ax0) * (ay1 - ay0) + (bx1 - bx0) * (by1 - by0) - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+/// 裁剪标注区域
+/// 旋转框会被摆正，输出尺寸为框的宽高；框完全在图片外或过小时返回None
+#[cfg(feature = "onnx")]
+pub fn crop_annotation(image: &DynamicImage, annotation: &AnnotationData) -> Option<DynamicImage> {
+    let rotation = annotation.rotation.unwrap_or(0.0);
+    if rotation.abs() < 1e-6 {
+        crop_axis_aligned(image, annotation)
+    } else {
+        crop_rotated(image, annotation, rotation)
+    }
+}
+
+/// 裁剪水平框（裁剪到图片范围内）
+#[cfg(feature = "onnx")]
+fn crop_axis_aligned(image: &DynamicImage, annotation: &AnnotationData) -> Option<DynamicImage> {
+    let (img_width, img_height) = image.dimensions();
+    let x_min = annotation.x.max(0.0).floor() as u32;
+    let y_min = annotation.y.max(0.0).floor() as u32;
+    let x_max = ((annotation.x + annotation.width).ceil().max(0.0) as u32).min(img_width);
+    let y_max = ((annotation.y + annotation.height).ceil().max(0.0) as u32).min(img_height);
+
+    if x_max <= x_min || y_max <= y_min {
+        return None;
+    }
+
+    Some(image.crop_imm(x_min, y_min, x_max - x_min, y_max - y_min))
+}
+
+/// 裁剪旋转框：在框的局部坐标系中逐像素双线性采样，图片外的区域填充黑色
+#[cfg(feature = "onnx")]
+fn crop_rotated(image: &DynamicImage, annotation: &AnnotationData, rotation: f64) -> Option<DynamicImage> {
+    let out_width = annotation.width.round() as u32;
+    let out_height = annotation.height.round() as u32;
+    if out_width == 0 || out_height == 0 {
+        return None;
+    }
+
+    let source = image.to_rgb8();
+    let (cx, cy) = annotation_center(annotation);
+    let (sin_r, cos_r) = rotation.sin_cos();
+    let mut output = RgbImage::new(out_width, out_height);
+
+    for (u, v, pixel) in output.enumerate_pixels_mut() {
+        // 像素中心在框局部坐标系中的位置
+        let lx = u as f64 + 0.5 - out_width as f64 / 2.0;
+        let ly = v as f64 + 0.5 - out_height as f64 / 2.0;
+        let sx = cx + lx * cos_r - ly * sin_r;
+        let sy = cy + lx * sin_r + ly * cos_r;
+        *pixel = sample_bilinear(&source, sx, sy);
+    }
+
+    Some(DynamicImage::ImageRgb8(output))
+}
+
+/// 双线性采样（坐标为连续像素坐标，像素中心位于 +0.5 处）
+#[cfg(feature = "onnx")]
+fn sample_bilinear(image: &RgbImage, x: f64, y: f64) -> Rgb<u8> {
+    let (width, height) = image.dimensions();
+    let fx = x - 0.5;
+    let fy = y - 0.5;
+    if fx < -1.0 || fy < -1.0 || fx > width as f64 || fy > height as f64 {
+        return Rgb([0, 0, 0]);
+    }
+
+    let x0 = fx.floor();
+    let y0 = fy.floor();
+    let tx = fx - x0;
+    let ty = fy - y0;
+
+    let pixel_at = |px: f64, py: f64| -> [f64; 3] {
+        if px < 0.0 || py < 0.0 || px >= width as f64 || py >= height as f64 {
+            return [0.0; 3];
+        }
+        let p = image.get_pixel(px as u32, py as u32);
+        [p[0] as f64, p[1] as f64, p[2] as f64]
+    };
+
+    let p00 = pixel_at(x0, y0);
+    let p10 = pixel_at(x0 + 1.0, y0);
+    let p01 = pixel_at(x0, y0 + 1.0);
+    let p11 = pixel_at(x0 + 1.0, y0 + 1.0);
+
+    let mut result = [0u8; 3];
+    for c in 0..3 {
+        let top = p00[c] * (1.0 - tx) + p10[c] * tx;
+        let bottom = p01[c] * (1.0 - tx) + p11[c] * tx;
+        result[c] = (top * (1.0 - ty) + bottom * ty).round().clamp(0.0, 255.0) as u8;
+    }
+
+    Rgb(result)
+}
+
+/// 二值掩码（按行存储）
+#[derive(Debug, Clone)]
+pub struct BinaryMask {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<bool>,
+}
+
+impl BinaryMask {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return false;
+        }
+        self.data[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        self.data[(y * self.width + x) as usize] = value;
+    }
+
+    /// 前景像素数量
+    #[cfg(feature = "onnx")]
+    pub fn area(&self) -> usize {
+        self.data.iter().filter(|v| **v).count()
+    }
+
+    /// 最大的8连通前景区域
+    pub fn largest_component(&self) -> BinaryMask {
+        let mut labels = vec![0u32; self.data.len()];
+        let mut best_label = 0;
+        let mut best_size = 0;
+        let mut next_label = 1;
+
+        for start in 0..self.data.len() {
+            if !self.data[start] || labels[start] != 0 {
+                continue;
+            }
+
+            let mut size = 0;
+            let mut stack = vec![start];
+            labels[start] = next_label;
+            while let Some(index) = stack.pop() {
+                size += 1;
+                let x = (index as u32 % self.width) as i64;
+                let y = (index as u32 / self.width) as i64;
+                for (dx, dy) in NEIGHBORS {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if self.get(nx, ny) {
+                        let neighbor = (ny as u32 * self.width + nx as u32) as usize;
+                        if labels[neighbor] == 0 {
+                            labels[neighbor] = next_label;
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+
+            if size > best_size {
+                best_size = size;
+                best_label = next_label;
+            }
+            next_label += 1;
+        }
+
+        BinaryMask {
+            width: self.width,
+            height: self.height,
+            data: labels.iter().map(|l| *l != 0 && *l == best_label).collect(),
+        }
+    }
+
+    /// 外轮廓（Moore邻域跟踪），点位于像素中心，按顺时针排列
+    /// 只跟踪光栅顺序中第一个前景像素所在的区域，通常先调用largest_component
+    pub fn outer_contour(&self) -> Vec<(f64, f64)> {
+        let Some(start_index) = self.data.iter().position(|v| *v) else {
+            return Vec::new();
+        };
+        let start = (
+            (start_index as u32 % self.width) as i64,
+            (start_index as u32 / self.width) as i64,
+        );
+
+        // 从当前点出发，以回溯点为起点顺时针查找下一个前景像素
+        let next = |current: (i64, i64), backtrack: (i64, i64)| -> Option<((i64, i64), (i64, i64))> {
+            let offset = (backtrack.0 - current.0, backtrack.1 - current.1);
+            let begin = NEIGHBORS.iter().position(|d| *d == offset)?;
+            for k in 1..=8 {
+                let (dx, dy) = NEIGHBORS[(begin + k) % 8];
+                let candidate = (current.0 + dx, current.1 + dy);
+                if self.get(candidate.0, candidate.1) {
+                    let (bx, by) = NEIGHBORS[(begin + k - 1) % 8];
+                    return Some((candidate, (current.0 + bx, current.1 + by)));
+                }
+            }
+            None
+        };
+
+        let to_point = |p: (i64, i64)| (p.0 as f64 + 0.5, p.1 as f64 + 0.5);
+        let mut contour = vec![to_point(start)];
+
+        // 起点是光栅顺序的第一个前景像素，左侧一定是背景
+        let Some((first, first_backtrack)) = next(start, (start.0 - 1, start.1)) else {
+            return contour; // 单个像素
+        };
+
+        let (mut current, mut backtrack) = (first, first_backtrack);
+        let max_steps = 4 * self.data.len() + 8;
+        for _ in 0..max_steps {
+            if current == start {
+                // 回到起点且下一步与第一步相同时结束（Jacob停止准则）
+                match next(current, backtrack) {
+                    Some((candidate, _)) if candidate == first => break,
+                    _ => {}
+                }
+            }
+            contour.push(to_point(current));
+            match next(current, backtrack) {
+                Some((candidate, new_backtrack)) => {
+                    current = candidate;
+                    backtrack = new_backtrack;
+                }
+                None => break,
+            }
+        }
+
+        contour
+    }
+}
+
+/// 8邻域方向，从左侧开始顺时针（图像坐标，y向下）
+const NEIGHBORS: [(i64, i64); 8] = [
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+];
+
+/// 点到线段的距离
+fn point_segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    if length_sq == 0.0 {
+        return ((p.0 - a.0).powi(2) + (p.1 - a.1).powi(2)).sqrt();
+    }
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0);
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+/// Douglas-Peucker折线简化（保留首尾点）
+fn simplify_polyline(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let (index, max_distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (i + 1, point_segment_distance(*p, first, last)))
+        .fold((0, 0.0), |best, item| if item.1 > best.1 { item } else { best });
+
+    if max_distance <= tolerance {
+        return vec![first, last];
+    }
+
+    let mut left = simplify_polyline(&points[..=index], tolerance);
+    let right = simplify_polyline(&points[index..], tolerance);
+    left.pop();
+    left.extend(right);
+    left
+}
+
+/// 简化闭合多边形：以起点和离起点最远的点分成两段分别简化
+pub fn simplify_polygon(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if points.len() < 4 {
+        return points.to_vec();
+    }
+
+    let start = points[0];
+    let far_index = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (i, (p.0 - start.0).powi(2) + (p.1 - start.1).powi(2)))
+        .fold((0, 0.0), |best, item| if item.1 > best.1 { item } else { best })
+        .0;
+    if far_index == 0 {
+        return vec![start];
+    }
+
+    let mut closed = points.to_vec();
+    closed.push(start);
+    let mut result = simplify_polyline(&closed[..=far_index], tolerance);
+    result.pop();
+    result.extend(simplify_polyline(&closed[far_index..], tolerance));
+    result.pop(); // 去掉重复的起点
+    result
+}
+
+/// 凸包（Andrew单调链），逆时针排列
+pub fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| {
+        a.0.partial_cmp(&b.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(sorted.len() * 2);
+    for pass in 0..2 {
+        let start_len = hull.len();
+        let iter: Box<dyn Iterator<Item = &(f64, f64)>> = if pass == 0 {
+            Box::new(sorted.iter())
+        } else {
+            Box::new(sorted.iter().rev())
+        };
+        for p in iter {
+            while hull.len() >= start_len + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+    }
+
+    hull
+}
+
+/// 最小面积外接矩形，返回 (中心x, 中心y, 宽, 高, 旋转弧度)
+pub fn min_area_rect(points: &[(f64, f64)]) -> Option<(f64, f64, f64, f64, f64)> {
+    let hull = convex_hull(points);
+    if hull.is_empty() {
+        return None;
+    }
+
+    // 凸包每条边的方向都是候选方向，水平方向作为兜底
+    let mut angles = vec![0.0];
+    for i in 0..hull.len() {
+        let a = hull[i];
+        let b = hull[(i + 1) % hull.len()];
+        if a != b {
+            angles.push((b.1 - a.1).atan2(b.0 - a.0));
+        }
+    }
+
+    let mut best: Option<(f64, f64, f64, f64, f64)> = None;
+    for angle in angles {
+        let (sin_a, cos_a) = angle.sin_cos();
+        let (mut min_u, mut max_u, mut min_v, mut max_v) =
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for p in &hull {
+            let u = p.0 * cos_a + p.1 * sin_a;
+            let v = -p.0 * sin_a + p.1 * cos_a;
+            min_u = min_u.min(u);
+            max_u = max_u.max(u);
+            min_v = min_v.min(v);
+            max_v = max_v.max(v);
+        }
+
+        let (width, height) = (max_u - min_u, max_v - min_v);
+        if best.is_none_or(|b| width * height < b.2 * b.3) {
+            let (cu, cv) = ((min_u + max_u) / 2.0, (min_v + max_v) / 2.0);
+            let cx = cu * cos_a - cv * sin_a;
+            let cy = cu * sin_a + cv * cos_a;
+            best = Some((cx, cy, width, height, angle));
+        }
+    }
+
+    // 角度归一化到 [-π/4, π/4)，宽高随之交换
+    best.map(|(cx, cy, width, height, angle)| {
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let turns = ((angle + quarter / 2.0) / quarter).floor();
+        let normalized = angle - turns * quarter;
+        if (turns as i64).rem_euclid(2) == 1 {
+            (cx, cy, height, width, normalized)
+        } else {
+            (cx, cy, width, height, normalized)
+        }
+    })
+}
+
+/// 像素轮廓的最小面积外接矩形
+/// 轮廓点位于像素中心，按像素四角计算以覆盖完整像素
+pub fn pixel_min_area_rect(contour: &[(f64, f64)]) -> Option<(f64, f64, f64, f64, f64)> {
+    let corners: Vec<(f64, f64)> = contour
+        .iter()
+        .flat_map(|(x, y)| {
+            [
+                (x - 0.5, y - 0.5),
+                (x + 0.5, y - 0.5),
+                (x + 0.5, y + 0.5),
+                (x - 0.5, y + 0.5),
+            ]
+        })
+        .collect();
+    min_area_rect(&corners)
+}
+
+/// 默认多边形简化容差（像素）
+pub const DEFAULT_SIMPLIFY_TOLERANCE: f64 = 1.5;
+
+/// 由掩码轮廓拟合的形状
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShapeKind {
+    /// 简化后的多边形
+    #[default]
+    Polygon,
+    /// 最小面积旋转框
+    RotatedRectangle,
+    /// 紧贴的水平框
+    Rectangle,
+}
+
+/// 拟合结果（坐标约定与AnnotationData一致）
+#[derive(Debug, Clone)]
+pub struct FittedShape {
+    pub annotation_type: &'static str,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub rotation: Option<f64>,
+    pub points: Option<Vec<[f64; 2]>>,
+}
+
+impl FittedShape {
+    /// 用拟合结果替换标注的几何信息，保留id、类别等其他字段
+    pub fn apply_to(self, annotation: &mut AnnotationData) {
+        annotation.annotation_type = self.annotation_type.to_string();
+        annotation.x = self.x;
+        annotation.y = self.y;
+        annotation.width = self.width;
+        annotation.height = self.height;
+        annotation.rotation = self.rotation;
+        annotation.points = self.points;
+    }
+}
+
+/// 由像素轮廓拟合形状，轮廓为空时返回None
+pub fn fit_contour(
+    contour: &[(f64, f64)],
+    shape: ShapeKind,
+    simplify_tolerance: f64,
+) -> Option<FittedShape> {
+    if contour.is_empty() {
+        return None;
+    }
+
+    // 轮廓点位于像素中心，外扩半个像素得到覆盖完整像素的水平框
+    let min_x = contour.iter().map(|p| p.0).fold(f64::MAX, f64::min) - 0.5;
+    let min_y = contour.iter().map(|p| p.1).fold(f64::MAX, f64::min) - 0.5;
+    let max_x = contour.iter().map(|p| p.0).fold(f64::MIN, f64::max) + 0.5;
+    let max_y = contour.iter().map(|p| p.1).fold(f64::MIN, f64::max) + 0.5;
+    let bounds = FittedShape {
+        annotation_type: "rectangle",
+        x: min_x,
+        y: min_y,
+        width: max_x - min_x,
+        height: max_y - min_y,
+        rotation: None,
+        points: None,
+    };
+
+    match shape {
+        ShapeKind::Rectangle => Some(bounds),
+        ShapeKind::Polygon => {
+            let polygon = simplify_polygon(contour, simplify_tolerance);
+            if polygon.len() < 3 {
+                return Some(bounds);
+            }
+            Some(FittedShape {
+                annotation_type: "polygon",
+                points: Some(polygon.iter().map(|(x, y)| [*x, *y]).collect()),
+                ..bounds
+            })
+        }
+        ShapeKind::RotatedRectangle => {
+            let (cx, cy, width, height, angle) = pixel_min_area_rect(contour)?;
+            Some(FittedShape {
+                annotation_type: "rotated-rectangle",
+                x: cx - width / 2.0,
+                y: cy - height / 2.0,
+                width,
+                height,
+                rotation: Some(angle),
+                points: None,
+            })
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! 类GrabCut前景分割
+//!
+//! 框外像素固定为背景，框内像素初始为前景；交替拟合前景/背景的颜色混合模型，
+//! 并用带平滑项的ICM（迭代条件模式）更新框内像素的标签。
+//! 不依赖模型，作为没有分割模型时的框收紧兜底方案
+
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+
+use super::geometry::BinaryMask;
+
+/// 每个颜色模型的高斯分量数
+const COMPONENTS: usize = 5;
+/// 处理区域最长边（较大的区域先缩小以保证速度）
+const MAX_SIDE: u32 = 256;
+/// 相邻像素标签不同时的惩罚（对数似然单位）
+const SMOOTHNESS: f64 = 3.0;
+/// 颜色方差下限，避免单色区域的分量退化
+const MIN_VARIANCE: f64 = 4.0;
+/// 拟合颜色模型时的最大样本数
+const MAX_SAMPLES: usize = 20000;
+
+/// 对角协方差高斯分量
+#[derive(Debug, Clone)]
+struct Gaussian {
+    weight: f64,
+    mean: [f64; 3],
+    variance: [f64; 3],
+}
+
+/// 高斯混合颜色模型
+struct ColorModel {
+    components: Vec<Gaussian>,
+}
+
+impl ColorModel {
+    /// 用k-means拟合颜色模型
+    fn fit(samples: &[[f64; 3]]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        // 样本过多时等间隔抽样
+        let stride = samples.len().div_ceil(MAX_SAMPLES);
+        let samples: Vec<[f64; 3]> = samples.iter().step_by(stride).copied().collect();
+        let k = COMPONENTS.min(samples.len());
+
+        // 按亮度排序后等间隔取初始中心，保证结果确定
+        let mut by_luma = samples.clone();
+        by_luma.sort_by(|a, b| {
+            let la = a[0] + a[1] + a[2];
+            let lb = b[0] + b[1] + b[2];
+            la.partial_cmp(&lb).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut centers: Vec<[f64; 3]> = (0..k)
+            .map(|i| by_luma[(2 * i + 1) * by_luma.len() / (2 * k)])
+            .collect();
+
+        let nearest = |centers: &[[f64; 3]], p: &[f64; 3]| {
+            centers
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i, (0..3).map(|d| (p[d] - c[d]).powi(2)).sum::<f64>()))
+                .fold((0, f64::MAX), |best, item| if item.1 < best.1 { item } else { best })
+                .0
+        };
+
+        let mut assignments = vec![0; samples.len()];
+        for _ in 0..5 {
+            for (i, p) in samples.iter().enumerate() {
+                assignments[i] = nearest(&centers, p);
+            }
+            let mut sums = vec![[0.0; 3]; k];
+            let mut counts = vec![0usize; k];
+            for (p, a) in samples.iter().zip(&assignments) {
+                for d in 0..3 {
+                    sums[*a][d] += p[d];
+                }
+                counts[*a] += 1;
+            }
+            for c in 0..k {
+                if counts[c] > 0 {
+                    centers[c] = sums[c].map(|v| v / counts[c] as f64);
+                }
+            }
+        }
+
+        let mut components = Vec::with_capacity(k);
+        for (c, mean) in centers.iter().enumerate() {
+            let members: Vec<&[f64; 3]> = samples
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, a)| **a == c)
+                .map(|(p, _)| p)
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            let mut variance = [0.0; 3];
+            for p in &members {
+                for d in 0..3 {
+                    variance[d] += (p[d] - mean[d]).powi(2);
+                }
+            }
+            components.push(Gaussian {
+                weight: members.len() as f64 / samples.len() as f64,
+                mean: *mean,
+                variance: variance.map(|v| (v / members.len() as f64).max(MIN_VARIANCE)),
+            });
+        }
+
+        Some(Self { components })
+    }
+
+    /// 颜色的对数似然
+    fn log_likelihood(&self, p: &[f64; 3]) -> f64 {
+        let logs: Vec<f64> = self
+            .components
+            .iter()
+            .map(|g| {
+                let mut log = g.weight.ln();
+                for ((value, mean), variance) in p.iter().zip(&g.mean).zip(&g.variance) {
+                    log -= 0.5 * ((2.0 * std::f64::consts::PI * variance).ln()
+                        + (value - mean).powi(2) / variance);
+                }
+                log
+            })
+            .collect();
+        let max = logs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        max + logs.iter().map(|l| (l - max).exp()).sum::<f64>().ln()
+    }
+}
+
+/// 分割框内的前景
+/// rect: [x_min, y_min, x_max, y_max]（图片像素坐标）
+/// 返回 (区域掩码, 区域左上角x, 区域左上角y)，框无效时返回None
+pub fn grabcut(image: &DynamicImage, rect: [f64; 4], iterations: usize) -> Option<(BinaryMask, u32, u32)> {
+    let (img_width, img_height) = image.dimensions();
+    let [x_min, y_min, x_max, y_max] = rect;
+    if x_max - x_min < 2.0 || y_max - y_min < 2.0 {
+        return None;
+    }
+
+    // 处理区域：框向外扩展一圈作为背景样本
+    let margin = ((x_max - x_min).max(y_max - y_min) * 0.1).max(5.0);
+    let rx0 = (x_min - margin).max(0.0).floor() as u32;
+    let ry0 = (y_min - margin).max(0.0).floor() as u32;
+    let rx1 = ((x_max + margin).ceil().max(0.0) as u32).min(img_width);
+    let ry1 = ((y_max + margin).ceil().max(0.0) as u32).min(img_height);
+    if rx1 <= rx0 + 1 || ry1 <= ry0 + 1 {
+        return None;
+    }
+    let (region_width, region_height) = (rx1 - rx0, ry1 - ry0);
+
+    // 较大的区域先缩小
+    let scale = (MAX_SIDE as f64 / region_width.max(region_height) as f64).min(1.0);
+    let width = ((region_width as f64 * scale).round() as u32).max(2);
+    let height = ((region_height as f64 * scale).round() as u32).max(2);
+    let region = image
+        .crop_imm(rx0, ry0, region_width, region_height)
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_rgb8();
+    let pixels: Vec<[f64; 3]> = region
+        .pixels()
+        .map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+        .collect();
+
+    // 框在缩小后区域中的位置
+    let bx0 = (((x_min - rx0 as f64) * scale).floor().max(0.0) as u32).min(width - 1);
+    let by0 = (((y_min - ry0 as f64) * scale).floor().max(0.0) as u32).min(height - 1);
+    let bx1 = (((x_max - rx0 as f64) * scale).ceil() as u32).clamp(bx0 + 1, width);
+    let by1 = (((y_max - ry0 as f64) * scale).ceil() as u32).clamp(by0 + 1, height);
+    let inside = |x: u32, y: u32| x >= bx0 && x < bx1 && y >= by0 && y < by1;
+
+    // 框外像素固定为背景；框贴着图片边缘没有框外像素时，用框的边缘像素作为背景
+    let mut fixed_background: Vec<bool> = (0..pixels.len() as u32)
+        .map(|i| !inside(i % width, i / width))
+        .collect();
+    if !fixed_background.iter().any(|v| *v) {
+        for (i, fixed) in fixed_background.iter_mut().enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            *fixed = x == bx0 || y == by0 || x + 1 == bx1 || y + 1 == by1;
+        }
+    }
+
+    let mut labels: Vec<bool> = fixed_background.iter().map(|fixed| !fixed).collect();
+    let mut previous_foreground = usize::MAX;
+
+    for _ in 0..iterations.max(1) {
+        let foreground: Vec<[f64; 3]> = pixels
+            .iter()
+            .zip(&labels)
+            .filter(|(_, l)| **l)
+            .map(|(p, _)| *p)
+            .collect();
+        let background: Vec<[f64; 3]> = pixels
+            .iter()
+            .zip(&labels)
+            .filter(|(_, l)| !**l)
+            .map(|(p, _)| *p)
+            .collect();
+        // 前景不再变化时提前结束
+        if foreground.len() == previous_foreground {
+            break;
+        }
+        previous_foreground = foreground.len();
+
+        let (Some(fg_model), Some(bg_model)) =
+            (ColorModel::fit(&foreground), ColorModel::fit(&background))
+        else {
+            break;
+        };
+
+        // 数据项：前景代价 - 背景代价
+        let data_cost: Vec<f64> = pixels
+            .iter()
+            .map(|p| bg_model.log_likelihood(p) - fg_model.log_likelihood(p))
+            .collect();
+
+        // ICM：逐像素选择数据项加平滑项更小的标签
+        for _ in 0..3 {
+            let mut changed = false;
+            for y in 0..height {
+                for x in 0..width {
+                    let index = (y * width + x) as usize;
+                    if fixed_background[index] {
+                        continue;
+                    }
+                    let mut foreground_neighbors = 0;
+                    let mut neighbors = 0;
+                    for (dx, dy) in [(-1i64, 0i64), (1, 0), (0, -1), (0, 1)] {
+                        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                        if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                            continue;
+                        }
+                        neighbors += 1;
+                        if labels[(ny as u32 * width + nx as u32) as usize] {
+                            foreground_neighbors += 1;
+                        }
+                    }
+                    let foreground_cost = data_cost[index]
+                        + SMOOTHNESS * (neighbors - foreground_neighbors) as f64;
+                    let background_cost = SMOOTHNESS * foreground_neighbors as f64;
+                    let label = foreground_cost < background_cost;
+                    if label != labels[index] {
+                        labels[index] = label;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    // 放大回原区域尺寸
+    let mut mask = BinaryMask::new(region_width, region_height);
+    for y in 0..region_height {
+        for x in 0..region_width {
+            let sx = ((x as f64 + 0.5) * scale) as u32;
+            let sy = ((y as f64 + 0.5) * scale) as u32;
+            if labels[(sy.min(height - 1) * width + sx.min(width - 1)) as usize] {
+                mask.set(x, y, true);
+            }
+        }
+    }
+
+    Some((mask, rx0, ry0))
+}
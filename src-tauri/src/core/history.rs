@@ -0,0 +1,170 @@
+//! 标注编辑记录
+//!
+//! 保存时与已有标注按ID对比：保留标注文件和每个标注的创建时间，为修改过的标注记录修改时间和修改者。
+//! 启用变更日志时，每次保存在标注文件的history中追加新增、修改、删除和标签变化记录（只追加不改写）
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use super::annotation::load_annotation_file;
+use crate::error::AppError;
+use crate::models::{AnnotationData, AnnotationFile, ChangeRecord};
+
+// 当前修改者，默认为系统用户名
+lazy_static::lazy_static! {
+    static ref AUTHOR: RwLock<String> = RwLock::new(
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_default()
+    );
+}
+
+// 是否记录变更日志
+static CHANGE_LOG_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 设置是否记录变更日志
+pub fn set_change_log(enabled: bool) {
+    CHANGE_LOG_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// 编辑记录中不参与内容比较的字段
+const RECORD_FIELDS: &[&str] = &["created", "modified", "author"];
+
+/// 两个标注中内容不同的字段（不含编辑记录字段）
+fn changed_fields(old: &AnnotationData, new: &AnnotationData) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    old.keys()
+        .chain(new.keys())
+        .filter(|key| !RECORD_FIELDS.contains(&key.as_str()))
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// 对比已有标注文件，补全要保存的标注文件中的编辑记录
+pub fn record_edits(existing: Option<&AnnotationFile>, annotation_file: &mut AnnotationFile) {
+    let now = chrono::Utc::now().to_rfc3339();
+    let author = AUTHOR.read().unwrap().clone();
+    let record = |action: &str, annotation_id: Option<&str>, label: &str, fields: Vec<String>| {
+        ChangeRecord {
+            timestamp: now.clone(),
+            author: author.clone(),
+            action: action.to_string(),
+            annotation_id: annotation_id.map(|id| id.to_string()),
+            label: label.to_string(),
+            fields,
+        }
+    };
+
+    let previous: HashMap<&str, &AnnotationData> = existing
+        .map(|file| {
+            file.annotations
+                .iter()
+                .map(|annotation| (annotation.id.as_str(), annotation))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut changes = Vec::new();
+    for annotation in &mut annotation_file.annotations {
+        match previous.get(annotation.id.as_str()) {
+            Some(old) => {
+                let fields = changed_fields(old, annotation);
+                if !old.created.is_empty() {
+                    annotation.created = old.created.clone();
+                }
+                if fields.is_empty() {
+                    annotation.modified = old.modified.clone();
+                    annotation.author = old.author.clone();
+                } else {
+                    annotation.modified = now.clone();
+                    annotation.author = author.clone();
+                    changes.push(record(
+                        "update",
+                        Some(&annotation.id),
+                        &annotation.label,
+                        fields,
+                    ));
+                }
+            }
+            None => {
+                if annotation.created.is_empty() {
+                    annotation.created = now.clone();
+                }
+                annotation.modified = now.clone();
+                annotation.author = author.clone();
+                changes.push(record(
+                    "create",
+                    Some(&annotation.id),
+                    &annotation.label,
+                    Vec::new(),
+                ));
+            }
+        }
+    }
+
+    if let Some(existing) = existing {
+        let current: BTreeSet<&str> = annotation_file
+            .annotations
+            .iter()
+            .map(|annotation| annotation.id.as_str())
+            .collect();
+        for old in &existing.annotations {
+            if !current.contains(old.id.as_str()) {
+                changes.push(record("delete", Some(&old.id), &old.label, Vec::new()));
+            }
+        }
+
+        let old_tags = serde_json::to_value(&existing.tags).ok();
+        if old_tags != serde_json::to_value(&annotation_file.tags).ok() {
+            changes.push(record("tags", None, "", Vec::new()));
+        }
+
+        // 创建时间和已有的变更日志以磁盘上的文件为准
+        if !existing.created.is_empty() {
+            annotation_file.created = existing.created.clone();
+        }
+        annotation_file.history = existing.history.clone();
+    }
+
+    annotation_file.modified = now;
+    if CHANGE_LOG_ENABLED.load(Ordering::Relaxed) {
+        annotation_file.history.extend(changes);
+    }
+}
+
+/// 获取当前修改者
+#[tauri::command]
+pub async fn get_annotation_author() -> Result<String, AppError> {
+    Ok(AUTHOR.read().unwrap().clone())
+}
+
+/// 设置当前修改者（记录在之后修改的标注和变更日志中）
+#[tauri::command]
+pub async fn set_annotation_author(author: String) -> Result<(), AppError> {
+    *AUTHOR.write().unwrap() = author.trim().to_string();
+    Ok(())
+}
+
+/// 设置是否记录变更日志
+#[tauri::command]
+pub async fn set_change_log_enabled(enabled: bool) -> Result<(), AppError> {
+    set_change_log(enabled);
+    Ok(())
+}
+
+/// 读取图片的变更日志（按时间顺序）
+#[tauri::command]
+pub async fn load_annotation_history(image_path: String) -> Result<Vec<ChangeRecord>, AppError> {
+    Ok(load_annotation_file(&image_path)?
+        .map(|file| file.history)
+        .unwrap_or_default())
+}
@@ -1,5 +1,5 @@
 use base64::{engine::general_purpose, Engine as _};
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView};
 use std::path::Path;
 
 use crate::error::AppError;
@@ -44,3 +44,26 @@ pub async fn load_image(path: String) -> Result<ImageData, AppError> {
         file_path: path,
     })
 }
+
+/// 图片的感知哈希（dHash，16位十六进制）：缩小为9x8灰度图后比较相邻像素，
+/// 同一图片重新编码或缩放后哈希相同或只差几位
+pub fn perceptual_hash(img: &DynamicImage) -> String {
+    let small = img.thumbnail_exact(9, 8).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// 两个感知哈希不同的位数，格式无效时为None
+pub fn hash_distance(a: &str, b: &str) -> Option<u32> {
+    let a = u64::from_str_radix(a, 16).ok()?;
+    let b = u64::from_str_radix(b, 16).ok()?;
+    Some((a ^ b).count_ones())
+}
@@ -1,7 +1,18 @@
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::core::annotation::{load_annotation_file, save_annotation_file};
+use crate::core::attributes::{current_schema, set_schema as set_attribute_schema};
+use crate::core::project::{
+    apply_project_state, is_open_project, load_project, write_project, DatasetScope,
+};
+use crate::core::scan::{scan_images, ScanOptions};
+use crate::core::taxonomy::{
+    alias_map, get_taxonomy_file_path, load_folder_classes, write_folder_classes, ClassDefinition,
+};
 use crate::error::AppError;
+use crate::utils::write_file_atomic;
 
 /// 获取 classes.txt 文件路径
 pub fn get_classes_file_path(image_folder: &str) -> PathBuf {
@@ -19,21 +30,25 @@ pub async fn read_classes_file(image_folder: String) -> Result<Vec<String>, AppE
         return Ok(Vec::new());
     }
     
-    let content = fs::read_to_string(&classes_path)?;
-    
-    // 按行分割，过滤空行和空白行
+    read_label_lines(&classes_path)
+}
+
+/// 读取标签文本文件（每行一个标签，过滤空行和空白行）
+pub fn read_label_lines(path: &Path) -> Result<Vec<String>, AppError> {
+    let content = fs::read_to_string(path)?;
+
     let labels: Vec<String> = content
         .lines()
         .map(|line| line.trim())
         .filter(|line| !line.is_empty())
         .map(|line| line.to_string())
         .collect();
-    
+
     Ok(labels)
 }
 
 /// 写入 classes.txt 文件
-/// 每行一个标签
+/// 每行一个标签；存在 .oria-classes.json 时改为更新类别体系（见 write_taxonomy_labels）
 #[tauri::command]
 pub async fn write_classes_file(
     image_folder: String,
@@ -47,6 +62,10 @@ pub async fn write_classes_file(
         .map(|label| label.trim().to_string())
         .filter(|label| !label.is_empty())
         .collect();
+
+    if get_taxonomy_file_path(&image_folder).exists() {
+        return write_taxonomy_labels(&image_folder, &unique_labels);
+    }
     
     // 去重但保持顺序
     unique_labels.sort();
@@ -59,8 +78,30 @@ pub async fn write_classes_file(
     Ok(())
 }
 
+/// 按标签列表更新 .oria-classes.json：保留名称或别名仍在列表中的类别（ID和其他属性不变），
+/// 去掉其余类别，新标签按列表顺序以最大ID加1追加
+fn write_taxonomy_labels(image_folder: &str, labels: &[String]) -> Result<(), AppError> {
+    let classes = load_folder_classes(image_folder)?;
+    let mut next_id = classes.iter().map(|c| c.id + 1).max().unwrap_or(0);
+    let mut updated: Vec<ClassDefinition> = classes
+        .into_iter()
+        .filter(|class| {
+            std::iter::once(&class.name)
+                .chain(&class.aliases)
+                .any(|name| labels.contains(name))
+        })
+        .collect();
+    for label in labels {
+        if !alias_map(&updated).contains_key(label) {
+            updated.push(ClassDefinition::new(next_id, label.clone()));
+            next_id += 1;
+        }
+    }
+    write_folder_classes(image_folder, &updated)
+}
+
 /// 追加标签到 classes.txt
-/// 如果标签已存在，不会重复添加
+/// 如果标签已存在，不会重复添加；存在 .oria-classes.json 时以最大ID加1追加类别
 #[tauri::command]
 pub async fn append_class_label(
     image_folder: String,
@@ -70,6 +111,16 @@ pub async fn append_class_label(
     if label.is_empty() {
         return Ok(());
     }
+
+    if get_taxonomy_file_path(&image_folder).exists() {
+        let mut classes = load_folder_classes(&image_folder)?;
+        if !alias_map(&classes).contains_key(label) {
+            let id = classes.iter().map(|c| c.id + 1).max().unwrap_or(0);
+            classes.push(ClassDefinition::new(id, label.to_string()));
+            write_folder_classes(&image_folder, &classes)?;
+        }
+        return Ok(());
+    }
     
     // 读取现有标签
     let mut labels = read_classes_file(image_folder.clone()).await?;
@@ -86,8 +137,12 @@ pub async fn append_class_label(
 
 /// 从文件夹中的所有 JSON 文件提取标签
 /// 用于初始化或重建 classes.txt
+/// options: 扫描选项，递归扫描时包含子目录中的标注
 #[tauri::command]
-pub async fn extract_labels_from_folder(image_folder: String) -> Result<Vec<String>, AppError> {
+pub async fn extract_labels_from_folder(
+    image_folder: String,
+    options: Option<ScanOptions>,
+) -> Result<Vec<String>, AppError> {
     use std::collections::HashSet;
     use crate::core::annotation::load_annotations_internal;
     
@@ -100,37 +155,14 @@ pub async fn extract_labels_from_folder(image_folder: String) -> Result<Vec<Stri
     
     let mut all_labels = HashSet::new();
     
-    // 遍历文件夹中的所有文件
-    let entries = fs::read_dir(folder_path)?;
-    
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        
-        // 只处理 JSON 文件
-        if path.extension().and_then(|s| s.to_str()) == Some("json") {
-            // 尝试找到对应的图片文件
-            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                // 尝试常见的图片扩展名
-                let image_extensions = ["jpg", "jpeg", "png", "bmp", "webp", "gif"];
-                
-                for ext in &image_extensions {
-                    let image_path = folder_path.join(format!("{}.{}", stem, ext));
-                    if image_path.exists() {
-                        // 加载标注数据
-                        if let Ok(annotations) = load_annotations_internal(
-                            image_path.to_str().unwrap_or("")
-                        ) {
-                            // 提取标签
-                            for annotation in annotations {
-                                let label = annotation.label.trim();
-                                if !label.is_empty() {
-                                    all_labels.insert(label.to_string());
-                                }
-                            }
-                        }
-                        break;
-                    }
+    // 遍历文件夹中的所有图片，读取对应的 JSON 标注
+    for image_path in scan_images(folder_path, &options.unwrap_or_default())? {
+        if let Ok(annotations) = load_annotations_internal(image_path.to_str().unwrap_or("")) {
+            // 提取标签
+            for annotation in annotations {
+                let label = annotation.label.trim();
+                if !label.is_empty() {
+                    all_labels.insert(label.to_string());
                 }
             }
         }
@@ -149,3 +181,386 @@ pub async fn classes_file_exists(image_folder: String) -> Result<bool, AppError>
     let classes_path = get_classes_file_path(&image_folder);
     Ok(classes_path.exists())
 }
+
+/// 数据集范围内的标签修改
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LabelOperation {
+    /// 重命名标签
+    Rename { from: String, to: String },
+    /// 把多个标签合并为一个
+    Merge { sources: Vec<String>, target: String },
+    /// 删除标签：reassign_to为空时删除该标签的标注，否则改为该标签
+    Delete {
+        label: String,
+        #[serde(default)]
+        reassign_to: Option<String>,
+    },
+}
+
+/// 单个标签的处理方式
+enum LabelChange {
+    Keep,
+    Relabel(String),
+    Remove,
+}
+
+impl LabelOperation {
+    fn change(&self, label: &str) -> LabelChange {
+        match self {
+            LabelOperation::Rename { from, to } if label == from => LabelChange::Relabel(to.clone()),
+            LabelOperation::Merge { sources, target }
+                if label != target && sources.iter().any(|source| source == label) =>
+            {
+                LabelChange::Relabel(target.clone())
+            }
+            LabelOperation::Delete { label: deleted, reassign_to } if label == deleted => {
+                match reassign_to {
+                    Some(target) => LabelChange::Relabel(target.clone()),
+                    None => LabelChange::Remove,
+                }
+            }
+            _ => LabelChange::Keep,
+        }
+    }
+
+    /// 检查操作参数
+    fn validate(&self) -> Result<(), AppError> {
+        let invalid = match self {
+            LabelOperation::Rename { from, to } => {
+                from.trim().is_empty() || to.trim().is_empty() || from == to
+            }
+            LabelOperation::Merge { sources, target } => {
+                sources.is_empty() || target.trim().is_empty()
+            }
+            LabelOperation::Delete { label, reassign_to } => {
+                label.trim().is_empty()
+                    || reassign_to
+                        .as_ref()
+                        .is_some_and(|target| target.trim().is_empty() || target == label)
+            }
+        };
+        if invalid {
+            return Err(AppError {
+                message: "标签操作参数无效".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// 应用到标签列表（classes.txt 或项目类别），保持原有顺序，目标标签已存在时不重复添加
+    fn apply_to_list(&self, labels: &[String]) -> Vec<String> {
+        let mut result: Vec<String> = Vec::new();
+        for label in labels {
+            let label = match self.change(label) {
+                LabelChange::Keep => label.clone(),
+                LabelChange::Relabel(target) => target,
+                LabelChange::Remove => continue,
+            };
+            if !result.contains(&label) {
+                result.push(label);
+            }
+        }
+        result
+    }
+
+    /// 应用到类别定义（.oria-classes.json 或项目类别），同时修改上级类别；
+    /// 合并时目标类别已存在则去掉来源类别
+    fn apply_to_classes(&self, classes: &[ClassDefinition]) -> Vec<ClassDefinition> {
+        let mut result: Vec<ClassDefinition> = Vec::new();
+        for class in classes {
+            let mut class = match self.change(&class.name) {
+                LabelChange::Keep => class.clone(),
+                LabelChange::Relabel(target) => {
+                    if classes.iter().chain(&result).any(|c| c.name == target) {
+                        continue;
+                    }
+                    ClassDefinition {
+                        name: target,
+                        ..class.clone()
+                    }
+                }
+                LabelChange::Remove => continue,
+            };
+            class.supercategory = class
+                .supercategory
+                .and_then(|parent| match self.change(&parent) {
+                    LabelChange::Keep => Some(parent),
+                    LabelChange::Relabel(target) => Some(target),
+                    LabelChange::Remove => None,
+                });
+            result.push(class);
+        }
+        result
+    }
+}
+
+/// 标签修改结果
+#[derive(Debug, Serialize)]
+pub struct LabelOperationResult {
+    pub dry_run: bool,
+    pub image_count: usize,           // 扫描的图片数
+    pub affected_files: Vec<String>,  // 有标注被修改的图片
+    pub relabeled_count: usize,       // 改为新标签的标注数
+    pub removed_count: usize,         // 删除的标注数
+    pub classes_before: Vec<String>,  // 类别文件修改前
+    pub classes_after: Vec<String>,   // 类别文件修改后
+    pub errors: Vec<String>,
+}
+
+/// 在文件夹或项目的所有标注中重命名、合并或删除标签，并同步修改 .oria-classes.json、classes.txt 和项目类别
+/// 先读取范围内的所有标注文件，有文件无法读取（损坏或来自更新的版本）时不写入任何文件，errors中列出这些文件；
+/// 写入时个别文件保存失败仍会修改类别文件和项目，使其与已保存的标注一致，失败的文件同样列在errors中。
+/// dry_run为true时只统计受影响的文件和标注数，不写入
+#[tauri::command]
+pub async fn apply_label_operation(
+    scope: DatasetScope,
+    operation: LabelOperation,
+    dry_run: Option<bool>,
+) -> Result<LabelOperationResult, AppError> {
+    operation.validate()?;
+    let dry_run = dry_run.unwrap_or(false);
+    let images = scope.images()?;
+
+    let mut result = LabelOperationResult {
+        dry_run,
+        image_count: images.len(),
+        affected_files: Vec::new(),
+        relabeled_count: 0,
+        removed_count: 0,
+        classes_before: Vec::new(),
+        classes_after: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    // 先读取并修改所有标注，不写入
+    let mut changed = Vec::new();
+    for image_path in images {
+        let image_path = image_path.to_string_lossy().to_string();
+        let mut annotation_file = match load_annotation_file(&image_path) {
+            Ok(Some(file)) => file,
+            Ok(None) => continue,
+            Err(e) => {
+                result.errors.push(format!("{}: {}", image_path, e.message));
+                continue;
+            }
+        };
+
+        let mut relabeled = 0;
+        let mut removed = 0;
+        annotation_file.annotations.retain_mut(|annotation| {
+            match operation.change(&annotation.label) {
+                LabelChange::Keep => true,
+                LabelChange::Relabel(target) => {
+                    annotation.label = target;
+                    relabeled += 1;
+                    true
+                }
+                LabelChange::Remove => {
+                    removed += 1;
+                    false
+                }
+            }
+        });
+        if relabeled > 0 || removed > 0 {
+            changed.push((image_path, annotation_file, relabeled, removed));
+        }
+    }
+    if !dry_run && !result.errors.is_empty() {
+        return Ok(result);
+    }
+
+    // 修改属性适用的类别，保存标注时按新的类别名称检查属性；
+    // 项目范围时修改项目中的属性定义，只有该项目是当前打开的项目时才切换全局的属性定义
+    let mut project = None;
+    if !dry_run {
+        match &scope {
+            DatasetScope::Project { project_path } => {
+                let project_path = Path::new(project_path);
+                let mut manifest = load_project(project_path)?;
+                manifest.classes = operation.apply_to_classes(&manifest.classes);
+                for attribute in &mut manifest.attributes {
+                    attribute.classes = operation.apply_to_list(&attribute.classes);
+                }
+                if is_open_project(project_path) {
+                    set_attribute_schema(manifest.attributes.clone());
+                }
+                project = Some((project_path, manifest));
+            }
+            _ => {
+                let mut schema = current_schema();
+                for attribute in &mut schema {
+                    attribute.classes = operation.apply_to_list(&attribute.classes);
+                }
+                set_attribute_schema(schema);
+            }
+        }
+    }
+
+    for (image_path, annotation_file, relabeled, removed) in changed {
+        if !dry_run {
+            if let Err(e) = save_annotation_file(&image_path, &annotation_file) {
+                result.errors.push(format!("{}: {}", image_path, e.message));
+                continue;
+            }
+        }
+        result.affected_files.push(image_path);
+        result.relabeled_count += relabeled;
+        result.removed_count += removed;
+    }
+    let write_classes = !dry_run;
+
+    // .oria-classes.json（同时写出 classes.txt）或 classes.txt
+    if let Some(folder) = scope.classes_folder()? {
+        let folder = folder.to_string_lossy().to_string();
+        let classes_path = get_classes_file_path(&folder);
+        if get_taxonomy_file_path(&folder).exists() {
+            let classes = load_folder_classes(&folder)?;
+            let updated = operation.apply_to_classes(&classes);
+            result.classes_before = classes.into_iter().map(|c| c.name).collect();
+            result.classes_after = updated.iter().map(|c| c.name.clone()).collect();
+            if write_classes && result.classes_after != result.classes_before {
+                write_folder_classes(&folder, &updated)?;
+            }
+        } else if classes_path.exists() {
+            result.classes_before = read_label_lines(&classes_path)?;
+            result.classes_after = operation.apply_to_list(&result.classes_before);
+            if write_classes && result.classes_after != result.classes_before {
+                write_file_atomic(&classes_path, result.classes_after.join("\n").as_bytes(), false)?;
+            }
+        }
+    }
+
+    // 项目类别和属性适用的类别
+    if let Some((project_path, manifest)) = project {
+        write_project(project_path, &manifest)?;
+        if is_open_project(project_path) {
+            apply_project_state(project_path, &manifest);
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_classes_keeps_taxonomy_ids() {
+        let dir = std::env::temp_dir().join(format!("oria-labels-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let folder = dir.to_string_lossy().to_string();
+        let mut car = ClassDefinition::new(3, "car".to_string());
+        car.aliases = vec!["automobile".to_string()];
+        write_folder_classes(
+            &folder,
+            &[
+                car,
+                ClassDefinition::new(5, "truck".to_string()),
+                ClassDefinition::new(7, "bus".to_string()),
+            ],
+        )
+        .unwrap();
+
+        let labels = ["person", "automobile", "truck", "person"]
+            .map(String::from)
+            .to_vec();
+        tauri::async_runtime::block_on(write_classes_file(folder.clone(), labels)).unwrap();
+
+        let classes = load_folder_classes(&folder).unwrap();
+        let ids: Vec<(u32, &str)> = classes.iter().map(|c| (c.id, c.name.as_str())).collect();
+        assert_eq!(ids, [(3, "car"), (5, "truck"), (8, "person")]);
+        assert_eq!(classes[0].aliases, ["automobile"]);
+        assert_eq!(
+            read_label_lines(&get_classes_file_path(&folder)).unwrap(),
+            ["car", "truck", "person"]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// 文件夹中的一张图片及其标注（标签为label）
+    fn labeled_image(dir: &Path, name: &str, label: &str) -> String {
+        let image_path = dir.join(format!("{}.jpg", name));
+        std::fs::write(&image_path, b"").unwrap();
+        let annotation: crate::models::AnnotationData = serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::new_v4().to_string(),
+            "type": "rectangle",
+            "x": 1.0,
+            "y": 1.0,
+            "width": 4.0,
+            "height": 4.0,
+            "label": label,
+        }))
+        .unwrap();
+        let mut file = crate::models::AnnotationFile::new(format!("{}.jpg", name), 10, 10);
+        file.annotations.push(annotation);
+        let image_path = image_path.to_string_lossy().to_string();
+        save_annotation_file(&image_path, &file).unwrap();
+        image_path
+    }
+
+    fn rename_car(dir: &Path) -> LabelOperationResult {
+        let scope = DatasetScope::Folder {
+            path: dir.to_string_lossy().to_string(),
+            options: ScanOptions::default(),
+        };
+        let operation = LabelOperation::Rename {
+            from: "car".to_string(),
+            to: "vehicle".to_string(),
+        };
+        tauri::async_runtime::block_on(apply_label_operation(scope, operation, None)).unwrap()
+    }
+
+    fn labels_of(image_path: &str) -> Vec<String> {
+        load_annotation_file(image_path)
+            .unwrap()
+            .unwrap()
+            .annotations
+            .into_iter()
+            .map(|a| a.label)
+            .collect()
+    }
+
+    #[test]
+    fn label_operation_updates_annotations_and_classes() {
+        let dir = std::env::temp_dir().join(format!("oria-labels-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = labeled_image(&dir, "a", "car");
+        let b = labeled_image(&dir, "b", "person");
+        let classes_path = get_classes_file_path(&dir.to_string_lossy());
+        std::fs::write(&classes_path, "person\ncar").unwrap();
+
+        let result = rename_car(&dir);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.affected_files, vec![a.clone()]);
+        assert_eq!(labels_of(&a), ["vehicle"]);
+        assert_eq!(labels_of(&b), ["person"]);
+        assert_eq!(
+            read_label_lines(&classes_path).unwrap(),
+            ["person", "vehicle"]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_annotation_aborts_label_operation() {
+        let dir = std::env::temp_dir().join(format!("oria-labels-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = labeled_image(&dir, "a", "car");
+        std::fs::write(dir.join("b.jpg"), b"").unwrap();
+        std::fs::write(dir.join("b.json"), "{ not json").unwrap();
+        let classes_path = get_classes_file_path(&dir.to_string_lossy());
+        std::fs::write(&classes_path, "car").unwrap();
+
+        let result = rename_car(&dir);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.affected_files.is_empty());
+        assert_eq!(labels_of(&a), ["car"]);
+        assert_eq!(read_label_lines(&classes_path).unwrap(), ["car"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,651 @@
+//! 数据集检查
+//!
+//! 检查文件夹或项目中的标注问题：尺寸无效或超出图片范围的标注、同类别的重复标注、未定义的类别、
+//! 标注文件记录的图片尺寸与图片不一致、孤立标注、标注数异常多的图片以及无法读取的文件。
+//! 可以安全修复的问题（裁剪到图片范围、删除无效或完全重复的标注、别名改为类别名称、补全图片尺寸）
+//! 修复时按当前文件重新检查后应用
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::annotation::{load_annotation_file, save_annotation_file};
+use super::geometry::{annotation_bounds, bounds_iou, polygon_area};
+use super::orphans::find_orphan_annotations;
+use super::project::DatasetScope;
+use super::storage::current_storage;
+use super::taxonomy::alias_map;
+use crate::error::AppError;
+use crate::models::{AnnotationData, AnnotationFile};
+
+/// 超出图片范围的容差（像素）
+const BOUNDS_TOLERANCE: f64 = 1.0;
+
+/// 交并比不低于该值的重复标注视为完全重复，可以自动删除
+const EXACT_DUPLICATE_IOU: f64 = 0.99;
+
+/// 问题严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// 问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+    InvalidSize,
+    OutOfBounds,
+    DuplicateAnnotation,
+    UnknownLabel,
+    ImageSizeMismatch,
+    MissingImageSize,
+    TooManyAnnotations,
+    OrphanAnnotation,
+    UnreadableAnnotation,
+    UnreadableImage,
+}
+
+/// 检查选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintOptions {
+    /// 同类别标注的交并比不低于该值时视为重复
+    #[serde(default = "default_duplicate_iou")]
+    pub duplicate_iou: f64,
+    /// 单张图片的标注数超过该值时提示
+    #[serde(default = "default_max_annotations")]
+    pub max_annotations: usize,
+}
+
+fn default_duplicate_iou() -> f64 {
+    0.9
+}
+
+fn default_max_annotations() -> usize {
+    200
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions {
+            duplicate_iou: default_duplicate_iou(),
+            max_annotations: default_max_annotations(),
+        }
+    }
+}
+
+/// 自动修复操作
+#[derive(Debug, Clone)]
+enum LintFix {
+    /// 裁剪到图片范围（宽、高）
+    Clip(String, u32, u32),
+    /// 负的宽高改为正值（保持框的位置）
+    Normalize(String),
+    Remove(String),
+    Relabel(String, String),
+    SetImageSize(u32, u32),
+}
+
+/// 检查出的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub kind: IssueKind,
+    pub severity: Severity,
+    pub path: String, // 图片路径（孤立标注为标注文件路径）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation_id: Option<String>,
+    pub message: String,
+    pub fixable: bool,
+    #[serde(skip)]
+    fix: Option<LintFix>,
+}
+
+impl LintIssue {
+    fn new(
+        kind: IssueKind,
+        severity: Severity,
+        path: &str,
+        annotation_id: Option<&str>,
+        message: String,
+        fix: Option<LintFix>,
+    ) -> Self {
+        LintIssue {
+            kind,
+            severity,
+            path: path.to_string(),
+            annotation_id: annotation_id.map(|id| id.to_string()),
+            message,
+            fixable: fix.is_some(),
+            fix,
+        }
+    }
+}
+
+/// 检查结果
+#[derive(Debug, Serialize)]
+pub struct LintReport {
+    pub image_count: usize,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    pub fixable_count: usize,
+    pub issues: Vec<LintIssue>,
+}
+
+/// 修复结果
+#[derive(Debug, Serialize)]
+pub struct LintFixResult {
+    pub dry_run: bool,
+    pub fixed_count: usize,
+    pub affected_files: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// 检查标注尺寸，返回问题描述和修复操作
+fn check_size(annotation: &AnnotationData) -> Option<(String, Option<LintFix>)> {
+    let remove = Some(LintFix::Remove(annotation.id.clone()));
+    if annotation.annotation_type == "polygon" {
+        let points = annotation.points.as_deref().unwrap_or_default();
+        if points.len() < 3 {
+            return Some((format!("多边形只有 {} 个顶点", points.len()), remove));
+        }
+        if polygon_area(points) <= 0.0 {
+            return Some(("多边形面积为0".to_string(), remove));
+        }
+        return None;
+    }
+
+    let (width, height) = (annotation.width, annotation.height);
+    if !width.is_finite() || !height.is_finite() || width == 0.0 || height == 0.0 {
+        Some((format!("标注尺寸无效: {} x {}", width, height), remove))
+    } else if width < 0.0 || height < 0.0 {
+        Some((
+            format!("标注宽高为负: {} x {}", width, height),
+            Some(LintFix::Normalize(annotation.id.clone())),
+        ))
+    } else {
+        None
+    }
+}
+
+/// 检查一张图片的标注文件
+fn check_annotation_file(
+    image_path: &str,
+    annotation_file: &AnnotationFile,
+    image_size: Option<(u32, u32)>,
+    options: &LintOptions,
+    aliases: &HashMap<String, String>,
+) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |kind, severity, annotation_id: Option<&str>, message, fix| {
+        issues.push(LintIssue::new(
+            kind,
+            severity,
+            image_path,
+            annotation_id,
+            message,
+            fix,
+        ))
+    };
+
+    if let Some((width, height)) = image_size {
+        if annotation_file.image_width == 0 || annotation_file.image_height == 0 {
+            issue(
+                IssueKind::MissingImageSize,
+                Severity::Info,
+                None,
+                "标注文件没有记录图片尺寸".to_string(),
+                Some(LintFix::SetImageSize(width, height)),
+            );
+        } else if (annotation_file.image_width, annotation_file.image_height) != (width, height) {
+            issue(
+                IssueKind::ImageSizeMismatch,
+                Severity::Warning,
+                None,
+                format!(
+                    "标注文件记录的图片尺寸 {}x{} 与图片 {}x{} 不一致",
+                    annotation_file.image_width, annotation_file.image_height, width, height
+                ),
+                None,
+            );
+        }
+    }
+
+    let annotations = &annotation_file.annotations;
+    if annotations.len() > options.max_annotations {
+        issue(
+            IssueKind::TooManyAnnotations,
+            Severity::Warning,
+            None,
+            format!("图片有 {} 个标注", annotations.len()),
+            None,
+        );
+    }
+
+    let mut valid = Vec::new();
+    for annotation in annotations {
+        let id = Some(annotation.id.as_str());
+        if let Some((message, fix)) = check_size(annotation) {
+            issue(IssueKind::InvalidSize, Severity::Error, id, message, fix);
+            continue;
+        }
+        valid.push(annotation);
+
+        if let Some((width, height)) = image_size {
+            let (width, height) = (width as f64, height as f64);
+            let (x_min, y_min, x_max, y_max) = annotation_bounds(annotation);
+            if x_max <= 0.0 || y_max <= 0.0 || x_min >= width || y_min >= height {
+                issue(
+                    IssueKind::OutOfBounds,
+                    Severity::Error,
+                    id,
+                    "标注完全在图片外".to_string(),
+                    Some(LintFix::Remove(annotation.id.clone())),
+                );
+            } else if x_min < -BOUNDS_TOLERANCE
+                || y_min < -BOUNDS_TOLERANCE
+                || x_max > width + BOUNDS_TOLERANCE
+                || y_max > height + BOUNDS_TOLERANCE
+            {
+                // 旋转框裁剪后不再是矩形，不自动修复
+                let fix = (annotation.annotation_type != "rotated-rectangle")
+                    .then(|| LintFix::Clip(annotation.id.clone(), width as u32, height as u32));
+                issue(
+                    IssueKind::OutOfBounds,
+                    Severity::Warning,
+                    id,
+                    "标注超出图片范围".to_string(),
+                    fix,
+                );
+            }
+        }
+
+        if !aliases.is_empty() {
+            match aliases.get(&annotation.label) {
+                Some(name) if name != &annotation.label => issue(
+                    IssueKind::UnknownLabel,
+                    Severity::Warning,
+                    id,
+                    format!("标签 {} 是类别 {} 的别名", annotation.label, name),
+                    Some(LintFix::Relabel(annotation.id.clone(), name.clone())),
+                ),
+                Some(_) => {}
+                None => issue(
+                    IssueKind::UnknownLabel,
+                    Severity::Warning,
+                    id,
+                    format!("未定义的类别: {}", annotation.label),
+                    None,
+                ),
+            }
+        }
+    }
+
+    for (i, first) in valid.iter().enumerate() {
+        for second in &valid[i + 1..] {
+            if first.label != second.label {
+                continue;
+            }
+            let iou = bounds_iou(first, second);
+            if iou >= options.duplicate_iou {
+                issue(
+                    IssueKind::DuplicateAnnotation,
+                    Severity::Warning,
+                    Some(&second.id),
+                    format!("与标注 {} 重复（IoU {:.2}）", first.id, iou),
+                    (iou >= EXACT_DUPLICATE_IOU).then(|| LintFix::Remove(second.id.clone())),
+                );
+            }
+        }
+    }
+
+    issues
+}
+
+/// 读取并检查一张图片，返回标注文件（不存在或无法读取时为None）和问题列表
+fn check_image(
+    image_path: &str,
+    options: &LintOptions,
+    aliases: &HashMap<String, String>,
+) -> (Option<AnnotationFile>, Vec<LintIssue>) {
+    let annotation_file = match load_annotation_file(image_path) {
+        Ok(Some(annotation_file)) => annotation_file,
+        Ok(None) => return (None, Vec::new()),
+        Err(e) => {
+            let issue = LintIssue::new(
+                IssueKind::UnreadableAnnotation,
+                Severity::Error,
+                image_path,
+                None,
+                e.message,
+                None,
+            );
+            return (None, vec![issue]);
+        }
+    };
+
+    let mut issues = Vec::new();
+    let image_size = match image::image_dimensions(image_path) {
+        Ok(size) => Some(size),
+        Err(e) => {
+            issues.push(LintIssue::new(
+                IssueKind::UnreadableImage,
+                Severity::Error,
+                image_path,
+                None,
+                format!("无法读取图片: {}", e),
+                None,
+            ));
+            None
+        }
+    };
+    issues.extend(check_annotation_file(
+        image_path,
+        &annotation_file,
+        image_size,
+        options,
+        aliases,
+    ));
+    (Some(annotation_file), issues)
+}
+
+/// 把修复操作应用到标注文件，返回应用的数量
+fn apply_fixes(annotation_file: &mut AnnotationFile, fixes: &[LintFix]) -> usize {
+    let mut removed = HashSet::new();
+    let mut applied = 0;
+
+    for fix in fixes {
+        let id = match fix {
+            LintFix::SetImageSize(width, height) => {
+                annotation_file.image_width = *width;
+                annotation_file.image_height = *height;
+                applied += 1;
+                continue;
+            }
+            LintFix::Remove(id) => {
+                if removed.insert(id.clone()) {
+                    applied += 1;
+                }
+                continue;
+            }
+            LintFix::Clip(id, _, _) | LintFix::Normalize(id) | LintFix::Relabel(id, _) => id,
+        };
+        let Some(annotation) = annotation_file.annotations.iter_mut().find(|a| &a.id == id) else {
+            continue;
+        };
+        match fix {
+            LintFix::Clip(_, width, height) => {
+                clip_annotation(annotation, *width as f64, *height as f64)
+            }
+            LintFix::Normalize(_) => {
+                if annotation.width < 0.0 {
+                    annotation.x += annotation.width;
+                    annotation.width = -annotation.width;
+                }
+                if annotation.height < 0.0 {
+                    annotation.y += annotation.height;
+                    annotation.height = -annotation.height;
+                }
+            }
+            LintFix::Relabel(_, label) => annotation.label = label.clone(),
+            LintFix::SetImageSize(..) | LintFix::Remove(_) => {}
+        }
+        applied += 1;
+    }
+
+    annotation_file
+        .annotations
+        .retain(|annotation| !removed.contains(&annotation.id));
+    applied
+}
+
+/// 把矩形或多边形裁剪到图片范围内
+fn clip_annotation(annotation: &mut AnnotationData, width: f64, height: f64) {
+    if let Some(points) = annotation.points.as_mut().filter(|p| !p.is_empty()) {
+        for point in points.iter_mut() {
+            point[0] = point[0].clamp(0.0, width);
+            point[1] = point[1].clamp(0.0, height);
+        }
+        // 多边形的 x, y, width, height 为外接框
+        let (x_min, y_min, x_max, y_max) = annotation_bounds(annotation);
+        annotation.x = x_min;
+        annotation.y = y_min;
+        annotation.width = x_max - x_min;
+        annotation.height = y_max - y_min;
+        return;
+    }
+
+    let x_min = annotation.x.clamp(0.0, width);
+    let y_min = annotation.y.clamp(0.0, height);
+    let x_max = (annotation.x + annotation.width).clamp(0.0, width);
+    let y_max = (annotation.y + annotation.height).clamp(0.0, height);
+    annotation.x = x_min;
+    annotation.y = y_min;
+    annotation.width = x_max - x_min;
+    annotation.height = y_max - y_min;
+}
+
+/// 范围内类别名称和别名的映射（没有定义类别时为空，不检查类别）
+fn scope_aliases(scope: &DatasetScope) -> Result<HashMap<String, String>, AppError> {
+    Ok(alias_map(&scope.classes()?))
+}
+
+/// 检查文件夹或项目中的标注
+#[tauri::command]
+pub async fn lint_dataset(
+    scope: DatasetScope,
+    options: Option<LintOptions>,
+) -> Result<LintReport, AppError> {
+    let options = options.unwrap_or_default();
+    let aliases = scope_aliases(&scope)?;
+    let images = scope.images()?;
+
+    let mut issues = Vec::new();
+    for image_path in &images {
+        let (_, image_issues) = check_image(&image_path.to_string_lossy(), &options, &aliases);
+        issues.extend(image_issues);
+    }
+
+    for orphan in find_orphan_annotations(&current_storage(), &scope.folders()?)? {
+        issues.push(LintIssue::new(
+            IssueKind::OrphanAnnotation,
+            Severity::Warning,
+            &orphan.annotation_path,
+            None,
+            format!("标注对应的图片不存在: {}", orphan.image_path),
+            None,
+        ));
+    }
+
+    let count = |severity| issues.iter().filter(|i| i.severity == severity).count();
+    Ok(LintReport {
+        image_count: images.len(),
+        error_count: count(Severity::Error),
+        warning_count: count(Severity::Warning),
+        info_count: count(Severity::Info),
+        fixable_count: issues.iter().filter(|i| i.fixable).count(),
+        issues,
+    })
+}
+
+/// 自动修复可以安全修复的问题
+/// kinds: 只修复这些类型的问题，为空时修复所有可修复的问题；dry_run为true时只统计不写入
+#[tauri::command]
+pub async fn fix_lint_issues(
+    scope: DatasetScope,
+    options: Option<LintOptions>,
+    kinds: Option<Vec<IssueKind>>,
+    dry_run: Option<bool>,
+) -> Result<LintFixResult, AppError> {
+    let options = options.unwrap_or_default();
+    let kinds = kinds.unwrap_or_default();
+    let dry_run = dry_run.unwrap_or(false);
+    let aliases = scope_aliases(&scope)?;
+
+    let mut result = LintFixResult {
+        dry_run,
+        fixed_count: 0,
+        affected_files: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for image_path in scope.images()? {
+        let image_path = image_path.to_string_lossy().to_string();
+        let (Some(mut annotation_file), issues) = check_image(&image_path, &options, &aliases)
+        else {
+            continue;
+        };
+        let fixes: Vec<LintFix> = issues
+            .into_iter()
+            .filter(|issue| kinds.is_empty() || kinds.contains(&issue.kind))
+            .filter_map(|issue| issue.fix)
+            .collect();
+        if fixes.is_empty() {
+            continue;
+        }
+
+        let fixed = apply_fixes(&mut annotation_file, &fixes);
+        if !dry_run {
+            if let Err(e) = save_annotation_file(&image_path, &annotation_file) {
+                result.errors.push(format!("{}: {}", image_path, e.message));
+                continue;
+            }
+        }
+        result.fixed_count += fixed;
+        result.affected_files.push(image_path);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(id: &str, x: f64, y: f64, width: f64, height: f64) -> AnnotationData {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "type": "rectangle",
+            "x": x,
+            "y": y,
+            "width": width,
+            "height": height,
+            "label": "car",
+        }))
+        .unwrap()
+    }
+
+    /// 检查标注文件（图片100x50）并应用所有可自动修复的问题
+    fn check_and_fix(annotation_file: &mut AnnotationFile) -> (Vec<LintIssue>, usize) {
+        let issues = check_annotation_file(
+            "a.jpg",
+            annotation_file,
+            Some((100, 50)),
+            &LintOptions::default(),
+            &HashMap::new(),
+        );
+        let fixes: Vec<LintFix> = issues.iter().filter_map(|i| i.fix.clone()).collect();
+        let applied = apply_fixes(annotation_file, &fixes);
+        (issues, applied)
+    }
+
+    fn find<'a>(annotation_file: &'a AnnotationFile, id: &str) -> Option<&'a AnnotationData> {
+        annotation_file.annotations.iter().find(|a| a.id == id)
+    }
+
+    #[test]
+    fn fixes_clip_normalize_and_remove_exact_duplicates() {
+        let mut annotation_file = AnnotationFile::new("a.jpg".to_string(), 100, 50);
+        annotation_file.annotations = vec![
+            rect("clip", 90.0, -5.0, 20.0, 20.0),
+            rect("negative", 40.0, 30.0, -10.0, -20.0),
+            rect("original", 10.0, 10.0, 10.0, 10.0),
+            rect("duplicate", 10.0, 10.0, 10.0, 10.0),
+            rect("outside", 200.0, 10.0, 10.0, 10.0),
+            rect("empty", 5.0, 5.0, 0.0, 10.0),
+        ];
+
+        let (issues, applied) = check_and_fix(&mut annotation_file);
+        assert_eq!(applied, 5);
+        assert!(issues.iter().all(|issue| issue.fixable));
+
+        let clipped = find(&annotation_file, "clip").unwrap();
+        assert_eq!(
+            (clipped.x, clipped.y, clipped.width, clipped.height),
+            (90.0, 0.0, 10.0, 15.0)
+        );
+        let normalized = find(&annotation_file, "negative").unwrap();
+        assert_eq!(
+            (
+                normalized.x,
+                normalized.y,
+                normalized.width,
+                normalized.height
+            ),
+            (30.0, 10.0, 10.0, 20.0)
+        );
+        assert!(find(&annotation_file, "original").is_some());
+        assert!(find(&annotation_file, "duplicate").is_none());
+        assert!(find(&annotation_file, "outside").is_none());
+        assert!(find(&annotation_file, "empty").is_none());
+
+        // 修复后再次检查没有问题
+        let (issues, applied) = check_and_fix(&mut annotation_file);
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(applied, 0);
+    }
+
+    #[test]
+    fn near_duplicates_and_rotated_boxes_are_not_fixed() {
+        let mut rotated = rect("rotated", 90.0, 10.0, 20.0, 10.0);
+        rotated.annotation_type = "rotated-rectangle".to_string();
+        rotated.rotation = Some(0.3);
+        let mut annotation_file = AnnotationFile::new("a.jpg".to_string(), 100, 50);
+        annotation_file.annotations = vec![
+            rect("first", 0.0, 0.0, 20.0, 20.0),
+            rect("second", 0.0, 0.0, 20.0, 19.0),
+            rotated,
+        ];
+
+        let (issues, applied) = check_and_fix(&mut annotation_file);
+        let kinds: Vec<IssueKind> = issues.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            [IssueKind::OutOfBounds, IssueKind::DuplicateAnnotation]
+        );
+        assert!(issues.iter().all(|issue| !issue.fixable));
+        assert_eq!(applied, 0);
+        assert_eq!(annotation_file.annotations.len(), 3);
+    }
+
+    #[test]
+    fn polygon_is_clipped_to_image() {
+        let polygon: AnnotationData = serde_json::from_value(serde_json::json!({
+            "id": "polygon",
+            "type": "polygon",
+            "x": -10.0,
+            "y": 0.0,
+            "width": 60.0,
+            "height": 40.0,
+            "label": "car",
+            "points": [[-10.0, 0.0], [50.0, 0.0], [50.0, 40.0]],
+        }))
+        .unwrap();
+        let mut annotation_file = AnnotationFile::new("a.jpg".to_string(), 100, 50);
+        annotation_file.annotations = vec![polygon];
+
+        let (_, applied) = check_and_fix(&mut annotation_file);
+        assert_eq!(applied, 1);
+        let clipped = &annotation_file.annotations[0];
+        assert_eq!(
+            clipped.points.as_deref().unwrap(),
+            [[0.0, 0.0], [50.0, 0.0], [50.0, 40.0]]
+        );
+        assert_eq!(
+            (clipped.x, clipped.y, clipped.width, clipped.height),
+            (0.0, 0.0, 50.0, 40.0)
+        );
+    }
+}
@@ -1,8 +1,25 @@
 //! 核心功能模块
 //!
-//! 包含标注、缓存和图片处理等核心功能
+//! 包含项目、标注、标注存储、格式版本、编辑记录、标注属性、标注关系、缓存、图片处理、几何计算、标注细化、视频抽帧和目标跟踪等核心功能
 
 pub mod annotation;
+pub mod annotation_db;
+pub mod attributes;
 pub mod cache;
+pub mod geometry;
+pub mod grabcut;
+pub mod history;
 pub mod image;
 pub mod labels;
+pub mod lint;
+pub mod orphans;
+pub mod project;
+pub mod refine;
+pub mod relations;
+pub mod scan;
+pub mod schema;
+pub mod statistics;
+pub mod storage;
+pub mod taxonomy;
+pub mod tracking;
+pub mod video;
@@ -0,0 +1,707 @@
+//! 孤立标注
+//!
+//! 图片被删除、改名或移动后，标注文件（或SQLite中的标注记录）会留在原处。
+//! 按存储方式反推标注对应的图片路径，图片不存在的标注即为孤立标注。
+//! 孤立标注可以按文件名或感知哈希重新关联到没有标注的图片，也可以移到回收目录或删除
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::annotation::{
+    annotation_file_exists_in, load_annotation_file_from, save_annotation_file_to,
+};
+use super::annotation_db;
+use super::image::{hash_distance, perceptual_hash};
+use super::project::DatasetScope;
+use super::scan::ScanOptions;
+use super::schema::upgrade_annotation_file;
+use super::storage::{current_storage, AnnotationStorage};
+use crate::error::AppError;
+use crate::models::AnnotationFile;
+use crate::utils::{backup_file_path, is_image_file};
+
+/// 默认的感知哈希最大距离（64位中不同的位数）
+const DEFAULT_HASH_DISTANCE: u32 = 6;
+
+/// 孤立标注
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanAnnotation {
+    /// 标注文件路径（SQLite存储时为数据库路径）
+    pub annotation_path: String,
+    /// 标注原来对应的图片路径；同目录存储只知道文件名主干，为 <目录>/<文件名主干>
+    pub image_path: String,
+}
+
+/// 是否为标注JSON：标注数组的旧格式，或包含version、image_path和annotations的对象，且能升级为当前格式。
+/// 数据集文件夹中的COCO文件（如导出的annotations.json）等其他JSON不会被当作标注，也就不会被回收或删除
+fn is_annotation_json(path: &Path) -> bool {
+    let Some(value) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return false;
+    };
+    let sidecar_shape = value.is_array()
+        || ["version", "image_path", "annotations"]
+            .iter()
+            .all(|key| value.get(key).is_some());
+    sidecar_shape && upgrade_annotation_file(value, &path.to_string_lossy()).is_ok()
+}
+
+/// 文件夹中（按扫描选项决定是否递归）的JSON文件，跳过隐藏文件（如类别体系文件）和隐藏目录（如回收目录）
+fn json_files(folder: &Path, options: &ScanOptions) -> Vec<PathBuf> {
+    let mut walker = walkdir::WalkDir::new(folder)
+        .min_depth(1)
+        .follow_links(options.follow_symlinks);
+    if !options.recursive {
+        walker = walker.max_depth(1);
+    }
+    walker
+        .into_iter()
+        .filter_entry(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+        })
+        .collect()
+}
+
+/// 目录中图片的文件名主干
+fn image_stems(dir: &Path) -> HashSet<OsString> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| is_image_file(path))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_os_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 同目录下是否有文件名主干相同的图片；每个目录只读取一次，结果缓存在stems中
+fn has_sidecar_image(
+    annotation_path: &Path,
+    stems: &mut HashMap<PathBuf, HashSet<OsString>>,
+) -> bool {
+    let Some(stem) = annotation_path.file_stem() else {
+        return false;
+    };
+    let dir = annotation_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    stems
+        .entry(dir.to_path_buf())
+        .or_insert_with(|| image_stems(dir))
+        .contains(stem)
+}
+
+/// 去掉路径末尾的 .json
+fn strip_json_extension(path: &Path) -> PathBuf {
+    path.with_extension("")
+}
+
+/// 查找文件夹中的孤立标注
+pub fn find_orphan_annotations(
+    storage: &AnnotationStorage,
+    folders: &[(PathBuf, ScanOptions)],
+) -> Result<Vec<OrphanAnnotation>, AppError> {
+    let in_folders = |image: &Path| folders.iter().any(|(folder, _)| image.starts_with(folder));
+    let mut orphans = Vec::new();
+
+    match storage {
+        AnnotationStorage::Sidecar | AnnotationStorage::FullFilename => {
+            let mut stems = HashMap::new();
+            for (folder, options) in folders {
+                for path in json_files(folder, options) {
+                    let has_image = match storage {
+                        AnnotationStorage::Sidecar => has_sidecar_image(&path, &mut stems),
+                        _ => strip_json_extension(&path).is_file(),
+                    };
+                    if !has_image && is_annotation_json(&path) {
+                        orphans.push(OrphanAnnotation {
+                            annotation_path: path.to_string_lossy().to_string(),
+                            image_path: strip_json_extension(&path).to_string_lossy().to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        AnnotationStorage::Mirrored {
+            annotations_dir,
+            image_root,
+        } => {
+            let annotations_dir = Path::new(annotations_dir);
+            let options = ScanOptions {
+                recursive: true,
+                ..Default::default()
+            };
+            if annotations_dir.is_dir() {
+                for path in json_files(annotations_dir, &options) {
+                    let relative = path.strip_prefix(annotations_dir).unwrap_or(&path);
+                    let image = Path::new(image_root).join(strip_json_extension(relative));
+                    if in_folders(&image) && !image.is_file() && is_annotation_json(&path) {
+                        orphans.push(OrphanAnnotation {
+                            annotation_path: path.to_string_lossy().to_string(),
+                            image_path: image.to_string_lossy().to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        AnnotationStorage::Sqlite { database } => {
+            let database = Path::new(database);
+            if database.is_file() {
+                for image in annotation_db::image_paths(database)? {
+                    if in_folders(&image) && !image.is_file() {
+                        orphans.push(OrphanAnnotation {
+                            annotation_path: database.to_string_lossy().to_string(),
+                            image_path: image.to_string_lossy().to_string(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(orphans)
+}
+
+/// 读取孤立标注的内容
+fn load_orphan(
+    storage: &AnnotationStorage,
+    orphan: &OrphanAnnotation,
+) -> Result<AnnotationFile, AppError> {
+    let value = match storage {
+        AnnotationStorage::Sqlite { database } => {
+            annotation_db::load(Path::new(database), &orphan.image_path)?.ok_or_else(|| {
+                AppError {
+                    message: format!("标注记录不存在: {}", orphan.image_path),
+                }
+            })?
+        }
+        _ => serde_json::from_str(&std::fs::read_to_string(&orphan.annotation_path)?)?,
+    };
+    Ok(upgrade_annotation_file(value, &orphan.image_path)?.0)
+}
+
+/// 删除孤立标注（标注文件和备份，或SQLite记录）
+fn remove_orphan(storage: &AnnotationStorage, orphan: &OrphanAnnotation) -> Result<(), AppError> {
+    match storage {
+        AnnotationStorage::Sqlite { database } => {
+            annotation_db::remove(Path::new(database), &orphan.image_path)
+        }
+        _ => {
+            let path = PathBuf::from(&orphan.annotation_path);
+            for path in [backup_file_path(&path), path] {
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 标注中记录的文件名与图片实际文件名不一致的图片
+#[derive(Debug, Clone, Serialize)]
+pub struct MismatchedImage {
+    pub image_path: String,
+    pub stored_image_path: String,
+}
+
+/// 重新关联的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RelinkMethod {
+    Filename,
+    Hash,
+}
+
+/// 孤立标注可以重新关联的图片
+#[derive(Debug, Clone, Serialize)]
+pub struct RelinkCandidate {
+    pub orphan: OrphanAnnotation,
+    pub new_image_path: String,
+    pub method: RelinkMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<u32>, // 感知哈希距离
+}
+
+/// 核对结果
+#[derive(Debug, Serialize)]
+pub struct ReconcileReport {
+    pub orphans: Vec<OrphanAnnotation>,
+    pub mismatched: Vec<MismatchedImage>,
+    pub relinks: Vec<RelinkCandidate>, // 只包含唯一匹配的孤立标注
+    pub errors: Vec<String>,
+}
+
+/// 核对选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileOptions {
+    /// 是否按感知哈希匹配（需要读取所有没有标注的图片）
+    #[serde(default)]
+    pub match_hash: bool,
+    #[serde(default = "default_hash_distance")]
+    pub max_hash_distance: u32,
+}
+
+fn default_hash_distance() -> u32 {
+    DEFAULT_HASH_DISTANCE
+}
+
+impl Default for ReconcileOptions {
+    fn default() -> Self {
+        ReconcileOptions {
+            match_hash: false,
+            max_hash_distance: DEFAULT_HASH_DISTANCE,
+        }
+    }
+}
+
+/// 文件名（不含目录）
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 为孤立标注查找可以重新关联的图片：先按记录的文件名，再按文件名主干，
+/// 都没有唯一匹配时按感知哈希取距离最近且唯一的图片
+fn find_relink(
+    annotation_file: &AnnotationFile,
+    unannotated: &[PathBuf],
+    hashes: &mut HashMap<PathBuf, Option<String>>,
+    options: &ReconcileOptions,
+) -> Option<(PathBuf, RelinkMethod, Option<u32>)> {
+    let stored = Path::new(&annotation_file.image_path);
+    let unique = |matches: Vec<&PathBuf>| match matches.as_slice() {
+        [image] => Some((*image).clone()),
+        _ => None,
+    };
+
+    let by_name = unique(
+        unannotated
+            .iter()
+            .filter(|image| image.file_name() == stored.file_name())
+            .collect(),
+    );
+    let by_stem = || {
+        unique(
+            unannotated
+                .iter()
+                .filter(|image| image.file_stem() == stored.file_stem())
+                .collect(),
+        )
+    };
+    if let Some(image) = by_name.or_else(by_stem) {
+        return Some((image, RelinkMethod::Filename, None));
+    }
+
+    if !options.match_hash || annotation_file.image_hash.is_empty() {
+        return None;
+    }
+    let mut best: Vec<(u32, &PathBuf)> = unannotated
+        .iter()
+        .filter_map(|image| {
+            let hash = hashes
+                .entry(image.clone())
+                .or_insert_with(|| image::open(image).ok().map(|img| perceptual_hash(&img)));
+            hash.as_deref()
+                .and_then(|hash| hash_distance(hash, &annotation_file.image_hash))
+                .filter(|distance| *distance <= options.max_hash_distance)
+                .map(|distance| (distance, image))
+        })
+        .collect();
+    best.sort_by_key(|(distance, _)| *distance);
+    match best.as_slice() {
+        [(distance, image)] => Some(((*image).clone(), RelinkMethod::Hash, Some(*distance))),
+        [(first, image), (second, _), ..] if first < second => {
+            Some(((*image).clone(), RelinkMethod::Hash, Some(*first)))
+        }
+        _ => None,
+    }
+}
+
+/// 核对数据集范围内按指定存储方式保存的孤立标注、文件名不一致的图片和可以重新关联的图片
+fn reconcile(
+    storage: &AnnotationStorage,
+    scope: &DatasetScope,
+    options: &ReconcileOptions,
+) -> Result<ReconcileReport, AppError> {
+    let mut report = ReconcileReport {
+        orphans: find_orphan_annotations(storage, &scope.folders()?)?,
+        mismatched: Vec::new(),
+        relinks: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let mut unannotated = Vec::new();
+    for image_path in scope.images()? {
+        let image_path_str = image_path.to_string_lossy().to_string();
+        match load_annotation_file_from(storage, &image_path_str) {
+            Ok(Some(annotation_file)) => {
+                let stored = Path::new(&annotation_file.image_path);
+                if stored.file_name() != image_path.file_name() {
+                    report.mismatched.push(MismatchedImage {
+                        image_path: image_path_str,
+                        stored_image_path: annotation_file.image_path,
+                    });
+                }
+            }
+            Ok(None) => unannotated.push(image_path),
+            Err(e) => report
+                .errors
+                .push(format!("{}: {}", image_path_str, e.message)),
+        }
+    }
+
+    let mut hashes = HashMap::new();
+    for orphan in &report.orphans {
+        let annotation_file = match load_orphan(storage, orphan) {
+            Ok(annotation_file) => annotation_file,
+            Err(e) => {
+                report
+                    .errors
+                    .push(format!("{}: {}", orphan.annotation_path, e.message));
+                continue;
+            }
+        };
+        if let Some((image, method, distance)) =
+            find_relink(&annotation_file, &unannotated, &mut hashes, options)
+        {
+            report.relinks.push(RelinkCandidate {
+                orphan: orphan.clone(),
+                new_image_path: image.to_string_lossy().to_string(),
+                method,
+                distance,
+            });
+        }
+    }
+
+    // 同一图片只能关联一个孤立标注
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for relink in &report.relinks {
+        *counts.entry(relink.new_image_path.clone()).or_insert(0) += 1;
+    }
+    report
+        .relinks
+        .retain(|relink| counts[&relink.new_image_path] == 1);
+
+    Ok(report)
+}
+
+/// 核对文件夹或项目中的标注：孤立标注、标注中记录的文件名与图片不一致的图片，以及孤立标注可以重新关联的图片
+#[tauri::command]
+pub async fn reconcile_annotations(
+    scope: DatasetScope,
+    options: Option<ReconcileOptions>,
+) -> Result<ReconcileReport, AppError> {
+    reconcile(&current_storage(), &scope, &options.unwrap_or_default())
+}
+
+/// 修复操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RepairAction {
+    /// 把孤立标注移到匹配的图片（该图片还没有标注）
+    Relink {
+        #[serde(flatten)]
+        options: ReconcileOptions,
+    },
+    /// 把孤立标注移到回收目录（保持相对路径，SQLite记录导出为JSON）
+    Trash { trash_dir: String },
+    /// 删除孤立标注
+    Delete,
+    /// 把标注中记录的文件名改为图片的实际文件名
+    FixImagePath,
+}
+
+/// 修复结果
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub dry_run: bool,
+    pub repaired: Vec<String>, // 处理的标注文件（SQLite为原图片路径）或图片
+    pub errors: Vec<String>,
+}
+
+/// 孤立标注在回收目录中的相对路径：相对于所在的扫描文件夹或镜像标注目录
+fn trash_relative_path(
+    storage: &AnnotationStorage,
+    folders: &[(PathBuf, ScanOptions)],
+    orphan: &OrphanAnnotation,
+) -> PathBuf {
+    let path = match storage {
+        AnnotationStorage::Sqlite { .. } => {
+            let mut path = Path::new(&orphan.image_path).as_os_str().to_os_string();
+            path.push(".json");
+            PathBuf::from(path)
+        }
+        _ => PathBuf::from(&orphan.annotation_path),
+    };
+    let roots: Vec<&Path> = match storage {
+        AnnotationStorage::Mirrored {
+            annotations_dir, ..
+        } => vec![Path::new(annotations_dir)],
+        _ => folders.iter().map(|(folder, _)| folder.as_path()).collect(),
+    };
+    roots
+        .iter()
+        .find_map(|root| path.strip_prefix(root).ok())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(file_name_of(&path)))
+}
+
+/// 移动文件，不能重命名时（跨磁盘）复制后删除
+fn move_file(from: &Path, to: &Path) -> Result<(), AppError> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// 处理一个孤立标注
+fn repair_orphan(
+    storage: &AnnotationStorage,
+    folders: &[(PathBuf, ScanOptions)],
+    orphan: &OrphanAnnotation,
+    action: &RepairAction,
+    relink: Option<&RelinkCandidate>,
+) -> Result<bool, AppError> {
+    match action {
+        RepairAction::Relink { .. } => {
+            let Some(relink) = relink else {
+                return Ok(false);
+            };
+            if annotation_file_exists_in(storage, &relink.new_image_path)? {
+                return Err(AppError {
+                    message: format!("图片已有标注: {}", relink.new_image_path),
+                });
+            }
+            let mut annotation_file = load_orphan(storage, orphan)?;
+            annotation_file.image_path = file_name_of(Path::new(&relink.new_image_path));
+            save_annotation_file_to(storage, &relink.new_image_path, &annotation_file)?;
+            remove_orphan(storage, orphan)?;
+        }
+        RepairAction::Trash { trash_dir } => {
+            let target = Path::new(trash_dir).join(trash_relative_path(storage, folders, orphan));
+            match storage {
+                AnnotationStorage::Sqlite { .. } => {
+                    let annotation_file = load_orphan(storage, orphan)?;
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&target, serde_json::to_string_pretty(&annotation_file)?)?;
+                    remove_orphan(storage, orphan)?;
+                }
+                _ => {
+                    let path = Path::new(&orphan.annotation_path);
+                    move_file(path, &target)?;
+                    let backup = backup_file_path(path);
+                    if backup.exists() {
+                        move_file(&backup, &backup_file_path(&target))?;
+                    }
+                }
+            }
+        }
+        RepairAction::Delete => remove_orphan(storage, orphan)?,
+        RepairAction::FixImagePath => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// 修复孤立标注或文件名不一致的图片
+/// image_paths: 只处理这些孤立标注（按其image_path）或图片，为空时处理全部；dry_run为true时只返回将要处理的项目
+#[tauri::command]
+pub async fn repair_annotations(
+    scope: DatasetScope,
+    action: RepairAction,
+    image_paths: Option<Vec<String>>,
+    dry_run: Option<bool>,
+) -> Result<RepairResult, AppError> {
+    repair_annotations_internal(
+        &current_storage(),
+        &scope,
+        &action,
+        image_paths.as_deref(),
+        dry_run.unwrap_or(false),
+    )
+}
+
+/// 内部辅助函数：按指定的存储方式修复孤立标注或文件名不一致的图片
+pub fn repair_annotations_internal(
+    storage: &AnnotationStorage,
+    scope: &DatasetScope,
+    action: &RepairAction,
+    image_paths: Option<&[String]>,
+    dry_run: bool,
+) -> Result<RepairResult, AppError> {
+    let selected = |path: &str| image_paths.is_none_or(|paths| paths.iter().any(|p| p == path));
+    let folders = scope.folders()?;
+    let options = match action {
+        RepairAction::Relink { options } => options.clone(),
+        _ => ReconcileOptions::default(),
+    };
+    let report = reconcile(storage, scope, &options)?;
+
+    let mut result = RepairResult {
+        dry_run,
+        repaired: Vec::new(),
+        errors: report.errors.clone(),
+    };
+
+    if let RepairAction::FixImagePath = action {
+        for mismatched in report.mismatched.iter().filter(|m| selected(&m.image_path)) {
+            if !dry_run {
+                let fixed =
+                    load_annotation_file_from(storage, &mismatched.image_path).and_then(|file| {
+                        let Some(mut annotation_file) = file else {
+                            return Ok(());
+                        };
+                        annotation_file.image_path =
+                            file_name_of(Path::new(&mismatched.image_path));
+                        save_annotation_file_to(storage, &mismatched.image_path, &annotation_file)
+                    });
+                if let Err(e) = fixed {
+                    result
+                        .errors
+                        .push(format!("{}: {}", mismatched.image_path, e.message));
+                    continue;
+                }
+            }
+            result.repaired.push(mismatched.image_path.clone());
+        }
+        return Ok(result);
+    }
+
+    for orphan in report.orphans.iter().filter(|o| selected(&o.image_path)) {
+        let relink = report
+            .relinks
+            .iter()
+            .find(|relink| relink.orphan.image_path == orphan.image_path);
+        if dry_run {
+            if !matches!(action, RepairAction::Relink { .. }) || relink.is_some() {
+                result.repaired.push(orphan.image_path.clone());
+            }
+            continue;
+        }
+        match repair_orphan(storage, &folders, orphan, action, relink) {
+            Ok(true) => result.repaired.push(orphan.image_path.clone()),
+            Ok(false) => {}
+            Err(e) => result
+                .errors
+                .push(format!("{}: {}", orphan.annotation_path, e.message)),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 临时数据集：dir/sub/a.jpg 没有标注，dir/a.json 是它移动前留下的孤立标注，
+    /// dir/annotations.json 是COCO文件
+    fn setup() -> (PathBuf, DatasetScope) {
+        let dir = std::env::temp_dir().join(format!("oria-orphans-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("a.jpg"), b"").unwrap();
+        let annotation_file = AnnotationFile::new("a.jpg".to_string(), 10, 10);
+        std::fs::write(
+            dir.join("a.json"),
+            serde_json::to_string(&annotation_file).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("annotations.json"),
+            r#"{"images": [], "annotations": [], "categories": []}"#,
+        )
+        .unwrap();
+        let scope = DatasetScope::Folder {
+            path: dir.to_string_lossy().to_string(),
+            options: ScanOptions {
+                recursive: true,
+                ..Default::default()
+            },
+        };
+        (dir, scope)
+    }
+
+    fn repair(scope: &DatasetScope, action: RepairAction) -> RepairResult {
+        repair_annotations_internal(&AnnotationStorage::Sidecar, scope, &action, None, false)
+            .unwrap()
+    }
+
+    #[test]
+    fn only_sidecar_json_is_reported() {
+        let (dir, scope) = setup();
+        let orphans =
+            find_orphan_annotations(&AnnotationStorage::Sidecar, &scope.folders().unwrap())
+                .unwrap();
+        let paths: Vec<PathBuf> = orphans
+            .iter()
+            .map(|orphan| PathBuf::from(&orphan.annotation_path))
+            .collect();
+        assert_eq!(paths, vec![dir.join("a.json")]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn relink_moves_orphan_to_matching_image() {
+        let (dir, scope) = setup();
+        let result = repair(
+            &scope,
+            RepairAction::Relink {
+                options: ReconcileOptions::default(),
+            },
+        );
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.repaired.len(), 1);
+        assert!(!dir.join("a.json").exists());
+        assert!(dir.join("sub").join("a.json").is_file());
+        assert!(dir.join("annotations.json").is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn trash_keeps_relative_path() {
+        let (dir, scope) = setup();
+        let trash_dir = dir.join(".trash");
+        let result = repair(
+            &scope,
+            RepairAction::Trash {
+                trash_dir: trash_dir.to_string_lossy().to_string(),
+            },
+        );
+        assert_eq!(result.repaired.len(), 1);
+        assert!(!dir.join("a.json").exists());
+        assert!(trash_dir.join("a.json").is_file());
+        assert!(dir.join("annotations.json").is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn delete_never_touches_coco_file() {
+        let (dir, scope) = setup();
+        let result = repair(&scope, RepairAction::Delete);
+        assert_eq!(result.repaired.len(), 1);
+        assert!(!dir.join("a.json").exists());
+        assert!(dir.join("annotations.json").is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,486 @@
+//! 项目清单
+//!
+//! `.oria` 项目文件（JSON）描述一个跨多个图片文件夹的数据集：图片根目录、类别（ID、颜色、上级类别等）、
+//! 标注存储位置、推理预设和导出预设。清单中的路径相对于项目文件所在目录，便于在git中共享
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::attributes::{set_schema as set_attribute_schema, validate_schema, AttributeDefinition};
+use super::history::set_change_log;
+use super::scan::{scan_images, ScanOptions};
+use super::storage::{set_storage, AnnotationStorage};
+use super::taxonomy::{
+    load_folder_classes, set_project_classes, validate_classes, ClassDefinition,
+};
+use crate::error::AppError;
+use crate::inference::model_inference::InferenceConfig;
+use crate::utils::write_file_atomic;
+
+/// 项目文件扩展名
+pub const PROJECT_EXTENSION: &str = "oria";
+
+/// 当前项目清单版本
+const PROJECT_VERSION: u32 = 1;
+
+/// 图片根目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageRoot {
+    pub path: String, // 相对于项目文件目录的路径（也可以是绝对路径）
+    #[serde(flatten)]
+    pub scan: ScanOptions,
+}
+
+/// 推理预设
+/// config与推理配置格式相同；保存为原始JSON，未启用ONNX的构建也能打开含ONNX预设的项目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferencePreset {
+    pub name: String,
+    pub config: serde_json::Value,
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    Coco,
+    Yolo,
+    Mot,
+    Classification,
+    Split,
+}
+
+/// 导出预设
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportPreset {
+    pub name: String,
+    pub format: ExportFormat,
+    pub export_path: String, // 相对于项目文件目录的路径（也可以是绝对路径）
+    /// 格式相关的其他导出参数（如YOLO的class_map、划分比例）
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub settings: serde_json::Value,
+}
+
+/// 项目清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    #[serde(default = "default_project_version")]
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub roots: Vec<ImageRoot>,
+    #[serde(default)]
+    pub classes: Vec<ClassDefinition>,
+    /// 标注属性定义
+    #[serde(default)]
+    pub attributes: Vec<AttributeDefinition>,
+    /// 镜像存储的目录同样相对于项目文件目录
+    #[serde(default)]
+    pub storage: AnnotationStorage,
+    /// 是否在标注文件中记录变更日志
+    #[serde(default)]
+    pub change_log: bool,
+    #[serde(default)]
+    pub inference_presets: Vec<InferencePreset>,
+    #[serde(default)]
+    pub export_presets: Vec<ExportPreset>,
+}
+
+fn default_project_version() -> u32 {
+    PROJECT_VERSION
+}
+
+/// 项目检查问题
+#[derive(Debug, Serialize)]
+pub struct ProjectIssue {
+    pub severity: String, // "error" 或 "warning"
+    pub message: String,
+}
+
+/// 项目检查结果
+#[derive(Debug, Serialize)]
+pub struct ProjectValidation {
+    pub valid: bool, // 没有error级别的问题
+    pub issues: Vec<ProjectIssue>,
+}
+
+/// 打开的项目
+#[derive(Debug, Serialize)]
+pub struct OpenedProject {
+    pub project_path: String,
+    pub project_dir: String,
+    pub manifest: ProjectManifest,
+    pub images: Vec<String>, // 所有根目录中的图片
+    pub validation: ProjectValidation,
+}
+
+/// 项目文件所在目录
+fn project_dir(project_path: &Path) -> PathBuf {
+    project_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf()
+}
+
+/// 解析清单中的相对路径
+pub fn resolve_project_path(project_path: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        project_dir(project_path).join(path)
+    }
+}
+
+/// 读取项目清单
+pub fn load_project(project_path: &Path) -> Result<ProjectManifest, AppError> {
+    if !project_path.exists() {
+        return Err(AppError {
+            message: format!("Project file does not exist: {}", project_path.display()),
+        });
+    }
+
+    let content = std::fs::read_to_string(project_path)?;
+    let manifest: ProjectManifest = serde_json::from_str(&content)?;
+    if manifest.version > PROJECT_VERSION {
+        return Err(AppError {
+            message: format!(
+                "项目文件版本 {} 高于当前支持的版本 {}，请升级应用",
+                manifest.version, PROJECT_VERSION
+            ),
+        });
+    }
+    Ok(manifest)
+}
+
+/// 写入项目清单
+pub fn write_project(project_path: &Path, manifest: &ProjectManifest) -> Result<(), AppError> {
+    let content = serde_json::to_string_pretty(manifest)? + "\n";
+    write_file_atomic(project_path, content.as_bytes(), false)?;
+    Ok(())
+}
+
+// 当前打开的项目文件（规范化路径），没有打开项目时为None
+lazy_static::lazy_static! {
+    static ref OPEN_PROJECT: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// 规范化项目文件路径，用于判断是否为当前打开的项目
+fn canonical_project_path(project_path: &Path) -> PathBuf {
+    project_path
+        .canonicalize()
+        .unwrap_or_else(|_| project_path.to_path_buf())
+}
+
+/// 切换到项目的标注存储方式、变更日志设置、类别和属性定义
+pub fn apply_project_state(project_path: &Path, manifest: &ProjectManifest) {
+    let roots = manifest
+        .roots
+        .iter()
+        .map(|root| resolve_project_path(project_path, &root.path))
+        .collect();
+    set_storage(manifest.storage.resolved(&project_dir(project_path)));
+    set_change_log(manifest.change_log);
+    set_attribute_schema(manifest.attributes.clone());
+    set_project_classes(roots, manifest.classes.clone());
+    *OPEN_PROJECT.write().unwrap() = Some(canonical_project_path(project_path));
+}
+
+/// 恢复为没有打开项目时的状态：同目录存储、不记录变更日志、没有属性定义和项目类别
+pub fn reset_project_state() {
+    set_storage(AnnotationStorage::Sidecar);
+    set_change_log(false);
+    set_attribute_schema(Vec::new());
+    set_project_classes(Vec::new(), Vec::new());
+    *OPEN_PROJECT.write().unwrap() = None;
+}
+
+/// 是否为当前打开的项目
+pub fn is_open_project(project_path: &Path) -> bool {
+    OPEN_PROJECT.read().unwrap().as_deref() == Some(canonical_project_path(project_path).as_path())
+}
+
+/// 检查项目清单
+pub fn validate_manifest(project_path: &Path, manifest: &ProjectManifest) -> ProjectValidation {
+    let mut issues = Vec::new();
+    let mut error = |message: String| {
+        issues.push(ProjectIssue {
+            severity: "error".to_string(),
+            message,
+        })
+    };
+
+    if manifest.name.trim().is_empty() {
+        error("项目名称为空".to_string());
+    }
+    if manifest.roots.is_empty() {
+        error("没有配置图片根目录".to_string());
+    }
+    for root in &manifest.roots {
+        if !resolve_project_path(project_path, &root.path).is_dir() {
+            error(format!("图片根目录不存在: {}", root.path));
+        }
+    }
+
+    if let Some(problem) = manifest
+        .storage
+        .resolved(&project_dir(project_path))
+        .validate()
+    {
+        error(problem);
+    }
+
+    for problem in validate_classes(&manifest.classes) {
+        error(problem);
+    }
+
+    for problem in validate_schema(&manifest.attributes) {
+        error(problem);
+    }
+    for attribute in &manifest.attributes {
+        for class in &attribute.classes {
+            if !manifest.classes.is_empty() && !manifest.classes.iter().any(|c| &c.name == class) {
+                error(format!(
+                    "属性 {} 适用的类别未定义: {}",
+                    attribute.name, class
+                ));
+            }
+        }
+    }
+
+    let mut preset_names = HashSet::new();
+    for preset in &manifest.inference_presets {
+        if !preset_names.insert(preset.name.as_str()) {
+            error(format!("推理预设名称重复: {}", preset.name));
+        }
+    }
+    let mut preset_names = HashSet::new();
+    for preset in &manifest.export_presets {
+        if !preset_names.insert(preset.name.as_str()) {
+            error(format!("导出预设名称重复: {}", preset.name));
+        }
+    }
+
+    // 推理预设在当前构建中无法解析时只作为警告（如未启用ONNX时的ONNX预设）
+    for preset in &manifest.inference_presets {
+        if let Err(e) = serde_json::from_value::<InferenceConfig>(preset.config.clone()) {
+            issues.push(ProjectIssue {
+                severity: "warning".to_string(),
+                message: format!("推理预设 {} 在当前版本中不可用: {}", preset.name, e),
+            });
+        }
+    }
+    if manifest.classes.is_empty() {
+        issues.push(ProjectIssue {
+            severity: "warning".to_string(),
+            message: "没有定义类别".to_string(),
+        });
+    }
+
+    ProjectValidation {
+        valid: !issues.iter().any(|issue| issue.severity == "error"),
+        issues,
+    }
+}
+
+/// 扫描项目所有根目录中的图片（同一图片只出现一次）
+pub fn project_images(
+    project_path: &Path,
+    manifest: &ProjectManifest,
+) -> Result<Vec<PathBuf>, AppError> {
+    let mut seen = HashSet::new();
+    let mut images = Vec::new();
+    for root in &manifest.roots {
+        let root_path = resolve_project_path(project_path, &root.path);
+        if !root_path.is_dir() {
+            continue;
+        }
+        for image in scan_images(&root_path, &root.scan)? {
+            let canonical = image.canonicalize().unwrap_or_else(|_| image.clone());
+            if seen.insert(canonical) {
+                images.push(image);
+            }
+        }
+    }
+    Ok(images)
+}
+
+/// 批量操作的数据集范围：一个文件夹（可选扫描选项）或项目的所有根目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DatasetScope {
+    Folder {
+        path: String,
+        #[serde(flatten)]
+        options: ScanOptions,
+    },
+    Project {
+        project_path: String,
+    },
+}
+
+impl DatasetScope {
+    /// 范围内的所有图片
+    pub fn images(&self) -> Result<Vec<PathBuf>, AppError> {
+        match self {
+            DatasetScope::Folder { path, options } => scan_images(Path::new(path), options),
+            DatasetScope::Project { project_path } => {
+                let project_path = Path::new(project_path);
+                project_images(project_path, &load_project(project_path)?)
+            }
+        }
+    }
+
+    /// 范围内的文件夹及其扫描选项（项目为所有存在的根目录）
+    pub fn folders(&self) -> Result<Vec<(PathBuf, ScanOptions)>, AppError> {
+        match self {
+            DatasetScope::Folder { path, options } => {
+                Ok(vec![(PathBuf::from(path), options.clone())])
+            }
+            DatasetScope::Project { project_path } => {
+                let project_path = Path::new(project_path);
+                Ok(load_project(project_path)?
+                    .roots
+                    .into_iter()
+                    .map(|root| (resolve_project_path(project_path, &root.path), root.scan))
+                    .filter(|(path, _)| path.is_dir())
+                    .collect())
+            }
+        }
+    }
+
+    /// 范围使用的类别定义（项目为项目类别，文件夹为 .oria-classes.json 或 classes.txt）
+    pub fn classes(&self) -> Result<Vec<ClassDefinition>, AppError> {
+        match self {
+            DatasetScope::Folder { path, .. } => load_folder_classes(path),
+            DatasetScope::Project { project_path } => {
+                Ok(load_project(Path::new(project_path))?.classes)
+            }
+        }
+    }
+
+    /// classes.txt 所在的文件夹（项目为第一个根目录）
+    pub fn classes_folder(&self) -> Result<Option<PathBuf>, AppError> {
+        match self {
+            DatasetScope::Folder { path, .. } => Ok(Some(PathBuf::from(path))),
+            DatasetScope::Project { project_path } => {
+                let project_path = Path::new(project_path);
+                Ok(load_project(project_path)?
+                    .roots
+                    .first()
+                    .map(|root| resolve_project_path(project_path, &root.path)))
+            }
+        }
+    }
+}
+
+/// 补全项目文件扩展名
+fn with_project_extension(project_path: &str) -> PathBuf {
+    let path = PathBuf::from(project_path);
+    if path.extension().and_then(|e| e.to_str()) == Some(PROJECT_EXTENSION) {
+        path
+    } else {
+        path.with_extension(PROJECT_EXTENSION)
+    }
+}
+
+/// 创建项目
+/// 清单中没有类别时，从第一个根目录的 .oria-classes.json 或 classes.txt 导入（classes.txt 按行号分配ID）
+#[tauri::command]
+pub async fn create_project(
+    project_path: String,
+    manifest: ProjectManifest,
+) -> Result<OpenedProject, AppError> {
+    let project_path = with_project_extension(&project_path);
+    if project_path.exists() {
+        return Err(AppError {
+            message: format!("Project file already exists: {}", project_path.display()),
+        });
+    }
+
+    let mut manifest = manifest;
+    manifest.version = PROJECT_VERSION;
+    if manifest.classes.is_empty() {
+        if let Some(root) = manifest.roots.first() {
+            let root_path = resolve_project_path(&project_path, &root.path);
+            manifest.classes = load_folder_classes(&root_path.to_string_lossy())?;
+        }
+    }
+
+    let validation = validate_manifest(&project_path, &manifest);
+    if !validation.valid {
+        return Err(AppError {
+            message: format!(
+                "项目配置无效: {}",
+                validation
+                    .issues
+                    .iter()
+                    .filter(|issue| issue.severity == "error")
+                    .map(|issue| issue.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
+        });
+    }
+
+    if let Some(parent) = project_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_project(&project_path, &manifest)?;
+
+    open_project(project_path.to_string_lossy().to_string()).await
+}
+
+/// 打开项目：读取清单、检查并扫描所有根目录中的图片，切换到项目的标注存储方式、变更日志设置、类别和属性定义
+#[tauri::command]
+pub async fn open_project(project_path: String) -> Result<OpenedProject, AppError> {
+    let path = PathBuf::from(&project_path);
+    let manifest = load_project(&path)?;
+    let validation = validate_manifest(&path, &manifest);
+    apply_project_state(&path, &manifest);
+    let images = project_images(&path, &manifest)?
+        .into_iter()
+        .map(|image| image.to_string_lossy().to_string())
+        .collect();
+
+    Ok(OpenedProject {
+        project_dir: project_dir(&path).to_string_lossy().to_string(),
+        project_path,
+        manifest,
+        images,
+        validation,
+    })
+}
+
+/// 关闭项目，恢复为打开单个文件夹时的状态（见reset_project_state）
+#[tauri::command]
+pub async fn close_project() -> Result<(), AppError> {
+    reset_project_state();
+    Ok(())
+}
+
+/// 保存项目清单（覆盖已有文件），保存的是当前打开的项目时同时切换到新的设置
+#[tauri::command]
+pub async fn save_project(
+    project_path: String,
+    manifest: ProjectManifest,
+) -> Result<ProjectValidation, AppError> {
+    let path = PathBuf::from(&project_path);
+    let validation = validate_manifest(&path, &manifest);
+    write_project(&path, &manifest)?;
+    if is_open_project(&path) {
+        apply_project_state(&path, &manifest);
+    }
+    Ok(validation)
+}
+
+/// 检查项目文件
+#[tauri::command]
+pub async fn validate_project(project_path: String) -> Result<ProjectValidation, AppError> {
+    let path = PathBuf::from(&project_path);
+    let manifest = load_project(&path)?;
+    Ok(validate_manifest(&path, &manifest))
+}
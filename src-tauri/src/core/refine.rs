@@ -0,0 +1,132 @@
+//! 标注框细化
+//!
+//! 用分割模型（框提示）或类GrabCut兜底方案分割框内目标，
+//! 将框收紧为水平框、拟合最小面积旋转框或转换为多边形，并写回标注文件
+
+use std::path::Path;
+
+use super::annotation::{load_annotation_file, save_annotation_file};
+use super::geometry::{annotation_bounds, fit_contour, ShapeKind, DEFAULT_SIMPLIFY_TOLERANCE};
+use super::grabcut::grabcut;
+use crate::error::AppError;
+use crate::inference::segmentation::SegmenterConfig;
+use crate::models::AnnotationData;
+
+/// GrabCut迭代次数
+const GRABCUT_ITERATIONS: usize = 10;
+
+/// 细化标注
+/// shape: 细化结果形状，默认收紧为水平框
+/// segmenter: 分割模型配置，未指定时使用类GrabCut兜底方案
+#[tauri::command]
+pub async fn refine_annotation(
+    image_path: String,
+    annotation_id: String,
+    shape: Option<ShapeKind>,
+    segmenter: Option<SegmenterConfig>,
+    simplify_tolerance: Option<f64>,
+) -> Result<AnnotationData, AppError> {
+    tokio::task::spawn_blocking(move || {
+        refine_annotation_internal(
+            &image_path,
+            &annotation_id,
+            shape.unwrap_or(ShapeKind::Rectangle),
+            segmenter.as_ref(),
+            simplify_tolerance.unwrap_or(DEFAULT_SIMPLIFY_TOLERANCE),
+        )
+    })
+    .await
+    .map_err(|e| AppError {
+        message: e.to_string(),
+    })?
+}
+
+/// 内部辅助函数：细化标注并写回标注文件
+pub fn refine_annotation_internal(
+    image_path: &str,
+    annotation_id: &str,
+    shape: ShapeKind,
+    segmenter: Option<&SegmenterConfig>,
+    simplify_tolerance: f64,
+) -> Result<AnnotationData, AppError> {
+    if !Path::new(image_path).exists() {
+        return Err(AppError {
+            message: format!("Image file does not exist: {}", image_path),
+        });
+    }
+
+    let mut annotation_file = load_annotation_file(image_path)?.ok_or_else(|| AppError {
+        message: format!("Annotation file does not exist for: {}", image_path),
+    })?;
+    let index = annotation_file
+        .annotations
+        .iter()
+        .position(|annotation| annotation.id == annotation_id)
+        .ok_or_else(|| AppError {
+            message: format!("Annotation not found: {}", annotation_id),
+        })?;
+
+    let (x_min, y_min, x_max, y_max) = annotation_bounds(&annotation_file.annotations[index]);
+    let rect = [x_min, y_min, x_max, y_max];
+
+    // 分割框内目标，轮廓坐标转换到原图坐标系
+    let contour = match segmenter {
+        Some(config) => segment_with_model(image_path, config, rect)?,
+        None => {
+            let image = image::open(image_path)?;
+            let (mask, offset_x, offset_y) =
+                grabcut(&image, rect, GRABCUT_ITERATIONS).ok_or_else(|| AppError {
+                    message: "标注框过小，无法细化".to_string(),
+                })?;
+            mask.largest_component()
+                .outer_contour()
+                .into_iter()
+                .map(|(x, y)| (x + offset_x as f64, y + offset_y as f64))
+                .collect()
+        }
+    };
+
+    let fitted = fit_contour(&contour, shape, simplify_tolerance).ok_or_else(|| AppError {
+        message: "未能在标注框内分割出目标".to_string(),
+    })?;
+
+    let annotation = &mut annotation_file.annotations[index];
+    fitted.apply_to(annotation);
+    let refined = annotation.clone();
+
+    annotation_file.modified = chrono::Utc::now().to_rfc3339();
+    save_annotation_file(image_path, &annotation_file)?;
+
+    Ok(refined)
+}
+
+/// 使用分割模型（框提示）分割
+#[cfg(feature = "onnx")]
+fn segment_with_model(
+    image_path: &str,
+    config: &SegmenterConfig,
+    rect: [f64; 4],
+) -> Result<Vec<(f64, f64)>, AppError> {
+    use crate::inference::segmentation::{prompt_mask, SegmentPrompt};
+
+    let prompt = SegmentPrompt {
+        points: Vec::new(),
+        bbox: Some(rect),
+    };
+    let (mask, _, _) = prompt_mask(image_path, config, &prompt).map_err(|e| AppError {
+        message: format!("分割失败: {}", e),
+    })?;
+
+    Ok(mask.largest_component().outer_contour())
+}
+
+#[cfg(not(feature = "onnx"))]
+fn segment_with_model(
+    _image_path: &str,
+    _config: &SegmenterConfig,
+    _rect: [f64; 4],
+) -> Result<Vec<(f64, f64)>, AppError> {
+    Err(AppError {
+        message: "分割模型需要ONNX功能，请使用--features onnx编译，或不指定分割模型以使用GrabCut".to_string(),
+    })
+}
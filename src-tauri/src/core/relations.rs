@@ -0,0 +1,189 @@
+//! 标注分组和父子关系
+//!
+//! 标注可以属于一个分组（group_id，如骑手和自行车），也可以引用同一图片中的父标注（parent_id，如车牌属于车辆）。
+//! 保存时清除失效的引用，并在标注文件的relations中写出完整的关系，供下游工具重建层级
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::annotation::{load_annotation_file, save_annotation_file};
+use crate::error::AppError;
+use crate::models::AnnotationFile;
+
+/// 标注关系（由标注的group_id和parent_id生成）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AnnotationRelation {
+    Parent { parent: String, child: String },
+    Group { group_id: u64, members: Vec<String> },
+}
+
+/// 整理标注关系：清除指向不存在的标注或自身的父引用、断开循环引用、
+/// 清除只剩一个成员的分组，并重新生成relations
+pub fn normalize_relations(annotation_file: &mut AnnotationFile) {
+    let ids: HashSet<String> = annotation_file
+        .annotations
+        .iter()
+        .map(|annotation| annotation.id.clone())
+        .collect();
+
+    for annotation in &mut annotation_file.annotations {
+        if annotation
+            .parent_id
+            .as_ref()
+            .is_some_and(|parent| parent == &annotation.id || !ids.contains(parent))
+        {
+            annotation.parent_id = None;
+        }
+    }
+
+    // 沿父引用向上查找，回到自身说明有循环，断开当前标注的父引用
+    let mut parents: HashMap<String, String> = annotation_file
+        .annotations
+        .iter()
+        .filter_map(|a| a.parent_id.clone().map(|parent| (a.id.clone(), parent)))
+        .collect();
+    for annotation in &mut annotation_file.annotations {
+        let mut current = annotation.parent_id.clone();
+        let mut steps = 0;
+        while let Some(parent) = current {
+            if parent == annotation.id {
+                annotation.parent_id = None;
+                parents.remove(&annotation.id);
+                break;
+            }
+            steps += 1;
+            if steps > ids.len() {
+                break;
+            }
+            current = parents.get(&parent).cloned();
+        }
+    }
+
+    let mut groups: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    for annotation in &annotation_file.annotations {
+        if let Some(group_id) = annotation.group_id {
+            groups
+                .entry(group_id)
+                .or_default()
+                .push(annotation.id.clone());
+        }
+    }
+    groups.retain(|_, members| members.len() > 1);
+    for annotation in &mut annotation_file.annotations {
+        if annotation
+            .group_id
+            .is_some_and(|group_id| !groups.contains_key(&group_id))
+        {
+            annotation.group_id = None;
+        }
+    }
+
+    annotation_file.relations = annotation_file
+        .annotations
+        .iter()
+        .filter_map(|annotation| {
+            annotation
+                .parent_id
+                .as_ref()
+                .map(|parent| AnnotationRelation::Parent {
+                    parent: parent.clone(),
+                    child: annotation.id.clone(),
+                })
+        })
+        .chain(
+            groups
+                .into_iter()
+                .map(|(group_id, members)| AnnotationRelation::Group { group_id, members }),
+        )
+        .collect();
+}
+
+/// 加载已有的标注文件
+fn load_existing(image_path: &str) -> Result<AnnotationFile, AppError> {
+    load_annotation_file(image_path)?.ok_or_else(|| AppError {
+        message: format!("No annotations for image: {}", image_path),
+    })
+}
+
+/// 把标注编为一组，返回新的分组ID（图片中已有分组ID的最大值加1）
+#[tauri::command]
+pub async fn group_annotations(
+    image_path: String,
+    annotation_ids: Vec<String>,
+) -> Result<u64, AppError> {
+    if annotation_ids.len() < 2 {
+        return Err(AppError {
+            message: "分组至少需要两个标注".to_string(),
+        });
+    }
+
+    let mut annotation_file = load_existing(&image_path)?;
+    let group_id = annotation_file
+        .annotations
+        .iter()
+        .filter_map(|annotation| annotation.group_id)
+        .max()
+        .map_or(1, |max| max + 1);
+
+    let mut found = 0;
+    for annotation in &mut annotation_file.annotations {
+        if annotation_ids.contains(&annotation.id) {
+            annotation.group_id = Some(group_id);
+            found += 1;
+        }
+    }
+    if found != annotation_ids.len() {
+        return Err(AppError {
+            message: "部分标注不存在".to_string(),
+        });
+    }
+
+    save_annotation_file(&image_path, &annotation_file)?;
+    Ok(group_id)
+}
+
+/// 设置或清除标注的父标注（不能形成循环）
+#[tauri::command]
+pub async fn set_annotation_parent(
+    image_path: String,
+    annotation_id: String,
+    parent_id: Option<String>,
+) -> Result<(), AppError> {
+    let mut annotation_file = load_existing(&image_path)?;
+
+    if let Some(parent) = &parent_id {
+        let parents: HashMap<&str, &str> = annotation_file
+            .annotations
+            .iter()
+            .filter_map(|a| a.parent_id.as_deref().map(|p| (a.id.as_str(), p)))
+            .collect();
+        if !annotation_file.annotations.iter().any(|a| &a.id == parent) {
+            return Err(AppError {
+                message: format!("父标注不存在: {}", parent),
+            });
+        }
+        // 父标注不能是自身或自身的后代
+        let mut current = Some(parent.as_str());
+        for _ in 0..=parents.len() {
+            let Some(id) = current else { break };
+            if id == annotation_id {
+                return Err(AppError {
+                    message: "父子关系不能形成循环".to_string(),
+                });
+            }
+            current = parents.get(id).copied();
+        }
+    }
+
+    let annotation = annotation_file
+        .annotations
+        .iter_mut()
+        .find(|a| a.id == annotation_id)
+        .ok_or_else(|| AppError {
+            message: format!("标注不存在: {}", annotation_id),
+        })?;
+    annotation.parent_id = parent_id;
+
+    save_annotation_file(&image_path, &annotation_file)
+}
@@ -0,0 +1,173 @@
+//! 图片文件扫描
+//!
+//! 支持递归扫描子目录（如 site/date/camera/*.jpg）、按相对路径的包含/排除glob过滤，
+//! 以及是否跟随符号链接目录
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+use crate::utils::is_image_file;
+
+/// 扫描选项（默认与原来一致：只扫描文件夹本身）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanOptions {
+    #[serde(default)]
+    pub recursive: bool,
+    /// 包含的相对路径glob（如 "site-a/**"），为空时包含全部
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// 排除的相对路径glob（如 "**/thumbs/**"），优先于包含
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// 是否进入符号链接指向的目录（符号链接文件总是包含，循环链接会被跳过）
+    #[serde(default)]
+    pub follow_symlinks: bool,
+}
+
+/// 编译glob列表
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, AppError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| AppError {
+            message: format!("无效的glob模式 {}: {}", pattern, e),
+        })?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| AppError {
+        message: format!("无效的glob模式: {}", e),
+    })
+}
+
+/// 相对于根目录的路径，统一使用 / 分隔（用于glob匹配和导出的file_name）
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 扫描文件夹中的图片，按相对路径自然排序
+pub fn scan_images(root: &Path, options: &ScanOptions) -> Result<Vec<PathBuf>, AppError> {
+    if !root.is_dir() {
+        return Err(AppError {
+            message: format!("Folder does not exist: {}", root.display()),
+        });
+    }
+
+    let include = build_glob_set(&options.include)?;
+    let exclude = build_glob_set(&options.exclude)?;
+
+    let mut walker = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(options.follow_symlinks);
+    if !options.recursive {
+        walker = walker.max_depth(1);
+    }
+
+    let mut seen = HashSet::new();
+    let mut images = Vec::new();
+    for entry in walker {
+        // 循环符号链接、无权限目录等无法读取的条目直接跳过
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        if !path.is_file() || !is_image_file(path) {
+            continue;
+        }
+
+        let relative = relative_path(root, path);
+        if exclude.as_ref().is_some_and(|set| set.is_match(&relative)) {
+            continue;
+        }
+        if include.as_ref().is_some_and(|set| !set.is_match(&relative)) {
+            continue;
+        }
+
+        // 跟随符号链接时同一文件可能通过多个路径出现
+        if options.follow_symlinks {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            if !seen.insert(canonical) {
+                continue;
+            }
+        }
+
+        images.push(path.to_path_buf());
+    }
+
+    images.sort_by(|a, b| natord::compare(&relative_path(root, a), &relative_path(root, b)));
+    Ok(images)
+}
+
+/// 导入时按文件名查找图片：导入文件中的路径在文件夹中不存在时，
+/// 在所有子目录中按文件名查找（文件名唯一时才使用）
+pub struct ImageLocator {
+    root: PathBuf,
+    by_name: Option<HashMap<String, Option<PathBuf>>>,
+}
+
+impl ImageLocator {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            by_name: None,
+        }
+    }
+
+    /// 解析导入文件中的相对路径
+    pub fn resolve(&mut self, file_name: &str) -> Option<PathBuf> {
+        let direct = self.root.join(file_name);
+        if direct.exists() {
+            return Some(direct);
+        }
+
+        // 首次需要时才递归建立文件名索引
+        let root = &self.root;
+        let by_name = self.by_name.get_or_insert_with(|| {
+            let options = ScanOptions {
+                recursive: true,
+                ..Default::default()
+            };
+            let mut index: HashMap<String, Option<PathBuf>> = HashMap::new();
+            for path in scan_images(root, &options).unwrap_or_default() {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                index
+                    .entry(name)
+                    .and_modify(|existing| *existing = None)
+                    .or_insert(Some(path));
+            }
+            index
+        });
+
+        let name = Path::new(&file_name.replace('\\', "/"))
+            .file_name()?
+            .to_string_lossy()
+            .to_string();
+        by_name.get(&name).cloned().flatten()
+    }
+}
+
+/// 扫描文件夹中的图片
+#[tauri::command]
+pub async fn scan_folder(
+    folder: String,
+    options: Option<ScanOptions>,
+) -> Result<Vec<String>, AppError> {
+    let images = scan_images(Path::new(&folder), &options.unwrap_or_default())?;
+    Ok(images
+        .into_iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
@@ -0,0 +1,365 @@
+//! 标注文件格式版本
+//!
+//! 读取标注文件时按版本依次执行迁移，把旧格式升级到当前版本后再解析：
+//! - 0（无版本号的旧格式）：只有标注数组
+//! - 1.0：包含图片信息、创建和修改时间的对象格式
+//! - 1.1：每个标注记录创建时间、修改时间和修改者
+//! - 1.2：标注属性（attributes）。旧版本读取时会丢弃属性，因此升级版本号阻止旧版本覆盖
+//! - 1.3：标注分组和父标注（group_id、parent_id、relations）
+//! - 1.4：图片的感知哈希（image_hash）
+//!
+//! 新增格式版本时在MIGRATIONS末尾添加一步迁移并更新ANNOTATION_SCHEMA_VERSION
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::annotation::{load_annotation_file_versioned, save_annotation_file_to};
+use super::scan::{scan_images, ScanOptions};
+use super::storage::current_storage;
+use crate::error::AppError;
+use crate::models::AnnotationFile;
+
+/// 当前标注文件格式版本
+pub const ANNOTATION_SCHEMA_VERSION: &str = "1.4";
+
+/// 无版本号的旧数组格式
+const LEGACY_VERSION: &str = "0";
+
+/// 迁移步骤：(源版本, 目标版本, 迁移函数)
+type Migration = (&'static str, &'static str, fn(Value, &str) -> Value);
+
+const MIGRATIONS: &[Migration] = &[
+    (LEGACY_VERSION, "1.0", migrate_legacy_array),
+    ("1.0", "1.1", migrate_annotation_timestamps),
+    ("1.1", "1.2", migrate_unchanged),
+    ("1.2", "1.3", migrate_unchanged),
+    ("1.3", "1.4", migrate_unchanged),
+];
+
+/// 0 -> 1.0：标注数组包装为对象，旧格式没有图片尺寸信息，使用0作为占位
+fn migrate_legacy_array(value: Value, image_path: &str) -> Value {
+    let file_name = Path::new(image_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(image_path);
+    serde_json::json!({
+        "version": "1.0",
+        "image_path": file_name,
+        "image_width": 0,
+        "image_height": 0,
+        "annotations": value,
+        "created": "",
+        "modified": "",
+    })
+}
+
+/// 1.0 -> 1.1：没有创建时间的标注使用文件的创建时间
+fn migrate_annotation_timestamps(mut value: Value, _image_path: &str) -> Value {
+    let created = value
+        .get("created")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    if let Some(annotations) = value.get_mut("annotations").and_then(Value::as_array_mut) {
+        for annotation in annotations.iter_mut().filter_map(Value::as_object_mut) {
+            let missing = annotation
+                .get("created")
+                .and_then(Value::as_str)
+                .is_none_or(str::is_empty);
+            if missing && !created.is_empty() {
+                annotation.insert("created".to_string(), Value::String(created.clone()));
+            }
+        }
+    }
+    value
+}
+
+/// 只新增了可选字段的版本，内容不需要变化
+fn migrate_unchanged(value: Value, _image_path: &str) -> Value {
+    value
+}
+
+/// 解析版本号 "主版本.次版本"
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().splitn(2, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = match parts.next() {
+        Some(minor) => minor.parse().ok()?,
+        None => 0,
+    };
+    Some((major, minor))
+}
+
+/// 标注文件的格式版本：数组为旧格式，没有版本号的对象视为1.0
+fn schema_version(value: &Value) -> String {
+    match value {
+        Value::Array(_) => LEGACY_VERSION.to_string(),
+        _ => value
+            .get("version")
+            .and_then(Value::as_str)
+            .unwrap_or("1.0")
+            .to_string(),
+    }
+}
+
+/// 把标注JSON升级到当前版本并解析
+/// 返回标注文件和迁移前的版本（已是当前版本时为None）；文件来自更新的版本时返回错误
+pub fn upgrade_annotation_file(
+    value: Value,
+    image_path: &str,
+) -> Result<(AnnotationFile, Option<String>), AppError> {
+    let original_version = schema_version(&value);
+    let current = parse_version(ANNOTATION_SCHEMA_VERSION).unwrap();
+    let parsed = parse_version(&original_version).ok_or_else(|| AppError {
+        message: format!("无法识别的标注文件版本: {}", original_version),
+    })?;
+    if parsed > current {
+        return Err(AppError {
+            message: format!(
+                "标注文件版本 {} 高于当前支持的版本 {}，请升级应用后再打开",
+                original_version, ANNOTATION_SCHEMA_VERSION
+            ),
+        });
+    }
+
+    let mut value = value;
+    let mut version = original_version.clone();
+    while parse_version(&version) != Some(current) {
+        let step = MIGRATIONS
+            .iter()
+            .find(|(from, _, _)| parse_version(from) == parse_version(&version))
+            .ok_or_else(|| AppError {
+                message: format!("无法迁移标注文件版本: {}", version),
+            })?;
+        let (_, to, migrate) = *step;
+        value = migrate(value, image_path);
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), Value::String(to.to_string()));
+        }
+        version = to.to_string();
+    }
+
+    let annotation_file: AnnotationFile = serde_json::from_value(value).map_err(|e| AppError {
+        message: format!("Failed to parse annotation file: {}", e),
+    })?;
+    let migrated_from = (original_version != ANNOTATION_SCHEMA_VERSION).then_some(original_version);
+    Ok((annotation_file, migrated_from))
+}
+
+/// 标注格式迁移结果
+#[derive(Debug, Serialize)]
+pub struct SchemaMigrationResult {
+    pub image_count: usize,
+    pub migrated_count: usize,
+    pub up_to_date_count: usize,
+    pub missing_count: usize,                       // 没有标注的图片数
+    pub migrated_versions: BTreeMap<String, usize>, // 各个原版本的文件数
+    pub errors: Vec<String>,                        // 无法读取或来自更新版本的文件
+}
+
+/// 把文件夹中的标注升级到当前格式版本并写回
+/// dry_run为true时只统计不写入
+#[tauri::command]
+pub async fn migrate_annotation_schema(
+    image_folder: String,
+    options: Option<ScanOptions>,
+    dry_run: Option<bool>,
+) -> Result<SchemaMigrationResult, AppError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let storage = current_storage();
+    let image_paths = scan_images(Path::new(&image_folder), &options.unwrap_or_default())?;
+
+    let mut result = SchemaMigrationResult {
+        image_count: image_paths.len(),
+        migrated_count: 0,
+        up_to_date_count: 0,
+        missing_count: 0,
+        migrated_versions: BTreeMap::new(),
+        errors: Vec::new(),
+    };
+
+    for image_path in image_paths {
+        let image_path = image_path.to_string_lossy().to_string();
+        match load_annotation_file_versioned(&storage, &image_path) {
+            Ok(None) => result.missing_count += 1,
+            Ok(Some((_, None))) => result.up_to_date_count += 1,
+            Ok(Some((annotation_file, Some(from_version)))) => {
+                if !dry_run {
+                    if let Err(e) = save_annotation_file_to(&storage, &image_path, &annotation_file)
+                    {
+                        result.errors.push(format!("{}: {}", image_path, e.message));
+                        continue;
+                    }
+                }
+                result.migrated_count += 1;
+                *result.migrated_versions.entry(from_version).or_insert(0) += 1;
+            }
+            Err(e) => result.errors.push(format!("{}: {}", image_path, e.message)),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn annotation(extra: Value) -> Value {
+        let mut value = json!({
+            "id": "a1",
+            "type": "rectangle",
+            "x": 1.0,
+            "y": 2.0,
+            "width": 3.0,
+            "height": 4.0,
+            "label": "car",
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        value
+    }
+
+    fn file(version: &str, annotation: Value, extra: Value) -> Value {
+        let mut value = json!({
+            "version": version,
+            "image_path": "a.jpg",
+            "image_width": 64,
+            "image_height": 48,
+            "annotations": [annotation],
+            "created": "2024-01-01T00:00:00Z",
+            "modified": "2024-01-02T00:00:00Z",
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        value
+    }
+
+    fn file_without_version() -> Value {
+        let mut value = file("1.0", annotation(json!({})), json!({}));
+        value.as_object_mut().unwrap().remove("version");
+        value
+    }
+
+    #[test]
+    fn migrations_form_a_chain_to_the_current_version() {
+        assert_eq!(MIGRATIONS.first().unwrap().0, LEGACY_VERSION);
+        assert_eq!(MIGRATIONS.last().unwrap().1, ANNOTATION_SCHEMA_VERSION);
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(pair[0].1, pair[1].0);
+        }
+    }
+
+    #[test]
+    fn legacy_array_migrates_to_1_0() {
+        let value = json!([annotation(json!({}))]);
+        let (file, migrated_from) = upgrade_annotation_file(value, "/data/frames/a.jpg").unwrap();
+
+        assert_eq!(migrated_from.as_deref(), Some("0"));
+        assert_eq!(file.version, ANNOTATION_SCHEMA_VERSION);
+        assert_eq!(file.image_path, "a.jpg");
+        assert_eq!((file.image_width, file.image_height), (0, 0));
+        assert_eq!(file.annotations.len(), 1);
+        assert_eq!(file.annotations[0].label, "car");
+    }
+
+    #[test]
+    fn version_1_0_fills_annotation_timestamps() {
+        let mut value = file("1.0", annotation(json!({})), json!({}));
+        value["annotations"]
+            .as_array_mut()
+            .unwrap()
+            .push(annotation(
+                json!({"id": "a2", "created": "2024-03-01T00:00:00Z"}),
+            ));
+        let (file, migrated_from) = upgrade_annotation_file(value, "a.jpg").unwrap();
+
+        assert_eq!(migrated_from.as_deref(), Some("1.0"));
+        assert_eq!(file.annotations[0].created, "2024-01-01T00:00:00Z");
+        assert_eq!(file.annotations[1].created, "2024-03-01T00:00:00Z");
+
+        // 没有版本号的对象视为1.0
+        let mut value = file_without_version();
+        value["annotations"][0]["created"] = json!("");
+        let (file, migrated_from) = upgrade_annotation_file(value, "a.jpg").unwrap();
+        assert_eq!(migrated_from.as_deref(), Some("1.0"));
+        assert_eq!(file.annotations[0].created, "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn version_1_1_keeps_attributes() {
+        let value = file(
+            "1.1",
+            annotation(json!({"attributes": {"occluded": true}})),
+            json!({}),
+        );
+        let (file, migrated_from) = upgrade_annotation_file(value, "a.jpg").unwrap();
+
+        assert_eq!(migrated_from.as_deref(), Some("1.1"));
+        assert_eq!(file.annotations[0].attributes["occluded"], json!(true));
+    }
+
+    #[test]
+    fn version_1_2_keeps_groups() {
+        let value = file(
+            "1.2",
+            annotation(json!({"group_id": 7, "parent_id": "a0"})),
+            json!({}),
+        );
+        let (file, migrated_from) = upgrade_annotation_file(value, "a.jpg").unwrap();
+
+        assert_eq!(migrated_from.as_deref(), Some("1.2"));
+        assert_eq!(file.annotations[0].group_id, Some(7));
+        assert_eq!(file.annotations[0].parent_id.as_deref(), Some("a0"));
+    }
+
+    #[test]
+    fn version_1_3_keeps_image_hash() {
+        let value = file(
+            "1.3",
+            annotation(json!({})),
+            json!({"image_hash": "ffff0000ffff0000"}),
+        );
+        let (file, migrated_from) = upgrade_annotation_file(value, "a.jpg").unwrap();
+
+        assert_eq!(migrated_from.as_deref(), Some("1.3"));
+        assert_eq!(file.version, ANNOTATION_SCHEMA_VERSION);
+        assert_eq!(file.image_hash, "ffff0000ffff0000");
+    }
+
+    #[test]
+    fn current_version_is_not_migrated() {
+        let value = file(ANNOTATION_SCHEMA_VERSION, annotation(json!({})), json!({}));
+        let (file, migrated_from) = upgrade_annotation_file(value, "a.jpg").unwrap();
+
+        assert_eq!(migrated_from, None);
+        assert_eq!(file.annotations.len(), 1);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        for version in ["1.5", "2.0", "2"] {
+            let value = file(version, annotation(json!({})), json!({}));
+            let error = upgrade_annotation_file(value, "a.jpg").unwrap_err();
+            assert!(error.message.contains(version), "{}", error.message);
+            assert!(
+                error.message.contains(ANNOTATION_SCHEMA_VERSION),
+                "{}",
+                error.message
+            );
+        }
+
+        let value = file("next", annotation(json!({})), json!({}));
+        assert!(upgrade_annotation_file(value, "a.jpg")
+            .unwrap_err()
+            .message
+            .contains("无法识别"));
+    }
+}
@@ -0,0 +1,416 @@
+//! 数据集统计
+//!
+//! 扫描文件夹或项目的所有标注，统计图片数、各类别的标注数、每张图片的标注数、标注框相对大小和宽高比、
+//! 旋转角度、图片分辨率和类别共现，可以导出为JSON或CSV
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use super::annotation::load_annotation_file;
+use super::geometry::annotation_bounds;
+use super::project::DatasetScope;
+use crate::error::AppError;
+use crate::models::AnnotationData;
+use crate::utils::csv_field;
+
+/// 标注框相对大小的分组边界：sqrt(框面积 / 图片面积)
+const BOX_SIZE_EDGES: &[f64] = &[0.0, 0.01, 0.02, 0.05, 0.1, 0.2, 0.3, 0.5, 0.75];
+
+/// 宽高比（宽 / 高）的分组边界
+const ASPECT_RATIO_EDGES: &[f64] = &[0.0, 0.25, 0.33, 0.5, 0.67, 0.8, 1.25, 1.5, 2.0, 3.0, 4.0];
+
+/// 旋转角度的分组宽度（度），旋转框角度按180度取模
+const ROTATION_BIN_WIDTH: f64 = 15.0;
+
+/// 直方图分组 [lower, upper)，upper为None表示没有上限
+#[derive(Debug, Serialize)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: Option<f64>,
+    pub count: usize,
+}
+
+/// 按分组边界统计，最后一组的上限为max
+fn histogram(values: &[f64], edges: &[f64], max: Option<f64>) -> Vec<HistogramBin> {
+    let mut bins: Vec<HistogramBin> = edges
+        .iter()
+        .enumerate()
+        .map(|(i, &lower)| HistogramBin {
+            lower,
+            upper: edges.get(i + 1).copied().or(max),
+            count: 0,
+        })
+        .collect();
+    for value in values {
+        if let Some(bin) = bins.iter_mut().rev().find(|bin| *value >= bin.lower) {
+            bin.count += 1;
+        }
+    }
+    bins
+}
+
+/// 类别统计
+#[derive(Debug, Default, Serialize)]
+pub struct ClassStatistics {
+    pub annotation_count: usize,
+    pub image_count: usize, // 包含该类别的图片数
+}
+
+/// 两个类别同时出现的图片数
+#[derive(Debug, Serialize)]
+pub struct LabelCooccurrence {
+    pub first: String,
+    pub second: String,
+    pub image_count: usize,
+}
+
+/// 数据集统计结果
+#[derive(Debug, Serialize)]
+pub struct DatasetStatistics {
+    pub image_count: usize,
+    pub labeled_count: usize, // 至少有一个标注的图片数
+    pub empty_count: usize,   // 没有标注的图片数（包括没有标注文件的图片）
+    pub annotation_count: usize,
+    pub classes: BTreeMap<String, ClassStatistics>,
+    pub annotation_types: BTreeMap<String, usize>,
+    pub boxes_per_image: BTreeMap<usize, usize>, // 标注数 -> 图片数
+    pub box_size: Vec<HistogramBin>,             // sqrt(框面积 / 图片面积)
+    pub aspect_ratio: Vec<HistogramBin>,         // 宽 / 高（像素）
+    pub rotation: Vec<HistogramBin>,             // 旋转框角度（度）
+    pub resolutions: BTreeMap<String, usize>,    // "宽x高" -> 图片数
+    pub cooccurrence: Vec<LabelCooccurrence>,    // 按图片数从多到少
+    pub errors: Vec<String>,                     // 无法读取的标注文件
+}
+
+/// 标注框的宽和高：矩形和旋转框使用自身的宽高，多边形使用外接框
+fn annotation_size(annotation: &AnnotationData) -> (f64, f64) {
+    match annotation.annotation_type.as_str() {
+        "rectangle" | "rotated-rectangle" => (annotation.width.abs(), annotation.height.abs()),
+        _ => {
+            let (x_min, y_min, x_max, y_max) = annotation_bounds(annotation);
+            (x_max - x_min, y_max - y_min)
+        }
+    }
+}
+
+/// 统计数据集范围内的所有图片和标注
+pub fn collect_statistics(scope: &DatasetScope) -> Result<DatasetStatistics, AppError> {
+    let images = scope.images()?;
+    let mut statistics = DatasetStatistics {
+        image_count: images.len(),
+        labeled_count: 0,
+        empty_count: 0,
+        annotation_count: 0,
+        classes: BTreeMap::new(),
+        annotation_types: BTreeMap::new(),
+        boxes_per_image: BTreeMap::new(),
+        box_size: Vec::new(),
+        aspect_ratio: Vec::new(),
+        rotation: Vec::new(),
+        resolutions: BTreeMap::new(),
+        cooccurrence: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut box_sizes = Vec::new();
+    let mut aspect_ratios = Vec::new();
+    let mut rotations = Vec::new();
+    let mut pairs: BTreeMap<(String, String), usize> = BTreeMap::new();
+
+    for image_path in images {
+        let image_path_str = image_path.to_string_lossy().to_string();
+        let annotation_file = match load_annotation_file(&image_path_str) {
+            Ok(annotation_file) => annotation_file,
+            Err(e) => {
+                statistics
+                    .errors
+                    .push(format!("{}: {}", image_path_str, e.message));
+                continue;
+            }
+        };
+
+        // 旧格式的标注文件没有图片尺寸，从图片文件头读取
+        let (width, height) = match &annotation_file {
+            Some(file) if file.image_width > 0 && file.image_height > 0 => {
+                (file.image_width, file.image_height)
+            }
+            _ => image::image_dimensions(&image_path).unwrap_or((0, 0)),
+        };
+        let resolution = if width > 0 && height > 0 {
+            format!("{}x{}", width, height)
+        } else {
+            "unknown".to_string()
+        };
+        *statistics.resolutions.entry(resolution).or_insert(0) += 1;
+
+        let annotations = annotation_file
+            .map(|file| file.annotations)
+            .unwrap_or_default();
+        *statistics
+            .boxes_per_image
+            .entry(annotations.len())
+            .or_insert(0) += 1;
+        if annotations.is_empty() {
+            statistics.empty_count += 1;
+            continue;
+        }
+        statistics.labeled_count += 1;
+        statistics.annotation_count += annotations.len();
+
+        for annotation in &annotations {
+            statistics
+                .classes
+                .entry(annotation.label.clone())
+                .or_default()
+                .annotation_count += 1;
+            *statistics
+                .annotation_types
+                .entry(annotation.annotation_type.clone())
+                .or_insert(0) += 1;
+
+            let (box_width, box_height) = annotation_size(annotation);
+            if width > 0 && height > 0 {
+                box_sizes.push((box_width * box_height / (width as f64 * height as f64)).sqrt());
+            }
+            if box_height > 0.0 {
+                aspect_ratios.push(box_width / box_height);
+            }
+            if annotation.annotation_type == "rotated-rectangle" {
+                // 标注中的旋转角度为弧度
+                let degrees = annotation.rotation.unwrap_or(0.0).to_degrees();
+                rotations.push(degrees.rem_euclid(180.0));
+            }
+        }
+
+        let labels: BTreeSet<&String> = annotations.iter().map(|a| &a.label).collect();
+        for label in &labels {
+            if let Some(class) = statistics.classes.get_mut(*label) {
+                class.image_count += 1;
+            }
+        }
+        let labels: Vec<&String> = labels.into_iter().collect();
+        for (i, first) in labels.iter().enumerate() {
+            for second in &labels[i + 1..] {
+                *pairs
+                    .entry(((*first).clone(), (*second).clone()))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    let rotation_edges: Vec<f64> = (0..(180.0 / ROTATION_BIN_WIDTH) as usize)
+        .map(|i| i as f64 * ROTATION_BIN_WIDTH)
+        .collect();
+    statistics.box_size = histogram(&box_sizes, BOX_SIZE_EDGES, None);
+    statistics.aspect_ratio = histogram(&aspect_ratios, ASPECT_RATIO_EDGES, None);
+    statistics.rotation = histogram(&rotations, &rotation_edges, Some(180.0));
+
+    statistics.cooccurrence = pairs
+        .into_iter()
+        .map(|((first, second), image_count)| LabelCooccurrence {
+            first,
+            second,
+            image_count,
+        })
+        .collect();
+    statistics
+        .cooccurrence
+        .sort_by_key(|pair| std::cmp::Reverse(pair.image_count));
+
+    Ok(statistics)
+}
+
+/// 直方图分组名称，如 "0.1-0.2"、"0.75+"
+fn bin_name(bin: &HistogramBin) -> String {
+    match bin.upper {
+        Some(upper) => format!("{}-{}", bin.lower, upper),
+        None => format!("{}+", bin.lower),
+    }
+}
+
+/// 转换为 section,key,value 格式的CSV
+fn statistics_csv(statistics: &DatasetStatistics) -> String {
+    let mut rows: Vec<(&str, String, usize)> = vec![
+        ("summary", "image_count".to_string(), statistics.image_count),
+        (
+            "summary",
+            "labeled_count".to_string(),
+            statistics.labeled_count,
+        ),
+        ("summary", "empty_count".to_string(), statistics.empty_count),
+        (
+            "summary",
+            "annotation_count".to_string(),
+            statistics.annotation_count,
+        ),
+    ];
+    for (label, class) in &statistics.classes {
+        rows.push(("class_annotations", label.clone(), class.annotation_count));
+        rows.push(("class_images", label.clone(), class.image_count));
+    }
+    for (annotation_type, count) in &statistics.annotation_types {
+        rows.push(("annotation_type", annotation_type.clone(), *count));
+    }
+    for (boxes, count) in &statistics.boxes_per_image {
+        rows.push(("boxes_per_image", boxes.to_string(), *count));
+    }
+    for (section, bins) in [
+        ("box_size", &statistics.box_size),
+        ("aspect_ratio", &statistics.aspect_ratio),
+        ("rotation", &statistics.rotation),
+    ] {
+        for bin in bins {
+            rows.push((section, bin_name(bin), bin.count));
+        }
+    }
+    for (resolution, count) in &statistics.resolutions {
+        rows.push(("resolution", resolution.clone(), *count));
+    }
+    for pair in &statistics.cooccurrence {
+        rows.push((
+            "cooccurrence",
+            format!("{}|{}", pair.first, pair.second),
+            pair.image_count,
+        ));
+    }
+
+    let mut lines = vec!["section,key,value".to_string()];
+    lines.extend(
+        rows.into_iter()
+            .map(|(section, key, value)| format!("{},{},{}", section, csv_field(&key), value)),
+    );
+    lines.join("\n") + "\n"
+}
+
+/// 统计文件夹或项目中的图片和标注
+#[tauri::command]
+pub async fn dataset_statistics(scope: DatasetScope) -> Result<DatasetStatistics, AppError> {
+    collect_statistics(&scope)
+}
+
+/// 导出数据集统计
+/// format: "json" 或 "csv"，未指定时按导出路径的扩展名判断；导出路径为目录时写入 dataset_statistics.json/csv
+#[tauri::command]
+pub async fn export_dataset_statistics(
+    scope: DatasetScope,
+    export_path: String,
+    format: Option<String>,
+) -> Result<String, AppError> {
+    let export_path = Path::new(&export_path);
+    let format = format.unwrap_or_else(|| match export_path.extension().and_then(|e| e.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => "csv".to_string(),
+        _ => "json".to_string(),
+    });
+    if format != "json" && format != "csv" {
+        return Err(AppError {
+            message: format!("Unsupported statistics format: {}", format),
+        });
+    }
+
+    let statistics = collect_statistics(&scope)?;
+    let content = if format == "csv" {
+        statistics_csv(&statistics)
+    } else {
+        serde_json::to_string_pretty(&statistics)? + "\n"
+    };
+
+    let output_path = if export_path.is_dir() {
+        export_path.join(format!("dataset_statistics.{}", format))
+    } else {
+        export_path.to_path_buf()
+    };
+    std::fs::write(&output_path, content)?;
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::annotation::save_annotation_file;
+    use crate::core::scan::ScanOptions;
+    use crate::models::AnnotationFile;
+
+    #[test]
+    fn histogram_counts_values_into_bins() {
+        let bins = histogram(
+            &[0.0, 0.005, 0.01, 0.3, 0.74, 0.9, 2.0, -1.0],
+            BOX_SIZE_EDGES,
+            None,
+        );
+        let counts: Vec<usize> = bins.iter().map(|bin| bin.count).collect();
+        // 负值不计入任何分组，最后一组没有上限
+        assert_eq!(counts, [2, 1, 0, 0, 0, 0, 1, 1, 2]);
+        assert_eq!(bins[0].upper, Some(0.01));
+        assert_eq!(bins.last().unwrap().upper, None);
+
+        let bins = histogram(&[10.0, 170.0], &[0.0, 90.0], Some(180.0));
+        assert_eq!(bins[1].upper, Some(180.0));
+        assert_eq!(bins[0].count + bins[1].count, 2);
+    }
+
+    #[test]
+    fn statistics_cover_classes_sizes_and_cooccurrence() {
+        let dir = std::env::temp_dir().join(format!("oria-statistics-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let annotation = |label: &str, width: f64, height: f64| -> AnnotationData {
+            serde_json::from_value(serde_json::json!({
+                "id": uuid::Uuid::new_v4().to_string(),
+                "type": "rectangle",
+                "x": 0.0,
+                "y": 0.0,
+                "width": width,
+                "height": height,
+                "label": label,
+            }))
+            .unwrap()
+        };
+        let save = |name: &str, annotations: Vec<AnnotationData>| {
+            let image_path = dir.join(name);
+            std::fs::write(&image_path, b"").unwrap();
+            if !annotations.is_empty() {
+                let mut file = AnnotationFile::new(name.to_string(), 100, 100);
+                file.annotations = annotations;
+                save_annotation_file(&image_path.to_string_lossy(), &file).unwrap();
+            }
+        };
+        save(
+            "1.jpg",
+            vec![
+                annotation("car", 10.0, 10.0),
+                annotation("person", 5.0, 20.0),
+            ],
+        );
+        save("2.jpg", vec![annotation("car", 50.0, 25.0)]);
+        save("3.jpg", vec![]);
+
+        let statistics = collect_statistics(&DatasetScope::Folder {
+            path: dir.to_string_lossy().to_string(),
+            options: ScanOptions::default(),
+        })
+        .unwrap();
+        assert_eq!(statistics.image_count, 3);
+        assert_eq!(statistics.labeled_count, 2);
+        assert_eq!(statistics.empty_count, 1);
+        assert_eq!(statistics.annotation_count, 3);
+        assert_eq!(statistics.classes["car"].annotation_count, 2);
+        assert_eq!(statistics.classes["car"].image_count, 2);
+        assert_eq!(statistics.boxes_per_image[&2], 1);
+        assert_eq!(statistics.resolutions["100x100"], 2);
+        assert_eq!(statistics.resolutions["unknown"], 1);
+        // sqrt(面积比)：0.1、0.1、0.354
+        let box_sizes: Vec<usize> = statistics.box_size.iter().map(|bin| bin.count).collect();
+        assert_eq!(box_sizes, [0, 0, 0, 0, 2, 0, 1, 0, 0]);
+        assert_eq!(statistics.cooccurrence.len(), 1);
+        assert_eq!(
+            (
+                statistics.cooccurrence[0].first.as_str(),
+                statistics.cooccurrence[0].second.as_str(),
+                statistics.cooccurrence[0].image_count
+            ),
+            ("car", "person", 1)
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,333 @@
+//! 标注存储位置
+//!
+//! 标注JSON可以放在图片旁边（默认），也可以放在单独的标注目录中镜像图片目录结构
+//! （图片目录只读时使用），或按完整文件名命名以避免 a.jpg 与 a.png 冲突；
+//! 大数据集还可以把所有标注保存在一个SQLite数据库中。
+//! 当前使用的存储方式保存在全局状态中，打开项目时按项目配置设置
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::annotation::{
+    annotation_file_exists_in, load_annotation_file_from, remove_annotation_file_from,
+    save_annotation_file_to,
+};
+use super::taxonomy::is_taxonomy_file;
+use crate::error::AppError;
+use crate::utils::get_annotation_file_path;
+
+/// 标注存储方式
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AnnotationStorage {
+    /// 与图片同目录的 <文件名主干>.json
+    #[default]
+    Sidecar,
+    /// 与图片同目录的 <完整文件名>.json（如 a.jpg.json）
+    FullFilename,
+    /// 在 annotations_dir 中镜像 image_root 下的目录结构：<annotations_dir>/<相对路径>.json
+    Mirrored {
+        annotations_dir: String,
+        image_root: String,
+    },
+    /// 所有标注保存在一个SQLite数据库文件中
+    Sqlite { database: String },
+}
+
+impl AnnotationStorage {
+    /// 图片对应的标注文件路径
+    pub fn annotation_path(&self, image_path: &str) -> Result<PathBuf, AppError> {
+        let path = match self {
+            AnnotationStorage::Sidecar => Ok(PathBuf::from(get_annotation_file_path(image_path))),
+            AnnotationStorage::FullFilename => {
+                let path = Path::new(image_path);
+                let file_name = path.file_name().ok_or_else(|| AppError {
+                    message: format!("Invalid image path: {}", image_path),
+                })?;
+                Ok(path.with_file_name(format!("{}.json", file_name.to_string_lossy())))
+            }
+            AnnotationStorage::Mirrored {
+                annotations_dir,
+                image_root,
+            } => {
+                let relative = relative_to_root(Path::new(image_path), Path::new(image_root))
+                    .ok_or_else(|| AppError {
+                        message: format!("图片不在镜像存储的图片根目录中: {}", image_path),
+                    })?;
+                let mut file_name = relative.as_os_str().to_os_string();
+                file_name.push(".json");
+                Ok(Path::new(annotations_dir).join(file_name))
+            }
+            AnnotationStorage::Sqlite { .. } => Err(AppError {
+                message: "SQLite存储没有单独的标注文件".to_string(),
+            }),
+        }?;
+        // 同目录存储时不能覆盖类别体系文件
+        if is_taxonomy_file(&path) {
+            return Err(AppError {
+                message: format!(
+                    "图片的标注文件与类别体系文件同名，请重命名图片: {}",
+                    image_path
+                ),
+            });
+        }
+        Ok(path)
+    }
+
+    /// 把配置中的相对路径解析为相对于base的绝对路径
+    pub fn resolved(&self, base: &Path) -> Self {
+        let resolve = |path: &str| {
+            let path = Path::new(path);
+            if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                base.join(path)
+            }
+            .to_string_lossy()
+            .to_string()
+        };
+        match self {
+            AnnotationStorage::Mirrored {
+                annotations_dir,
+                image_root,
+            } => AnnotationStorage::Mirrored {
+                annotations_dir: resolve(annotations_dir),
+                image_root: resolve(image_root),
+            },
+            AnnotationStorage::Sqlite { database } => AnnotationStorage::Sqlite {
+                database: resolve(database),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// 检查配置，返回问题描述
+    pub fn validate(&self) -> Option<String> {
+        match self {
+            AnnotationStorage::Mirrored {
+                annotations_dir,
+                image_root,
+            } => {
+                if annotations_dir.trim().is_empty() {
+                    Some("镜像存储的标注目录为空".to_string())
+                } else if !Path::new(image_root).is_dir() {
+                    Some(format!("镜像存储的图片根目录不存在: {}", image_root))
+                } else {
+                    None
+                }
+            }
+            AnnotationStorage::Sqlite { database } if database.trim().is_empty() => {
+                Some("SQLite存储的数据库路径为空".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 图片相对于根目录的路径；先按原路径比较，再按规范化路径比较（处理符号链接和 ..）
+fn relative_to_root(image_path: &Path, root: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = image_path.strip_prefix(root) {
+        return Some(relative.to_path_buf());
+    }
+    let image = image_path.canonicalize().ok()?;
+    let root = root.canonicalize().ok()?;
+    image.strip_prefix(root).ok().map(|p| p.to_path_buf())
+}
+
+// 当前使用的存储方式
+lazy_static::lazy_static! {
+    static ref STORAGE: RwLock<AnnotationStorage> = RwLock::new(AnnotationStorage::Sidecar);
+}
+
+/// 当前使用的存储方式
+pub fn current_storage() -> AnnotationStorage {
+    STORAGE.read().unwrap().clone()
+}
+
+/// 设置当前使用的存储方式
+pub fn set_storage(storage: AnnotationStorage) {
+    *STORAGE.write().unwrap() = storage;
+}
+
+/// 存储迁移结果
+#[derive(Debug, Serialize)]
+pub struct StorageMigrationResult {
+    pub migrated_count: usize,
+    pub missing_count: usize,  // 没有标注文件的图片数
+    pub conflict_count: usize, // 目标位置已存在标注文件或与其他图片共用源标注文件而跳过的图片数
+    /// 与其他图片共用同一个源标注文件的图片（如同目录存储下的 a.jpg 和 a.png），无法确定标注属于哪张图片
+    pub shared_sources: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl StorageMigrationResult {
+    /// 没有错误和共用的源标注文件，可以切换到目标存储方式
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty() && self.shared_sources.is_empty()
+    }
+}
+
+/// 获取当前的标注存储方式
+#[tauri::command]
+pub async fn get_annotation_storage() -> Result<AnnotationStorage, AppError> {
+    Ok(current_storage())
+}
+
+/// 设置当前的标注存储方式（镜像存储使用绝对路径）
+#[tauri::command]
+pub async fn set_annotation_storage(storage: AnnotationStorage) -> Result<(), AppError> {
+    if let Some(problem) = storage.validate() {
+        return Err(AppError { message: problem });
+    }
+    set_storage(storage);
+    Ok(())
+}
+
+/// 在两种存储方式之间迁移标注
+/// 也用于SQLite数据库与JSON标注文件之间的导入导出
+/// overwrite: 目标位置已存在标注时是否覆盖；remove_source: 迁移后是否删除原标注
+/// 多张图片共用同一个源标注文件时作为冲突跳过并在shared_sources中列出
+/// 全部成功后把当前存储方式切换为目标方式（使用项目时还需保存项目配置）
+#[tauri::command]
+pub async fn migrate_annotation_storage(
+    image_paths: Vec<String>,
+    from: AnnotationStorage,
+    to: AnnotationStorage,
+    overwrite: Option<bool>,
+    remove_source: Option<bool>,
+) -> Result<StorageMigrationResult, AppError> {
+    let result = migrate_annotation_storage_internal(
+        &image_paths,
+        &from,
+        &to,
+        overwrite.unwrap_or(false),
+        remove_source.unwrap_or(false),
+    )?;
+
+    if result.is_complete() {
+        set_storage(to);
+    }
+
+    Ok(result)
+}
+
+/// 内部辅助函数：在指定的两种存储方式之间迁移标注，不修改当前存储方式
+pub fn migrate_annotation_storage_internal(
+    image_paths: &[String],
+    from: &AnnotationStorage,
+    to: &AnnotationStorage,
+    overwrite: bool,
+    remove_source: bool,
+) -> Result<StorageMigrationResult, AppError> {
+    for storage in [from, to] {
+        if let Some(problem) = storage.validate() {
+            return Err(AppError { message: problem });
+        }
+    }
+    if from == to {
+        return Err(AppError {
+            message: "源存储方式与目标存储方式相同".to_string(),
+        });
+    }
+    let mut result = StorageMigrationResult {
+        migrated_count: 0,
+        missing_count: 0,
+        conflict_count: 0,
+        shared_sources: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    // 按源标注文件分组，多张图片对应同一个文件时不复制也不删除
+    let mut sources: HashMap<PathBuf, HashSet<&str>> = HashMap::new();
+    if !matches!(from, AnnotationStorage::Sqlite { .. }) {
+        for image_path in image_paths {
+            if let Ok(path) = from.annotation_path(image_path) {
+                sources.entry(path).or_default().insert(image_path);
+            }
+        }
+    }
+    let is_shared = |image_path: &str| {
+        from.annotation_path(image_path)
+            .ok()
+            .and_then(|path| sources.get(&path))
+            .is_some_and(|images| images.len() > 1)
+    };
+
+    for image_path in image_paths {
+        if is_shared(image_path) {
+            result.conflict_count += 1;
+            result.shared_sources.push(image_path.clone());
+            continue;
+        }
+        let migrate = || -> Result<MigrationOutcome, AppError> {
+            let annotation_file = match load_annotation_file_from(from, image_path)? {
+                Some(file) => file,
+                None => return Ok(MigrationOutcome::Missing),
+            };
+            if !overwrite && annotation_file_exists_in(to, image_path)? {
+                return Ok(MigrationOutcome::Conflict);
+            }
+            save_annotation_file_to(to, image_path, &annotation_file)?;
+            if remove_source {
+                remove_annotation_file_from(from, image_path)?;
+            }
+            Ok(MigrationOutcome::Migrated)
+        };
+
+        match migrate() {
+            Ok(MigrationOutcome::Migrated) => result.migrated_count += 1,
+            Ok(MigrationOutcome::Missing) => result.missing_count += 1,
+            Ok(MigrationOutcome::Conflict) => result.conflict_count += 1,
+            Err(e) => result.errors.push(format!("{}: {}", image_path, e.message)),
+        }
+    }
+
+    Ok(result)
+}
+
+/// 单张图片的迁移结果
+enum MigrationOutcome {
+    Migrated,
+    Missing,
+    Conflict,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AnnotationFile;
+
+    #[test]
+    fn shared_sidecar_is_reported_as_conflict() {
+        let dir = std::env::temp_dir().join(format!("oria-storage-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = |name: &str| dir.join(name).to_string_lossy().to_string();
+        for name in ["a", "b"] {
+            let annotation_file = AnnotationFile::new(format!("{}.jpg", name), 10, 10);
+            let content = serde_json::to_string(&annotation_file).unwrap();
+            std::fs::write(dir.join(format!("{}.json", name)), content).unwrap();
+        }
+
+        let result = migrate_annotation_storage_internal(
+            &[image("a.jpg"), image("a.png"), image("b.jpg")],
+            &AnnotationStorage::Sidecar,
+            &AnnotationStorage::FullFilename,
+            false,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(result.migrated_count, 1);
+        assert_eq!(result.conflict_count, 2);
+        assert_eq!(result.shared_sources, [image("a.jpg"), image("a.png")]);
+        assert!(dir.join("a.json").exists());
+        assert!(!dir.join("a.jpg.json").exists() && !dir.join("a.png.json").exists());
+        assert!(dir.join("b.jpg.json").exists() && !dir.join("b.json").exists());
+        // 有冲突时不切换存储方式
+        assert!(!result.is_complete());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
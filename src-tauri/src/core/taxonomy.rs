@@ -0,0 +1,331 @@
+//! 类别体系
+//!
+//! .oria-classes.json 在 classes.txt 的基础上为每个类别记录稳定的ID、显示颜色、快捷键、标注说明、上级类别和别名。
+//! 两个文件可以并存：保存 .oria-classes.json 时同步写出 classes.txt（按ID排序），只有 classes.txt 时按行号分配ID。
+//! 文件名以点开头，不会与图片的同目录标注文件（<文件名主干>.json）冲突
+//! 上级类别可以是另一个类别（形成层级）或只用于分组的名称，导出为COCO的supercategory；
+//! 导入COCO和MOT时别名映射为类别名称
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::labels::{get_classes_file_path, read_label_lines};
+use crate::error::AppError;
+use crate::utils::write_file_atomic;
+
+/// 类别体系文件名（与 classes.txt 位于同一文件夹）
+const TAXONOMY_FILE_NAME: &str = ".oria-classes.json";
+
+/// 当前类别体系文件版本
+const TAXONOMY_VERSION: u32 = 1;
+
+/// 类别定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassDefinition {
+    pub id: u32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>, // "#RRGGBB"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hotkey: Option<String>, // 单个按键，如 "1"、"q"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>, // 标注说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supercategory: Option<String>,
+    /// 导入时视为该类别的其他名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl ClassDefinition {
+    pub fn new(id: u32, name: String) -> Self {
+        ClassDefinition {
+            id,
+            name,
+            color: None,
+            hotkey: None,
+            description: None,
+            supercategory: None,
+            aliases: Vec::new(),
+        }
+    }
+}
+
+/// 类别体系文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct TaxonomyFile {
+    #[serde(default = "default_taxonomy_version")]
+    version: u32,
+    classes: Vec<ClassDefinition>,
+}
+
+fn default_taxonomy_version() -> u32 {
+    TAXONOMY_VERSION
+}
+
+/// 当前项目的类别及其适用的图片根目录（规范化路径）
+#[derive(Default)]
+struct ProjectClasses {
+    roots: Vec<PathBuf>,
+    classes: Vec<ClassDefinition>,
+}
+
+// 当前项目的类别（为空或文件夹不在项目根目录中时使用文件夹中的类别文件）
+lazy_static::lazy_static! {
+    static ref PROJECT_CLASSES: RwLock<ProjectClasses> = RwLock::new(ProjectClasses::default());
+}
+
+/// 规范化路径，不存在时原样返回
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 设置当前项目的类别和项目的图片根目录
+pub fn set_project_classes(roots: Vec<PathBuf>, classes: Vec<ClassDefinition>) {
+    *PROJECT_CLASSES.write().unwrap() = ProjectClasses {
+        roots: roots.iter().map(|root| canonical(root)).collect(),
+        classes,
+    };
+}
+
+/// 文件夹位于当前项目的某个根目录中时返回项目类别
+fn project_classes_for(image_folder: &str) -> Option<Vec<ClassDefinition>> {
+    let project = PROJECT_CLASSES.read().unwrap();
+    if project.classes.is_empty() {
+        return None;
+    }
+    let folder = canonical(Path::new(image_folder));
+    project
+        .roots
+        .iter()
+        .any(|root| folder.starts_with(root))
+        .then(|| project.classes.clone())
+}
+
+/// 获取类别体系文件路径
+pub fn get_taxonomy_file_path(image_folder: &str) -> PathBuf {
+    Path::new(image_folder).join(TAXONOMY_FILE_NAME)
+}
+
+/// 是否为类别体系文件
+pub fn is_taxonomy_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == TAXONOMY_FILE_NAME)
+}
+
+/// 检查颜色格式 #RRGGBB
+fn is_valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// 检查类别定义，返回问题列表
+pub fn validate_classes(classes: &[ClassDefinition]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut ids = HashSet::new();
+    let mut hotkeys = HashSet::new();
+    let mut names: HashMap<&str, &str> = HashMap::new(); // 名称或别名 -> 类别名称
+
+    for class in classes {
+        if class.name.trim().is_empty() {
+            problems.push(format!("类别 {} 的名称为空", class.id));
+        }
+        if !ids.insert(class.id) {
+            problems.push(format!("类别ID重复: {}", class.id));
+        }
+        if let Some(color) = &class.color {
+            if !is_valid_color(color) {
+                problems.push(format!(
+                    "类别 {} 的颜色格式无效: {}（应为 #RRGGBB）",
+                    class.name, color
+                ));
+            }
+        }
+        if let Some(hotkey) = &class.hotkey {
+            if hotkey.trim().is_empty() {
+                problems.push(format!("类别 {} 的快捷键为空", class.name));
+            } else if !hotkeys.insert(hotkey.to_lowercase()) {
+                problems.push(format!("快捷键重复: {}", hotkey));
+            }
+        }
+        for name in std::iter::once(&class.name).chain(&class.aliases) {
+            match names.insert(name.as_str(), class.name.as_str()) {
+                Some(other) if other == class.name => {
+                    problems.push(format!("类别 {} 的别名重复: {}", class.name, name))
+                }
+                Some(other) => problems.push(format!(
+                    "名称 {} 同时属于类别 {} 和 {}",
+                    name, other, class.name
+                )),
+                None => {}
+            }
+        }
+    }
+
+    // 沿上级类别向上查找，回到自身说明有循环
+    let parents: HashMap<&str, &str> = classes
+        .iter()
+        .filter_map(|c| c.supercategory.as_deref().map(|s| (c.name.as_str(), s)))
+        .collect();
+    for class in classes {
+        let mut current = class.supercategory.as_deref();
+        for _ in 0..=parents.len() {
+            let Some(name) = current else { break };
+            if name == class.name {
+                problems.push(format!("类别 {} 的上级类别形成循环", class.name));
+                break;
+            }
+            current = parents.get(name).copied();
+        }
+    }
+
+    problems
+}
+
+/// 读取文件夹中的类别：优先 .oria-classes.json，否则按 classes.txt 的行号分配ID，都不存在时为空
+pub fn load_folder_classes(image_folder: &str) -> Result<Vec<ClassDefinition>, AppError> {
+    let taxonomy_path = get_taxonomy_file_path(image_folder);
+    if taxonomy_path.exists() {
+        let content = std::fs::read_to_string(&taxonomy_path)?;
+        let file: TaxonomyFile = serde_json::from_str(&content).map_err(|e| AppError {
+            message: format!("Failed to parse {}: {}", taxonomy_path.display(), e),
+        })?;
+        return Ok(file.classes);
+    }
+
+    let classes_path = get_classes_file_path(image_folder);
+    if !classes_path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(read_label_lines(&classes_path)?
+        .into_iter()
+        .enumerate()
+        .map(|(id, name)| ClassDefinition::new(id as u32, name))
+        .collect())
+}
+
+/// 写入 .oria-classes.json，并按ID顺序同步写出 classes.txt
+pub fn write_folder_classes(
+    image_folder: &str,
+    classes: &[ClassDefinition],
+) -> Result<(), AppError> {
+    let mut classes = classes.to_vec();
+    classes.sort_by_key(|class| class.id);
+
+    let file = TaxonomyFile {
+        version: TAXONOMY_VERSION,
+        classes,
+    };
+    let content = serde_json::to_string_pretty(&file)? + "\n";
+    write_file_atomic(
+        &get_taxonomy_file_path(image_folder),
+        content.as_bytes(),
+        false,
+    )?;
+
+    let names: Vec<&str> = file.classes.iter().map(|c| c.name.as_str()).collect();
+    write_file_atomic(
+        &get_classes_file_path(image_folder),
+        names.join("\n").as_bytes(),
+        false,
+    )?;
+    Ok(())
+}
+
+/// 图片文件夹使用的类别：文件夹位于打开的项目中时为项目类别，否则为文件夹中的类别文件
+pub fn resolve_classes(image_folder: &str) -> Result<Vec<ClassDefinition>, AppError> {
+    match project_classes_for(image_folder) {
+        Some(classes) => Ok(classes),
+        None => load_folder_classes(image_folder),
+    }
+}
+
+/// 名称和别名到类别名称的映射
+pub fn alias_map(classes: &[ClassDefinition]) -> HashMap<String, String> {
+    classes
+        .iter()
+        .flat_map(|class| {
+            std::iter::once(&class.name)
+                .chain(&class.aliases)
+                .map(|name| (name.clone(), class.name.clone()))
+        })
+        .collect()
+}
+
+/// 获取图片文件夹使用的类别
+#[tauri::command]
+pub async fn load_class_taxonomy(image_folder: String) -> Result<Vec<ClassDefinition>, AppError> {
+    resolve_classes(&image_folder)
+}
+
+/// 保存文件夹的类别体系（.oria-classes.json 和 classes.txt）
+/// 文件夹位于打开的项目中时类别由项目文件定义，保存后也不会生效，因此返回错误
+#[tauri::command]
+pub async fn save_class_taxonomy(
+    image_folder: String,
+    classes: Vec<ClassDefinition>,
+) -> Result<(), AppError> {
+    if project_classes_for(&image_folder).is_some() {
+        return Err(AppError {
+            message: format!(
+                "{} 属于当前打开的项目，类别由项目文件定义，请通过 save_project 修改",
+                image_folder
+            ),
+        });
+    }
+    let problems = validate_classes(&classes);
+    if !problems.is_empty() {
+        return Err(AppError {
+            message: format!("类别定义无效: {}", problems.join("; ")),
+        });
+    }
+    write_folder_classes(&image_folder, &classes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_classes_only_apply_inside_project_roots() {
+        let base = std::env::temp_dir().join(format!("oria-taxonomy-{}", uuid::Uuid::new_v4()));
+        let root = base.join("project");
+        let other = base.join("other");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        set_project_classes(
+            vec![root.clone()],
+            vec![ClassDefinition::new(0, "project".to_string())],
+        );
+
+        let names = |folder: &Path| -> Vec<String> {
+            resolve_classes(&folder.to_string_lossy())
+                .unwrap()
+                .into_iter()
+                .map(|c| c.name)
+                .collect()
+        };
+        assert_eq!(names(&root.join("sub")), vec!["project"]);
+
+        // 项目外的文件夹保存后可以读回
+        let classes = vec![ClassDefinition::new(3, "car".to_string())];
+        tauri::async_runtime::block_on(save_class_taxonomy(
+            other.to_string_lossy().to_string(),
+            classes.clone(),
+        ))
+        .unwrap();
+        assert_eq!(names(&other), vec!["car"]);
+
+        // 项目内的文件夹不能保存不会生效的类别文件
+        assert!(tauri::async_runtime::block_on(save_class_taxonomy(
+            root.to_string_lossy().to_string(),
+            classes,
+        ))
+        .is_err());
+        assert!(!get_taxonomy_file_path(&root.to_string_lossy()).exists());
+
+        set_project_classes(Vec::new(), Vec::new());
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
@@ -0,0 +1,872 @@
+//! 目标跟踪
+//!
+//! 为标注分配跨帧的跟踪ID，并把第N帧的标注传播到第N+1帧：
+//! 在目标帧运行检测模型后按IoU匹配继承跟踪ID，或按前两帧的位移匀速外推；
+//! 也可以在两个关键帧之间按跟踪ID插值补全中间帧的标注
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::annotation::{load_annotation_file, save_annotation_file};
+use super::geometry::{annotation_center, bounds_iou};
+use super::scan::{scan_images, ScanOptions};
+use crate::error::AppError;
+use crate::inference::model_inference::{InferenceConfig, InferenceManager};
+use crate::models::{AnnotationData, AnnotationFile};
+
+/// 默认的匹配IoU阈值
+fn default_iou_threshold() -> f64 {
+    0.3
+}
+
+/// 标注传播方式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum PropagationMode {
+    /// 在目标帧运行检测模型，检测框与源帧标注按IoU贪心匹配，匹配到的检测框继承跟踪ID和类别
+    Detections {
+        config: Box<InferenceConfig>,
+        #[serde(default = "default_iou_threshold")]
+        iou_threshold: f64,
+        /// 未匹配的检测框是否作为新目标加入（分配新的跟踪ID）
+        #[serde(default)]
+        keep_unmatched: bool,
+    },
+    /// 按上一帧到源帧的位移匀速外推，没有上一帧或上一帧中没有该目标时原样复制
+    Motion {
+        #[serde(default)]
+        previous_image: Option<String>,
+    },
+}
+
+/// 标注传播结果
+#[derive(Debug, Serialize)]
+pub struct PropagationResult {
+    pub annotations: Vec<AnnotationData>, // 目标帧的全部标注
+    pub propagated_count: usize,          // 新增到目标帧的标注数
+    pub matched_count: usize,             // 与检测框匹配的目标数（仅检测模式）
+    pub new_track_count: usize,           // 新分配的跟踪ID数
+    pub skipped_count: usize,             // 目标帧已存在同一跟踪ID而跳过的目标数
+}
+
+// 各图片文件夹已使用的最大跟踪ID，首次使用时扫描文件夹，之后随标注保存更新
+lazy_static::lazy_static! {
+    static ref MAX_TRACK_IDS: Mutex<HashMap<PathBuf, u64>> = Mutex::new(HashMap::new());
+}
+
+/// 保存标注时更新所在文件夹的最大跟踪ID（文件夹尚未扫描过时不记录）
+pub fn record_track_ids(image_path: &str, annotation_file: &AnnotationFile) {
+    let Some(folder) = Path::new(image_path).parent() else {
+        return;
+    };
+    let max_id = annotation_file
+        .annotations
+        .iter()
+        .filter_map(|a| a.track_id)
+        .max();
+    if let Some(max_id) = max_id {
+        if let Some(cached) = MAX_TRACK_IDS.lock().unwrap().get_mut(folder) {
+            *cached = (*cached).max(max_id);
+        }
+    }
+}
+
+/// 序列的帧：文件夹中直接包含的图片（按文件名自然排序），跟踪ID和帧顺序都以文件夹为单位
+fn sequence_frames(folder: &Path) -> Result<Vec<PathBuf>, AppError> {
+    scan_images(folder, &ScanOptions::default())
+}
+
+/// 文件夹中已使用的最大跟踪ID的下一个值
+/// 每个文件夹只扫描一次标注文件，删除标注后不回收跟踪ID
+pub fn next_track_id(folder: &Path) -> Result<u64, AppError> {
+    if let Some(max_id) = MAX_TRACK_IDS.lock().unwrap().get(folder) {
+        return Ok(max_id + 1);
+    }
+
+    let mut max_id = 0;
+    for path in sequence_frames(folder)? {
+        if let Ok(Some(file)) = load_annotation_file(&path.to_string_lossy()) {
+            for annotation in &file.annotations {
+                if let Some(track_id) = annotation.track_id {
+                    max_id = max_id.max(track_id);
+                }
+            }
+        }
+    }
+    let mut cache = MAX_TRACK_IDS.lock().unwrap();
+    let max_id = cache.entry(folder.to_path_buf()).or_insert(max_id);
+    Ok(*max_id + 1)
+}
+
+/// 读取标注文件，不存在时按图片尺寸创建空的标注文件
+fn load_or_create(image_path: &str) -> Result<AnnotationFile, AppError> {
+    match load_annotation_file(image_path)? {
+        Some(file) => Ok(file),
+        None => {
+            let path = Path::new(image_path);
+            let (width, height) = image::image_dimensions(path)?;
+            Ok(AnnotationFile::new(
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(image_path)
+                    .to_string(),
+                width,
+                height,
+            ))
+        }
+    }
+}
+
+/// 复制标注为新的标注（新ID和创建时间，修改记录在保存时生成）
+fn new_instance(annotation: &AnnotationData) -> AnnotationData {
+    AnnotationData {
+        id: uuid::Uuid::new_v4().to_string(),
+        interpolated: false,
+        created: chrono::Utc::now().to_rfc3339(),
+        modified: String::new(),
+        author: String::new(),
+        ..annotation.clone()
+    }
+}
+
+/// 按位移外推标注：位置、尺寸和旋转角按 current + (current - previous) 计算，多边形只平移
+fn extrapolate(previous: &AnnotationData, current: &AnnotationData) -> AnnotationData {
+    let mut predicted = new_instance(current);
+    let dx = current.x - previous.x;
+    let dy = current.y - previous.y;
+    predicted.x += dx;
+    predicted.y += dy;
+
+    match &mut predicted.points {
+        Some(points) if predicted.annotation_type == "polygon" => {
+            for point in points.iter_mut() {
+                point[0] += dx;
+                point[1] += dy;
+            }
+        }
+        _ => {
+            predicted.width = (current.width + current.width - previous.width).max(1.0);
+            predicted.height = (current.height + current.height - previous.height).max(1.0);
+            if let (Some(prev), Some(curr)) = (previous.rotation, current.rotation) {
+                predicted.rotation = Some(curr + (curr - prev));
+            }
+        }
+    }
+
+    predicted
+}
+
+/// 把源帧标注传播到目标帧
+#[tauri::command]
+pub async fn propagate_annotations(
+    source_image: String,
+    target_image: String,
+    mode: PropagationMode,
+) -> Result<PropagationResult, AppError> {
+    // 检测模式先在目标帧运行模型（不保存推理结果）
+    let detections = match &mode {
+        PropagationMode::Detections { config, .. } => Some(
+            InferenceManager::new(config.as_ref().clone())
+                .detect(&target_image)
+                .await
+                .map_err(|e| AppError {
+                    message: format!("推理失败: {}", e),
+                })?
+                .annotations,
+        ),
+        PropagationMode::Motion { .. } => None,
+    };
+
+    propagate_annotations_internal(&source_image, &target_image, &mode, detections)
+}
+
+/// 内部辅助函数：传播标注并写回源帧（补全跟踪ID）和目标帧的标注文件
+pub fn propagate_annotations_internal(
+    source_image: &str,
+    target_image: &str,
+    mode: &PropagationMode,
+    detections: Option<Vec<AnnotationData>>,
+) -> Result<PropagationResult, AppError> {
+    for path in [source_image, target_image] {
+        if !Path::new(path).exists() {
+            return Err(AppError {
+                message: format!("Image file does not exist: {}", path),
+            });
+        }
+    }
+
+    let mut source_file = load_annotation_file(source_image)?.ok_or_else(|| AppError {
+        message: format!("Annotation file does not exist for: {}", source_image),
+    })?;
+    let mut target_file = load_or_create(target_image)?;
+
+    let folder = Path::new(source_image)
+        .parent()
+        .unwrap_or_else(|| Path::new("."));
+    let mut next_id = next_track_id(folder)?;
+    let mut new_track_count = 0;
+
+    // 源帧中没有跟踪ID的标注先分配新ID
+    if source_file.annotations.iter().any(|a| a.track_id.is_none()) {
+        for annotation in source_file.annotations.iter_mut() {
+            if annotation.track_id.is_none() {
+                annotation.track_id = Some(next_id);
+                next_id += 1;
+                new_track_count += 1;
+            }
+        }
+        source_file.modified = chrono::Utc::now().to_rfc3339();
+        save_annotation_file(source_image, &source_file)?;
+    }
+
+    // 目标帧已有的跟踪ID不重复传播
+    let existing: HashSet<u64> = target_file
+        .annotations
+        .iter()
+        .filter_map(|a| a.track_id)
+        .collect();
+    let sources: Vec<&AnnotationData> = source_file
+        .annotations
+        .iter()
+        .filter(|a| !a.track_id.is_some_and(|id| existing.contains(&id)))
+        .collect();
+    let skipped_count = source_file.annotations.len() - sources.len();
+
+    let mut propagated = Vec::new();
+    let mut matched_count = 0;
+
+    match mode {
+        PropagationMode::Detections {
+            iou_threshold,
+            keep_unmatched,
+            ..
+        } => {
+            let detections = detections.unwrap_or_default();
+
+            // 按IoU从高到低贪心匹配
+            let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+            for (si, source) in sources.iter().enumerate() {
+                for (di, detection) in detections.iter().enumerate() {
+                    let iou = bounds_iou(source, detection);
+                    if iou >= *iou_threshold {
+                        pairs.push((iou, si, di));
+                    }
+                }
+            }
+            pairs.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+            let mut source_used = vec![false; sources.len()];
+            let mut detection_used = vec![false; detections.len()];
+            for (_, si, di) in pairs {
+                if source_used[si] || detection_used[di] {
+                    continue;
+                }
+                source_used[si] = true;
+                detection_used[di] = true;
+
+                let mut annotation = detections[di].clone();
+                annotation.label = sources[si].label.clone();
+                annotation.track_id = sources[si].track_id;
+                propagated.push(annotation);
+                matched_count += 1;
+            }
+
+            if *keep_unmatched {
+                for (detection, used) in detections.iter().zip(&detection_used) {
+                    if *used {
+                        continue;
+                    }
+                    let mut annotation = detection.clone();
+                    annotation.track_id = Some(next_id);
+                    next_id += 1;
+                    new_track_count += 1;
+                    propagated.push(annotation);
+                }
+            }
+        }
+        PropagationMode::Motion { previous_image } => {
+            let previous: HashMap<u64, AnnotationData> = match previous_image {
+                Some(path) => load_annotation_file(path)?
+                    .map(|file| file.annotations)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|a| a.track_id.map(|id| (id, a)))
+                    .collect(),
+                None => HashMap::new(),
+            };
+
+            for source in &sources {
+                let annotation = match source.track_id.and_then(|id| previous.get(&id)) {
+                    Some(prev) => extrapolate(prev, source),
+                    None => new_instance(source),
+                };
+                propagated.push(annotation);
+            }
+        }
+    }
+
+    let propagated_count = propagated.len();
+    target_file.annotations.extend(propagated);
+    target_file.modified = chrono::Utc::now().to_rfc3339();
+    save_annotation_file(target_image, &target_file)?;
+
+    Ok(PropagationResult {
+        annotations: target_file.annotations,
+        propagated_count,
+        matched_count,
+        new_track_count,
+        skipped_count,
+    })
+}
+
+/// 关键帧插值结果
+#[derive(Debug, Serialize)]
+pub struct InterpolationResult {
+    pub frame_count: usize,        // 两个关键帧之间的帧数
+    pub track_ids: Vec<u64>,       // 插值的跟踪ID
+    pub interpolated_count: usize, // 生成的标注数
+    pub skipped_count: usize,      // 中间帧已有人工标注而跳过的标注数
+}
+
+/// 角度差归一化到 (-π, π]，使旋转沿较短方向插值
+fn shortest_angle(delta: f64) -> f64 {
+    let two_pi = std::f64::consts::TAU;
+    let wrapped = delta.rem_euclid(two_pi);
+    if wrapped > std::f64::consts::PI {
+        wrapped - two_pi
+    } else {
+        wrapped
+    }
+}
+
+/// 在两个标注之间插值，t为0到1之间的比例
+/// 按中心点、宽高和旋转角插值，旋转框绕中心旋转因此不会因角度变化而漂移；
+/// 多边形顶点数一致时逐点插值，否则返回None
+fn interpolate_annotation(
+    start: &AnnotationData,
+    end: &AnnotationData,
+    t: f64,
+) -> Option<AnnotationData> {
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    let mut annotation = new_instance(start);
+    annotation.interpolated = true;
+
+    if start.annotation_type == "polygon" || end.annotation_type == "polygon" {
+        let (Some(start_points), Some(end_points)) = (&start.points, &end.points) else {
+            return None;
+        };
+        if start.annotation_type != end.annotation_type || start_points.len() != end_points.len() {
+            return None;
+        }
+        let points: Vec<[f64; 2]> = start_points
+            .iter()
+            .zip(end_points)
+            .map(|(a, b)| [lerp(a[0], b[0]), lerp(a[1], b[1])])
+            .collect();
+        let (x_min, y_min, x_max, y_max) = points.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(x0, y0, x1, y1), p| (x0.min(p[0]), y0.min(p[1]), x1.max(p[0]), y1.max(p[1])),
+        );
+        annotation.x = x_min;
+        annotation.y = y_min;
+        annotation.width = x_max - x_min;
+        annotation.height = y_max - y_min;
+        annotation.points = Some(points);
+        return Some(annotation);
+    }
+
+    let (start_cx, start_cy) = annotation_center(start);
+    let (end_cx, end_cy) = annotation_center(end);
+    let width = lerp(start.width, end.width);
+    let height = lerp(start.height, end.height);
+    annotation.x = lerp(start_cx, end_cx) - width / 2.0;
+    annotation.y = lerp(start_cy, end_cy) - height / 2.0;
+    annotation.width = width;
+    annotation.height = height;
+
+    if start.annotation_type == "rotated-rectangle" || end.annotation_type == "rotated-rectangle" {
+        let start_rotation = start.rotation.unwrap_or(0.0);
+        let end_rotation = end.rotation.unwrap_or(0.0);
+        annotation.annotation_type = "rotated-rectangle".to_string();
+        annotation.rotation =
+            Some(start_rotation + shortest_angle(end_rotation - start_rotation) * t);
+    }
+
+    Some(annotation)
+}
+
+/// 帧在视频中的序号：标注文件记录了视频来源时使用原始帧序号，否则使用文件夹中的排序位置
+fn frame_position(path: &Path, sorted_index: usize) -> f64 {
+    load_annotation_file(&path.to_string_lossy())
+        .ok()
+        .flatten()
+        .and_then(|file| file.source_video)
+        .map(|source| source.frame_index as f64)
+        .unwrap_or(sorted_index as f64)
+}
+
+/// 在两个关键帧之间插值
+/// 两个关键帧中跟踪ID相同的标注在中间各帧（同一文件夹中按文件名排序）生成插值标注，
+/// 插值标注标记为interpolated；重新插值时替换之前的插值结果，不覆盖人工标注
+#[tauri::command]
+pub async fn interpolate_keyframes(
+    start_image: String,
+    end_image: String,
+    track_ids: Option<Vec<u64>>,
+) -> Result<InterpolationResult, AppError> {
+    tokio::task::spawn_blocking(move || {
+        interpolate_keyframes_internal(&start_image, &end_image, track_ids.as_deref())
+    })
+    .await
+    .map_err(|e| AppError {
+        message: e.to_string(),
+    })?
+}
+
+/// 内部辅助函数：插值并写入中间帧的标注文件
+pub fn interpolate_keyframes_internal(
+    start_image: &str,
+    end_image: &str,
+    track_ids: Option<&[u64]>,
+) -> Result<InterpolationResult, AppError> {
+    let folder = Path::new(start_image)
+        .parent()
+        .unwrap_or_else(|| Path::new("."));
+    if Path::new(end_image)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        != folder
+    {
+        return Err(AppError {
+            message: "两个关键帧必须在同一文件夹中".to_string(),
+        });
+    }
+
+    let frames = sequence_frames(folder)?;
+
+    let position_of = |path: &str| {
+        let file_name = Path::new(path).file_name();
+        frames.iter().position(|p| p.file_name() == file_name)
+    };
+    let (Some(first), Some(second)) = (position_of(start_image), position_of(end_image)) else {
+        return Err(AppError {
+            message: "关键帧不在文件夹的图片列表中".to_string(),
+        });
+    };
+    // 允许倒序传入关键帧
+    let (start_index, end_index) = (first.min(second), first.max(second));
+
+    let load = |path: &Path| {
+        let path = path.to_string_lossy();
+        load_annotation_file(&path)?.ok_or_else(|| AppError {
+            message: format!("Annotation file does not exist for: {}", path),
+        })
+    };
+    let start_file = load(&frames[start_index])?;
+    let end_file = load(&frames[end_index])?;
+
+    // 两个关键帧中都存在的跟踪ID
+    let end_tracks: HashMap<u64, &AnnotationData> = end_file
+        .annotations
+        .iter()
+        .filter_map(|a| a.track_id.map(|id| (id, a)))
+        .collect();
+    let pairs: Vec<(u64, &AnnotationData, &AnnotationData)> = start_file
+        .annotations
+        .iter()
+        .filter_map(|a| {
+            let id = a.track_id?;
+            let end = end_tracks.get(&id)?;
+            Some((id, a, *end))
+        })
+        .filter(|(id, _, _)| track_ids.is_none_or(|ids| ids.contains(id)))
+        .collect();
+    if pairs.is_empty() {
+        return Err(AppError {
+            message: "两个关键帧中没有相同跟踪ID的标注".to_string(),
+        });
+    }
+
+    let start_position = frame_position(&frames[start_index], start_index);
+    let end_position = frame_position(&frames[end_index], end_index);
+    let span = end_position - start_position;
+
+    let mut interpolated_count = 0;
+    let mut skipped_count = 0;
+    for (index, frame) in frames
+        .iter()
+        .enumerate()
+        .take(end_index)
+        .skip(start_index + 1)
+    {
+        let frame_str = frame.to_string_lossy().to_string();
+        let mut file = load_or_create(&frame_str)?;
+        let t = if span.abs() > f64::EPSILON {
+            ((frame_position(frame, index) - start_position) / span).clamp(0.0, 1.0)
+        } else {
+            (index - start_index) as f64 / (end_index - start_index) as f64
+        };
+
+        for (track_id, start, end) in &pairs {
+            // 已有人工标注的跟踪ID不覆盖，之前的插值结果替换
+            if file
+                .annotations
+                .iter()
+                .any(|a| a.track_id == Some(*track_id) && !a.interpolated)
+            {
+                skipped_count += 1;
+                continue;
+            }
+            file.annotations
+                .retain(|a| !(a.track_id == Some(*track_id) && a.interpolated));
+
+            if let Some(annotation) = interpolate_annotation(start, end, t) {
+                file.annotations.push(annotation);
+                interpolated_count += 1;
+            }
+        }
+
+        file.modified = chrono::Utc::now().to_rfc3339();
+        save_annotation_file(&frame_str, &file)?;
+    }
+
+    Ok(InterpolationResult {
+        frame_count: end_index - start_index - 1,
+        track_ids: pairs.iter().map(|(id, _, _)| *id).collect(),
+        interpolated_count,
+        skipped_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(track_id: u64) -> AnnotationData {
+        serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::new_v4().to_string(),
+            "type": "rectangle",
+            "x": 1.0,
+            "y": 1.0,
+            "width": 4.0,
+            "height": 4.0,
+            "label": "car",
+            "track_id": track_id,
+        }))
+        .unwrap()
+    }
+
+    fn rect(track_id: u64, x: f64, y: f64, width: f64, height: f64) -> AnnotationData {
+        AnnotationData {
+            x,
+            y,
+            width,
+            height,
+            ..tracked(track_id)
+        }
+    }
+
+    /// 创建序列文件夹：每帧一个空图片文件和给定的标注
+    fn sequence(frames: Vec<(&str, Vec<AnnotationData>)>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oria-tracking-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, annotations) in frames {
+            let image_path = dir.join(name);
+            std::fs::write(&image_path, b"").unwrap();
+            let mut file = AnnotationFile::new(name.to_string(), 100, 100);
+            file.annotations = annotations;
+            save_annotation_file(&image_path.to_string_lossy(), &file).unwrap();
+        }
+        dir
+    }
+
+    fn load(dir: &Path, name: &str) -> AnnotationFile {
+        load_annotation_file(&dir.join(name).to_string_lossy())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn shortest_angle_wraps_around() {
+        use std::f64::consts::{PI, TAU};
+
+        assert_close(shortest_angle(0.5), 0.5);
+        assert_close(shortest_angle(-0.5), -0.5);
+        // 从350°转到10°走+20°，而不是-340°
+        assert_close(shortest_angle(TAU - 0.1), -0.1);
+        assert_close(shortest_angle(0.1 - TAU), 0.1);
+        assert_close(shortest_angle(3.0 * TAU + 0.2), 0.2);
+        assert_close(shortest_angle(PI), PI);
+        assert_close(shortest_angle(-PI), PI);
+    }
+
+    #[test]
+    fn rotation_interpolates_across_the_wraparound() {
+        use std::f64::consts::PI;
+
+        let start = AnnotationData {
+            annotation_type: "rotated-rectangle".to_string(),
+            rotation: Some(PI - 0.2),
+            ..rect(1, 0.0, 0.0, 10.0, 10.0)
+        };
+        let end = AnnotationData {
+            rotation: Some(-PI + 0.2),
+            ..start.clone()
+        };
+
+        // 中点是π而不是0
+        let middle = interpolate_annotation(&start, &end, 0.5).unwrap();
+        assert_close(middle.rotation.unwrap(), PI);
+        let quarter = interpolate_annotation(&start, &end, 0.25).unwrap();
+        assert_close(quarter.rotation.unwrap(), PI - 0.1);
+    }
+
+    #[test]
+    fn rectangle_interpolates_linearly_between_keyframes() {
+        let start = rect(4, 0.0, 0.0, 10.0, 10.0);
+        let end = rect(4, 20.0, 10.0, 20.0, 30.0);
+
+        let middle = interpolate_annotation(&start, &end, 0.5).unwrap();
+        assert!(middle.interpolated);
+        assert_eq!(middle.track_id, Some(4));
+        assert_ne!(middle.id, start.id);
+        // 中心从(5, 5)移动到(30, 25)，尺寸从10x10变为20x30
+        assert_close(middle.width, 15.0);
+        assert_close(middle.height, 20.0);
+        assert_close(middle.x, 10.0);
+        assert_close(middle.y, 5.0);
+        assert_eq!(middle.rotation, None);
+    }
+
+    #[test]
+    fn keyframe_interpolation_fills_frames_and_keeps_manual_annotations() {
+        let dir = sequence(vec![
+            (
+                "f1.jpg",
+                vec![rect(1, 0.0, 0.0, 10.0, 10.0), rect(2, 0.0, 0.0, 4.0, 4.0)],
+            ),
+            ("f2.jpg", vec![]),
+            ("f3.jpg", vec![rect(2, 50.0, 50.0, 4.0, 4.0)]),
+            (
+                "f10.jpg",
+                vec![rect(1, 30.0, 0.0, 10.0, 10.0), rect(2, 0.0, 0.0, 4.0, 4.0)],
+            ),
+        ]);
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        // 倒序传入关键帧，f10按自然排序在f3之后
+        let result =
+            interpolate_keyframes_internal(&path("f10.jpg"), &path("f1.jpg"), None).unwrap();
+        assert_eq!(result.frame_count, 2);
+        assert_eq!(result.interpolated_count, 3);
+        assert_eq!(result.skipped_count, 1);
+
+        let f2 = load(&dir, "f2.jpg");
+        let track_1 = f2
+            .annotations
+            .iter()
+            .find(|a| a.track_id == Some(1))
+            .unwrap();
+        assert!(track_1.interpolated);
+        assert_close(track_1.x, 10.0);
+        let f3 = load(&dir, "f3.jpg");
+        let manual = f3
+            .annotations
+            .iter()
+            .find(|a| a.track_id == Some(2))
+            .unwrap();
+        assert!(!manual.interpolated);
+        assert_close(manual.x, 50.0);
+        assert_close(
+            f3.annotations
+                .iter()
+                .find(|a| a.track_id == Some(1))
+                .unwrap()
+                .x,
+            20.0,
+        );
+
+        // 重新插值替换之前的插值结果
+        interpolate_keyframes_internal(&path("f1.jpg"), &path("f10.jpg"), Some(&[1])).unwrap();
+        assert_eq!(load(&dir, "f2.jpg").annotations.len(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keyframe_interpolation_uses_source_video_frame_indices() {
+        let dir = sequence(vec![
+            ("a.jpg", vec![rect(1, 0.0, 0.0, 10.0, 10.0)]),
+            ("b.jpg", vec![]),
+            ("c.jpg", vec![rect(1, 40.0, 0.0, 10.0, 10.0)]),
+        ]);
+        // 抽帧间隔不均匀：第0、10、40帧
+        for (name, frame_index) in [("a.jpg", 0), ("b.jpg", 10), ("c.jpg", 40)] {
+            let mut file = load(&dir, name);
+            file.source_video = Some(crate::models::VideoSource {
+                video_path: "clip.mp4".to_string(),
+                frame_index,
+                timestamp_ms: frame_index as f64 * 40.0,
+            });
+            save_annotation_file(&dir.join(name).to_string_lossy(), &file).unwrap();
+        }
+
+        interpolate_keyframes_internal(
+            &dir.join("a.jpg").to_string_lossy(),
+            &dir.join("c.jpg").to_string_lossy(),
+            None,
+        )
+        .unwrap();
+        // t = 10 / 40，而不是按文件夹位置的 1 / 2
+        let b = load(&dir, "b.jpg");
+        assert_eq!(b.annotations.len(), 1);
+        assert_close(b.annotations[0].x, 10.0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn motion_propagation_extrapolates_and_assigns_track_ids() {
+        let mut untracked = rect(0, 60.0, 60.0, 5.0, 5.0);
+        untracked.track_id = None;
+        let dir = sequence(vec![
+            ("1.jpg", vec![rect(1, 0.0, 0.0, 10.0, 10.0)]),
+            ("2.jpg", vec![rect(1, 5.0, 2.0, 12.0, 10.0), untracked]),
+            ("3.jpg", vec![]),
+        ]);
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        let result = propagate_annotations_internal(
+            &path("2.jpg"),
+            &path("3.jpg"),
+            &PropagationMode::Motion {
+                previous_image: Some(path("1.jpg")),
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.propagated_count, 2);
+        assert_eq!(result.new_track_count, 1);
+
+        // 源帧中没有跟踪ID的标注分配了新ID并写回
+        let source = load(&dir, "2.jpg");
+        assert!(source.annotations.iter().all(|a| a.track_id.is_some()));
+        let target = load(&dir, "3.jpg");
+        let moved = target
+            .annotations
+            .iter()
+            .find(|a| a.track_id == Some(1))
+            .unwrap();
+        assert_close(moved.x, 10.0);
+        assert_close(moved.y, 4.0);
+        assert_close(moved.width, 14.0);
+        let copied = target
+            .annotations
+            .iter()
+            .find(|a| a.track_id == Some(2))
+            .unwrap();
+        assert_close(copied.x, 60.0);
+
+        // 目标帧已有的跟踪ID不重复传播
+        let again = propagate_annotations_internal(
+            &path("2.jpg"),
+            &path("3.jpg"),
+            &PropagationMode::Motion {
+                previous_image: None,
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(again.propagated_count, 0);
+        assert_eq!(again.skipped_count, 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn detection_propagation_matches_by_iou() {
+        let dir = sequence(vec![
+            ("1.jpg", vec![rect(3, 0.0, 0.0, 10.0, 10.0)]),
+            ("2.jpg", vec![]),
+        ]);
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let detection = |x: f64| {
+            let mut annotation = rect(0, x, 0.0, 10.0, 10.0);
+            annotation.track_id = None;
+            annotation.label = "vehicle".to_string();
+            annotation
+        };
+        let mode: PropagationMode = serde_json::from_value(serde_json::json!({
+            "type": "detections",
+            "config": {
+                "mode": {"type": "api", "base_url": "", "conf_threshold": 0.5, "iou_threshold": 0.5},
+                "count": {"type": "all"},
+            },
+            "keep_unmatched": true,
+        }))
+        .unwrap();
+
+        let result = propagate_annotations_internal(
+            &path("1.jpg"),
+            &path("2.jpg"),
+            &mode,
+            Some(vec![detection(80.0), detection(1.0)]),
+        )
+        .unwrap();
+        assert_eq!(result.matched_count, 1);
+        assert_eq!(result.new_track_count, 1);
+
+        // 匹配的检测继承源标注的跟踪ID和标签，未匹配的检测分配新ID
+        let matched = result
+            .annotations
+            .iter()
+            .find(|a| a.track_id == Some(3))
+            .unwrap();
+        assert_close(matched.x, 1.0);
+        assert_eq!(matched.label, "car");
+        let unmatched = result
+            .annotations
+            .iter()
+            .find(|a| a.track_id == Some(4))
+            .unwrap();
+        assert_close(unmatched.x, 80.0);
+        assert_eq!(unmatched.label, "vehicle");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn next_track_id_follows_saved_annotations() {
+        let dir = std::env::temp_dir().join(format!("oria-tracking-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let save = |name: &str, track_id: u64| {
+            let image_path = dir.join(name);
+            std::fs::write(&image_path, b"").unwrap();
+            let mut file = AnnotationFile::new(name.to_string(), 10, 10);
+            file.annotations.push(tracked(track_id));
+            save_annotation_file(&image_path.to_string_lossy(), &file).unwrap();
+        };
+
+        save("000001.jpg", 3);
+        assert_eq!(next_track_id(&dir).unwrap(), 4);
+
+        // 扫描后保存的标注直接更新缓存的最大值
+        save("000002.jpg", 9);
+        assert_eq!(next_track_id(&dir).unwrap(), 10);
+        save("000003.jpg", 5);
+        assert_eq!(next_track_id(&dir).unwrap(), 10);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,861 @@
+//! 视频抽帧
+//!
+//! 按固定步长或场景变化从视频中抽取帧，以稳定的帧序号命名保存为图片，
+//! 并在每帧的标注文件中记录来源视频和时间戳。
+//! GIF使用image库解码（纯Rust）；其他格式通过随应用打包的ffmpeg/ffprobe子进程解码为RGB原始帧，
+//! 时间戳取自解码器输出的pts（可变帧率视频也准确）
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::JoinHandle;
+
+use image::codecs::gif::GifDecoder;
+use image::imageops::FilterType;
+use image::{AnimationDecoder, DynamicImage, Frames, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use super::annotation::{load_annotation_file, save_annotation_file};
+use crate::error::AppError;
+use crate::models::{AnnotationFile, VideoSource};
+use crate::utils::is_frame_source_file;
+
+/// 场景变化检测使用的灰度缩略图尺寸
+const SCENE_THUMB_WIDTH: u32 = 64;
+const SCENE_THUMB_HEIGHT: u32 = 36;
+
+/// 错误信息中保留的ffmpeg日志行数
+const FFMPEG_LOG_LINES: usize = 20;
+
+/// 抽帧方式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ExtractionMode {
+    /// 每隔every_n帧抽取一帧（从第0帧开始）
+    Stride { every_n: u64 },
+    /// 与上一抽取帧的平均灰度差（0-255）超过阈值时抽取，两次抽取至少间隔min_interval帧
+    SceneChange {
+        threshold: f64,
+        #[serde(default)]
+        min_interval: u64,
+    },
+}
+
+/// 抽帧配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameExtractionConfig {
+    pub video_path: String,
+    pub output_folder: String, // 帧图片和标注文件的输出目录
+    pub mode: ExtractionMode,
+    #[serde(default)]
+    pub max_frames: Option<usize>, // 最多抽取的帧数
+    #[serde(default)]
+    pub ffmpeg_path: Option<String>, // ffmpeg可执行文件路径，默认使用随应用打包的ffmpeg，没有时从PATH查找
+    #[serde(default = "default_image_format")]
+    pub image_format: String, // "jpg" 或 "png"
+}
+
+fn default_image_format() -> String {
+    "jpg".to_string()
+}
+
+/// 抽帧结果
+#[derive(Debug, Serialize)]
+pub struct FrameExtractionResult {
+    pub video_path: String,
+    pub output_folder: String,
+    pub frame_paths: Vec<String>,
+    pub decoded_frames: u64, // 解码的总帧数
+    pub extracted_count: usize,
+    pub width: u32,
+    pub height: u32,
+    pub warnings: Vec<String>, // 不影响已抽取帧的问题（如解码的帧数少于容器记录的帧数）
+}
+
+/// 解码后的视频帧
+struct DecodedFrame {
+    index: u64,
+    timestamp_ms: f64,
+    image: RgbImage,
+}
+
+/// 视频信息（ffprobe）
+struct VideoProbe {
+    width: u32,
+    height: u32,
+    fps: f64,
+    frame_count: u64, // 容器记录的帧数，没有时为读取到的数据包数
+}
+
+/// 视频帧来源
+enum FrameReader {
+    Gif {
+        frames: Frames<'static>,
+        index: u64,
+        elapsed_ms: f64,
+    },
+    Ffmpeg {
+        child: Child,
+        stdout: ChildStdout,
+        timestamps: Receiver<Option<f64>>, // showinfo输出的每帧pts（秒）
+        log: Option<JoinHandle<Vec<String>>>, // ffmpeg的错误日志
+        probe: VideoProbe,
+        index: u64,
+        warnings: Vec<String>,
+    },
+}
+
+/// 是否为GIF动图（图片格式，也可以通过抽帧按帧拆分）
+fn is_gif(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("gif"))
+}
+
+/// 随应用打包的可执行文件（Tauri externalBin放在主程序同目录）
+fn bundled_tool(name: &str) -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let path = exe
+        .parent()?
+        .join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+    path.is_file().then_some(path)
+}
+
+/// ffmpeg和ffprobe的路径：指定ffmpeg时使用同目录的ffprobe，否则优先使用随应用打包的版本，最后从PATH查找
+fn ffmpeg_tools(ffmpeg_path: Option<&str>) -> (PathBuf, PathBuf) {
+    if let Some(ffmpeg) = ffmpeg_path {
+        let ffmpeg = PathBuf::from(ffmpeg);
+        let ffprobe = match ffmpeg.file_name().and_then(|n| n.to_str()) {
+            Some(name) if name.contains("ffmpeg") => {
+                ffmpeg.with_file_name(name.replacen("ffmpeg", "ffprobe", 1))
+            }
+            _ => PathBuf::from("ffprobe"),
+        };
+        return (ffmpeg, ffprobe);
+    }
+    let find = |name: &str| bundled_tool(name).unwrap_or_else(|| PathBuf::from(name));
+    (find("ffmpeg"), find("ffprobe"))
+}
+
+/// 从showinfo日志行中取出pts_time，如 "[Parsed_showinfo_0 @ 0x..] [info] n:   3 pts:  3003 pts_time:0.1001 ..."
+fn parse_showinfo_pts(line: &str) -> Option<Option<f64>> {
+    if !line.contains("Parsed_showinfo") {
+        return None;
+    }
+    let value = line.split_once("pts_time:")?.1.split_whitespace().next()?;
+    Some(value.parse().ok())
+}
+
+/// 读取ffmpeg的stderr：每帧的pts发送到通道，返回错误日志
+fn read_ffmpeg_log(
+    stderr: impl Read + Send + 'static,
+    timestamps: mpsc::Sender<Option<f64>>,
+) -> JoinHandle<Vec<String>> {
+    std::thread::spawn(move || {
+        let mut errors = Vec::new();
+        for line in BufReader::new(stderr).lines() {
+            let Ok(line) = line else { break };
+            if let Some(pts) = parse_showinfo_pts(&line) {
+                let _ = timestamps.send(pts);
+            } else if line.contains("[error]") || line.contains("[fatal]") {
+                if errors.len() == FFMPEG_LOG_LINES {
+                    errors.remove(0);
+                }
+                errors.push(line.trim().to_string());
+            }
+        }
+        errors
+    })
+}
+
+impl FrameReader {
+    /// 打开视频
+    fn open(video_path: &Path, ffmpeg_path: Option<&str>) -> Result<Self, AppError> {
+        if is_gif(video_path) {
+            let decoder = GifDecoder::new(BufReader::new(File::open(video_path)?))?;
+            return Ok(FrameReader::Gif {
+                frames: decoder.into_frames(),
+                index: 0,
+                elapsed_ms: 0.0,
+            });
+        }
+
+        let (ffmpeg, ffprobe) = ffmpeg_tools(ffmpeg_path);
+        let probe = probe_video(video_path, &ffprobe)?;
+
+        // showinfo在日志中输出每帧的pts；passthrough保证不为凑帧率而复制或丢弃帧；
+        // noautorotate使输出保持ffprobe报告的宽高（手机视频带旋转信息时自动旋转会交换宽高，帧数据错位）
+        let mut child = Command::new(&ffmpeg)
+            .args([
+                "-hide_banner",
+                "-nostdin",
+                "-nostats",
+                "-loglevel",
+                "level+info",
+                "-noautorotate",
+                "-i",
+            ])
+            .arg(video_path)
+            .args([
+                "-map",
+                "0:v:0",
+                "-vf",
+                "showinfo",
+                "-fps_mode",
+                "passthrough",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgb24",
+                "-",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| AppError {
+                message: format!("无法启动ffmpeg（{}）: {}", ffmpeg.display(), e),
+            })?;
+        let stdout = child.stdout.take().ok_or_else(|| AppError {
+            message: "无法读取ffmpeg输出".to_string(),
+        })?;
+        let stderr = child.stderr.take().ok_or_else(|| AppError {
+            message: "无法读取ffmpeg日志".to_string(),
+        })?;
+        let (sender, timestamps) = mpsc::channel();
+        let log = read_ffmpeg_log(stderr, sender);
+
+        Ok(FrameReader::Ffmpeg {
+            child,
+            stdout,
+            timestamps,
+            log: Some(log),
+            probe,
+            index: 0,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// 解码过程中的警告
+    fn warnings(&self) -> &[String] {
+        match self {
+            FrameReader::Gif { .. } => &[],
+            FrameReader::Ffmpeg { warnings, .. } => warnings,
+        }
+    }
+
+    /// 读取下一帧，视频结束时返回None
+    fn next_frame(&mut self) -> Result<Option<DecodedFrame>, AppError> {
+        match self {
+            FrameReader::Gif {
+                frames,
+                index,
+                elapsed_ms,
+            } => {
+                let frame = match frames.next() {
+                    Some(frame) => frame?,
+                    None => return Ok(None),
+                };
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let decoded = DecodedFrame {
+                    index: *index,
+                    timestamp_ms: *elapsed_ms,
+                    image: DynamicImage::ImageRgba8(frame.into_buffer()).to_rgb8(),
+                };
+                *index += 1;
+                *elapsed_ms += numer as f64 / denom.max(1) as f64;
+                Ok(Some(decoded))
+            }
+            FrameReader::Ffmpeg {
+                child,
+                stdout,
+                timestamps,
+                log,
+                probe,
+                index,
+                warnings,
+            } => {
+                let mut buffer = vec![0u8; probe.width as usize * probe.height as usize * 3];
+                let mut filled = 0;
+                while filled < buffer.len() {
+                    match stdout.read(&mut buffer[filled..]) {
+                        Ok(0) => break,
+                        Ok(n) => filled += n,
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                if filled < buffer.len() {
+                    warnings.extend(finish_ffmpeg(child, log, probe, *index, filled > 0)?);
+                    return Ok(None);
+                }
+
+                let image =
+                    RgbImage::from_raw(probe.width, probe.height, buffer).ok_or_else(|| {
+                        AppError {
+                            message: "ffmpeg输出的帧大小不正确".to_string(),
+                        }
+                    })?;
+                // pts缺失（NOPTS）时按平均帧率估算
+                let timestamp_ms = match timestamps.recv() {
+                    Ok(Some(pts)) => pts * 1000.0,
+                    Ok(None) => *index as f64 * 1000.0 / probe.fps,
+                    Err(_) => {
+                        return Err(AppError {
+                            message: format!("ffmpeg没有输出第 {} 帧的时间戳", index),
+                        })
+                    }
+                };
+                let decoded = DecodedFrame {
+                    index: *index,
+                    timestamp_ms,
+                    image,
+                };
+                *index += 1;
+                Ok(Some(decoded))
+            }
+        }
+    }
+}
+
+/// ffmpeg输出结束：检查退出状态和解码的帧数，失败时返回带ffmpeg错误日志的错误。
+/// 容器记录的帧数可能多于实际可解码的帧数（编辑列表、末尾的B帧），
+/// 因此帧数不足只在ffmpeg报告了错误（如文件被截断）时视为失败，否则作为警告返回
+fn finish_ffmpeg(
+    child: &mut Child,
+    log: &mut Option<JoinHandle<Vec<String>>>,
+    probe: &VideoProbe,
+    decoded: u64,
+    partial_frame: bool,
+) -> Result<Option<String>, AppError> {
+    let status = child.wait()?;
+    let errors = log
+        .take()
+        .and_then(|log| log.join().ok())
+        .unwrap_or_default();
+    let with_log = |message: String| AppError {
+        message: if errors.is_empty() {
+            message
+        } else {
+            format!("{}: {}", message, errors.join("; "))
+        },
+    };
+
+    if !status.success() {
+        return Err(with_log(format!("ffmpeg解码失败（{}）", status)));
+    }
+    if partial_frame {
+        return Err(with_log(format!("ffmpeg输出的第 {} 帧不完整", decoded)));
+    }
+    if decoded < probe.frame_count {
+        let message = format!(
+            "只解码了 {} 帧，视频容器记录为 {} 帧",
+            decoded, probe.frame_count
+        );
+        if !errors.is_empty() {
+            return Err(with_log(message));
+        }
+        return Ok(Some(message));
+    }
+    Ok(None)
+}
+
+impl Drop for FrameReader {
+    fn drop(&mut self) {
+        // 提前结束时终止ffmpeg进程
+        if let FrameReader::Ffmpeg { child, log, .. } = self {
+            let _ = child.kill();
+            let _ = child.wait();
+            if let Some(log) = log.take() {
+                let _ = log.join();
+            }
+        }
+    }
+}
+
+/// 使用ffprobe读取视频宽高、帧率和帧数
+fn probe_video(video_path: &Path, ffprobe: &Path) -> Result<VideoProbe, AppError> {
+    let output = Command::new(ffprobe)
+        .args([
+            "-v",
+            "error",
+            "-count_packets",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height,avg_frame_rate,nb_frames,nb_read_packets",
+            "-of",
+            "default=noprint_wrappers=1",
+        ])
+        .arg(video_path)
+        .output()
+        .map_err(|e| AppError {
+            message: format!("无法启动ffprobe（{}）: {}", ffprobe.display(), e),
+        })?;
+    if !output.status.success() {
+        return Err(AppError {
+            message: format!(
+                "ffprobe无法读取视频: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    // 输出形如 "width=1920\nheight=1080\navg_frame_rate=30000/1001\nnb_frames=N/A\nnb_read_packets=300"
+    let text = String::from_utf8_lossy(&output.stdout);
+    let field = |key: &str| {
+        text.lines()
+            .filter_map(|line| line.trim().split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    };
+    let parse_error = || AppError {
+        message: format!("无法解析ffprobe输出: {}", text.trim()),
+    };
+    let number = |key: &str| field(key).and_then(|value| value.parse::<u64>().ok());
+
+    let width = number("width").ok_or_else(parse_error)? as u32;
+    let height = number("height").ok_or_else(parse_error)? as u32;
+    let fps = match field("avg_frame_rate").map(|value| value.split_once('/')) {
+        Some(Some((numer, denom))) => {
+            let numer: f64 = numer.parse().map_err(|_| parse_error())?;
+            let denom: f64 = denom.parse().map_err(|_| parse_error())?;
+            if denom > 0.0 {
+                numer / denom
+            } else {
+                0.0
+            }
+        }
+        Some(None) => field("avg_frame_rate")
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(parse_error)?,
+        None => return Err(parse_error()),
+    };
+    if width == 0 || height == 0 || fps <= 0.0 {
+        return Err(parse_error());
+    }
+    let frame_count = number("nb_frames")
+        .or_else(|| number("nb_read_packets"))
+        .unwrap_or(0);
+
+    Ok(VideoProbe {
+        width,
+        height,
+        fps,
+        frame_count,
+    })
+}
+
+/// 场景变化检测用的灰度缩略图
+fn scene_thumbnail(image: &RgbImage) -> Vec<u8> {
+    image::imageops::resize(
+        image,
+        SCENE_THUMB_WIDTH,
+        SCENE_THUMB_HEIGHT,
+        FilterType::Triangle,
+    )
+    .pixels()
+    .map(|p| ((p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000) as u8)
+    .collect()
+}
+
+/// 两张缩略图的平均绝对差
+fn mean_difference(a: &[u8], b: &[u8]) -> f64 {
+    let total: u64 = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (*x as i32 - *y as i32).unsigned_abs() as u64)
+        .sum();
+    total as f64 / a.len().max(1) as f64
+}
+
+/// 帧图片文件名：{视频名}_{帧序号}.{格式}，重复抽取同一视频时文件名保持不变
+fn frame_file_name(video_path: &Path, frame_index: u64, image_format: &str) -> String {
+    let stem = video_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "video".to_string());
+    format!("{}_{:06}.{}", stem, frame_index, image_format)
+}
+
+/// 从视频中抽取帧
+#[tauri::command]
+pub async fn extract_video_frames(
+    config: FrameExtractionConfig,
+) -> Result<FrameExtractionResult, AppError> {
+    tokio::task::spawn_blocking(move || extract_video_frames_internal(&config))
+        .await
+        .map_err(|e| AppError {
+            message: e.to_string(),
+        })?
+}
+
+/// 内部辅助函数：抽取帧并写入帧图片和标注文件
+pub fn extract_video_frames_internal(
+    config: &FrameExtractionConfig,
+) -> Result<FrameExtractionResult, AppError> {
+    let video_path = Path::new(&config.video_path);
+    if !video_path.exists() {
+        return Err(AppError {
+            message: format!("Video file does not exist: {}", config.video_path),
+        });
+    }
+    if !is_frame_source_file(video_path) {
+        return Err(AppError {
+            message: format!("不支持的视频格式: {}", config.video_path),
+        });
+    }
+    let image_format = config.image_format.to_lowercase();
+    let image_format = match image_format.as_str() {
+        "jpg" | "jpeg" => "jpg",
+        "png" => "png",
+        other => {
+            return Err(AppError {
+                message: format!("不支持的帧图片格式: {}", other),
+            })
+        }
+    };
+    if let ExtractionMode::Stride { every_n: 0 } = config.mode {
+        return Err(AppError {
+            message: "抽帧步长必须大于0".to_string(),
+        });
+    }
+
+    let output_folder = PathBuf::from(&config.output_folder);
+    std::fs::create_dir_all(&output_folder)?;
+
+    // 标注文件中记录视频的绝对路径，便于导出时按视频分组
+    let source_path = video_path
+        .canonicalize()
+        .unwrap_or_else(|_| video_path.to_path_buf())
+        .to_string_lossy()
+        .to_string();
+
+    let mut reader = FrameReader::open(video_path, config.ffmpeg_path.as_deref())?;
+    let max_frames = config.max_frames.unwrap_or(usize::MAX);
+
+    let mut frame_paths = Vec::new();
+    let mut decoded_frames = 0u64;
+    let mut last_extracted: Option<(u64, Vec<u8>)> = None;
+    let (mut width, mut height) = (0, 0);
+
+    while frame_paths.len() < max_frames {
+        let Some(frame) = reader.next_frame()? else {
+            break;
+        };
+        decoded_frames += 1;
+        (width, height) = frame.image.dimensions();
+
+        let thumbnail = match config.mode {
+            ExtractionMode::Stride { every_n } => {
+                if frame.index % every_n != 0 {
+                    continue;
+                }
+                None
+            }
+            ExtractionMode::SceneChange {
+                threshold,
+                min_interval,
+            } => {
+                let thumbnail = scene_thumbnail(&frame.image);
+                if let Some((last_index, last_thumbnail)) = &last_extracted {
+                    if frame.index - last_index < min_interval
+                        || mean_difference(&thumbnail, last_thumbnail) < threshold
+                    {
+                        continue;
+                    }
+                }
+                Some(thumbnail)
+            }
+        };
+
+        let file_name = frame_file_name(video_path, frame.index, image_format);
+        let frame_path = output_folder.join(&file_name);
+        let frame_path_str = frame_path.to_string_lossy().to_string();
+        DynamicImage::ImageRgb8(frame.image).save(&frame_path)?;
+
+        // 保留已有标注，只更新尺寸和来源
+        let mut annotation_file = load_annotation_file(&frame_path_str)?
+            .unwrap_or_else(|| AnnotationFile::new(file_name.clone(), width, height));
+        annotation_file.image_path = file_name;
+        annotation_file.image_width = width;
+        annotation_file.image_height = height;
+        annotation_file.source_video = Some(VideoSource {
+            video_path: source_path.clone(),
+            frame_index: frame.index,
+            timestamp_ms: frame.timestamp_ms,
+        });
+        annotation_file.modified = chrono::Utc::now().to_rfc3339();
+        save_annotation_file(&frame_path_str, &annotation_file)?;
+
+        if let Some(thumbnail) = thumbnail {
+            last_extracted = Some((frame.index, thumbnail));
+        }
+        frame_paths.push(frame_path_str);
+    }
+
+    // println!("Extracted {} frames from {}", frame_paths.len(), config.video_path);
+
+    Ok(FrameExtractionResult {
+        video_path: source_path,
+        output_folder: config.output_folder.clone(),
+        extracted_count: frame_paths.len(),
+        frame_paths,
+        decoded_frames,
+        width,
+        height,
+        warnings: reader.warnings().to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba, RgbaImage};
+
+    /// 测试用的临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("oria-video-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 生成每帧100毫秒的GIF样例，帧颜色依次为colors
+    fn write_sample_gif(path: &Path, colors: &[[u8; 3]]) {
+        let mut encoder = GifEncoder::new(File::create(path).unwrap());
+        let frames = colors.iter().map(|[r, g, b]| {
+            Frame::from_parts(
+                RgbaImage::from_pixel(32, 24, Rgba([*r, *g, *b, 255])),
+                0,
+                0,
+                Delay::from_numer_denom_ms(100, 1),
+            )
+        });
+        encoder.encode_frames(frames).unwrap();
+    }
+
+    fn config(
+        video_path: &Path,
+        output_folder: &Path,
+        mode: ExtractionMode,
+    ) -> FrameExtractionConfig {
+        FrameExtractionConfig {
+            video_path: video_path.to_string_lossy().to_string(),
+            output_folder: output_folder.to_string_lossy().to_string(),
+            mode,
+            max_frames: None,
+            ffmpeg_path: None,
+            image_format: "png".to_string(),
+        }
+    }
+
+    /// 测试环境中是否有ffmpeg和ffprobe（没有时跳过需要ffmpeg的测试）
+    fn ffmpeg_available() -> bool {
+        let (ffmpeg, ffprobe) = ffmpeg_tools(None);
+        [ffmpeg, ffprobe].iter().all(|tool| {
+            Command::new(tool)
+                .arg("-version")
+                .output()
+                .is_ok_and(|output| output.status.success())
+        })
+    }
+
+    /// 用ffmpeg生成样例视频
+    fn write_sample_video(path: &Path, args: &[&str]) {
+        let status = Command::new(ffmpeg_tools(None).0)
+            .args([
+                "-v",
+                "error",
+                "-y",
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=size=64x48:rate=10",
+            ])
+            .args(args)
+            .arg(path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn gif_stride_extraction() {
+        let dir = temp_dir("gif-stride");
+        let video = dir.join("clip.gif");
+        write_sample_gif(
+            &video,
+            &[
+                [255, 0, 0],
+                [0, 255, 0],
+                [0, 0, 255],
+                [255, 255, 0],
+                [0, 255, 255],
+            ],
+        );
+
+        let output = dir.join("frames");
+        let result = extract_video_frames_internal(&config(
+            &video,
+            &output,
+            ExtractionMode::Stride { every_n: 2 },
+        ))
+        .unwrap();
+
+        assert_eq!(result.decoded_frames, 5);
+        assert_eq!(result.extracted_count, 3);
+        assert_eq!((result.width, result.height), (32, 24));
+        let names: Vec<String> = result
+            .frame_paths
+            .iter()
+            .map(|path| {
+                Path::new(path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            ["clip_000000.png", "clip_000002.png", "clip_000004.png"]
+        );
+
+        let annotation_file = load_annotation_file(&result.frame_paths[2])
+            .unwrap()
+            .unwrap();
+        let source = annotation_file.source_video.unwrap();
+        assert_eq!(source.frame_index, 4);
+        assert!((source.timestamp_ms - 400.0).abs() < 1e-6);
+        assert_eq!(source.video_path, result.video_path);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn gif_scene_change_extraction() {
+        let dir = temp_dir("gif-scene");
+        let video = dir.join("scene.gif");
+        write_sample_gif(
+            &video,
+            &[
+                [0, 0, 0],
+                [0, 0, 0],
+                [255, 255, 255],
+                [255, 255, 255],
+                [0, 0, 0],
+            ],
+        );
+
+        let result = extract_video_frames_internal(&config(
+            &video,
+            &dir.join("frames"),
+            ExtractionMode::SceneChange {
+                threshold: 30.0,
+                min_interval: 0,
+            },
+        ))
+        .unwrap();
+
+        let indices: Vec<u64> = result
+            .frame_paths
+            .iter()
+            .map(|path| {
+                load_annotation_file(path)
+                    .unwrap()
+                    .unwrap()
+                    .source_video
+                    .unwrap()
+                    .frame_index
+            })
+            .collect();
+        assert_eq!(indices, [0, 2, 4]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ffmpeg_timestamps_follow_pts() {
+        if !ffmpeg_available() {
+            eprintln!("ffmpeg not found, skipping");
+            return;
+        }
+        let dir = temp_dir("vfr");
+        let video = dir.join("vfr.mp4");
+        // 可变帧率：第N帧的pts为 N*N*10 毫秒
+        write_sample_video(
+            &video,
+            &[
+                "-frames:v",
+                "6",
+                "-vf",
+                "settb=1/1000,setpts=N*N*10",
+                "-fps_mode",
+                "passthrough",
+                "-c:v",
+                "mpeg4",
+            ],
+        );
+
+        let result = extract_video_frames_internal(&config(
+            &video,
+            &dir.join("frames"),
+            ExtractionMode::Stride { every_n: 1 },
+        ))
+        .unwrap();
+
+        assert_eq!(result.decoded_frames, 6);
+        for (n, path) in result.frame_paths.iter().enumerate() {
+            let source = load_annotation_file(path)
+                .unwrap()
+                .unwrap()
+                .source_video
+                .unwrap();
+            let expected = (n * n * 10) as f64;
+            assert!(
+                (source.timestamp_ms - expected).abs() <= 1.0,
+                "frame {}: {} ms, expected {} ms",
+                n,
+                source.timestamp_ms,
+                expected
+            );
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ffmpeg_truncated_video_is_an_error() {
+        if !ffmpeg_available() {
+            eprintln!("ffmpeg not found, skipping");
+            return;
+        }
+        let dir = temp_dir("truncated");
+        let video = dir.join("truncated.mp4");
+        write_sample_video(
+            &video,
+            &[
+                "-frames:v",
+                "30",
+                "-c:v",
+                "mpeg4",
+                "-movflags",
+                "+faststart",
+            ],
+        );
+        let content = std::fs::read(&video).unwrap();
+        std::fs::write(&video, &content[..content.len() / 2]).unwrap();
+
+        let result = extract_video_frames_internal(&config(
+            &video,
+            &dir.join("frames"),
+            ExtractionMode::Stride { every_n: 1 },
+        ));
+        assert!(result.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
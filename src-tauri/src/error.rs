@@ -35,3 +35,11 @@ impl From<image::ImageError> for AppError {
         }
     }
 }
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        AppError {
+            message: format!("Database Error: {}", error),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::annotation::load_annotation_file;
+use crate::core::scan::{relative_path, scan_images, ScanOptions};
+use crate::error::AppError;
+use crate::models::ImageTag;
+use crate::utils::csv_field;
+
+/// 分类数据集导出配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClassificationExportConfig {
+    pub export_path: String, // 导出目录路径
+    #[serde(default)]
+    pub all_tags: bool, // 是否按全部标签导出（默认只按第一个标签）
+}
+
+/// 分类数据集导出结果
+#[derive(Debug, Serialize)]
+pub struct ClassificationExportResult {
+    pub exported_count: usize,  // 复制的图片文件数
+    pub untagged_count: usize,  // 没有图片级标签的图片数
+    pub class_folders: Vec<String>,
+}
+
+/// 将标签转换为安全的文件夹名称
+fn sanitize_folder_name(label: &str) -> String {
+    let name: String = label
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    if name.is_empty() || name == "." || name == ".." {
+        "_".to_string()
+    } else {
+        name
+    }
+}
+
+/// 收集文件夹中图片及其图片级标签（按相对路径自然排序）
+fn collect_image_tags(
+    image_folder: &str,
+    options: &ScanOptions,
+) -> Result<Vec<(std::path::PathBuf, Vec<ImageTag>)>, AppError> {
+    let image_paths = scan_images(Path::new(image_folder), options)?;
+
+    let mut result = Vec::new();
+    for path in image_paths {
+        let tags = match load_annotation_file(&path.to_string_lossy()) {
+            Ok(file) => file.map(|f| f.tags).unwrap_or_default(),
+            Err(e) => {
+                println!(
+                    "Failed to load annotations for {}: {}",
+                    path.display(),
+                    e.message
+                );
+                Vec::new()
+            }
+        };
+        result.push((path, tags));
+    }
+
+    Ok(result)
+}
+
+/// 导出为分类数据集目录结构（export_path/<label>/<image>）
+/// options: 扫描选项，递归扫描时在类别目录中保留子目录结构
+#[tauri::command]
+pub async fn export_classification(
+    image_folder: String,
+    config: ClassificationExportConfig,
+    options: Option<ScanOptions>,
+) -> Result<ClassificationExportResult, AppError> {
+    let images = collect_image_tags(&image_folder, &options.unwrap_or_default())?;
+    std::fs::create_dir_all(&config.export_path)?;
+
+    let mut exported_count = 0;
+    let mut untagged_count = 0;
+    let mut class_folders: Vec<String> = Vec::new();
+
+    for (image_path, tags) in images {
+        if tags.is_empty() {
+            untagged_count += 1;
+            continue;
+        }
+
+        let labels: Vec<&ImageTag> = if config.all_tags {
+            tags.iter().collect()
+        } else {
+            tags.iter().take(1).collect()
+        };
+
+        // 相对路径保留子目录，不同子目录中的同名图片不会互相覆盖
+        let relative = relative_path(Path::new(&image_folder), &image_path);
+        for tag in labels {
+            let folder_name = sanitize_folder_name(&tag.label);
+            let target = Path::new(&config.export_path)
+                .join(&folder_name)
+                .join(&relative);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&image_path, target)?;
+            exported_count += 1;
+
+            if !class_folders.contains(&folder_name) {
+                class_folders.push(folder_name);
+            }
+        }
+    }
+
+    class_folders.sort_by(|a, b| natord::compare(a, b));
+
+    Ok(ClassificationExportResult {
+        exported_count,
+        untagged_count,
+        class_folders,
+    })
+}
+
+/// 导出图片级标签CSV清单（image,label,score，每个标签一行）
+/// image列为相对于图片文件夹的路径（/ 分隔）
+#[tauri::command]
+pub async fn export_tags_csv(
+    image_folder: String,
+    export_path: String,
+    options: Option<ScanOptions>,
+) -> Result<String, AppError> {
+    let images = collect_image_tags(&image_folder, &options.unwrap_or_default())?;
+
+    let mut lines = vec!["image,label,score".to_string()];
+    for (image_path, tags) in images {
+        let file_name = relative_path(Path::new(&image_folder), &image_path);
+        for tag in tags {
+            let score = tag.score.map(|s| format!("{:.6}", s)).unwrap_or_default();
+            lines.push(format!(
+                "{},{},{}",
+                csv_field(&file_name),
+                csv_field(&tag.label),
+                score
+            ));
+        }
+    }
+
+    let output_path = if Path::new(&export_path).is_dir() {
+        Path::new(&export_path).join("tags.csv")
+    } else {
+        Path::new(&export_path).to_path_buf()
+    };
+    std::fs::write(&output_path, lines.join("\n") + "\n")?;
+
+    Ok(output_path.to_string_lossy().to_string())
+}
@@ -1,12 +1,15 @@
 use image::GenericImageView;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use crate::annotation::{load_annotations_internal, save_annotations_internal};
+use crate::core::attributes::{retain_defined, AnnotationFilter, RESERVED_ATTRIBUTE_NAMES};
+use crate::core::geometry::polygon_area;
+use crate::core::scan::{relative_path, scan_images, ImageLocator, ScanOptions};
+use crate::core::taxonomy::{alias_map, resolve_classes};
 use crate::error::AppError;
 use crate::models::AnnotationData;
-use crate::utils::is_image_file;
 
 /// COCO格式数据结构
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,7 +32,7 @@ pub struct CocoAnnotation {
     pub id: u32,
     pub image_id: u32,
     pub category_id: u32,
-    pub segmentation: Vec<Vec<f64>>, // 用于旋转框的多边形表示，矩形框为空数组
+    pub segmentation: Vec<Vec<f64>>, // 多边形标注的顶点 [x1, y1, x2, y2, ...]，矩形框和旋转框为空数组
     pub area: f64,
     pub bbox: Vec<f64>, // [x, y, width, height]
     pub iscrowd: u8,
@@ -40,6 +43,15 @@ pub struct CocoAnnotation {
 pub struct CocoAttributes {
     pub occluded: bool,
     pub rotation: f64, // 旋转角度（在attributes中，单位：度）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<u64>, // 跟踪ID（CVAT视频标注同名属性）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<u64>, // 分组ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u32>, // 父标注的COCO标注ID
+    /// 项目定义的其他属性
+    #[serde(flatten)]
+    pub custom: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -50,8 +62,16 @@ pub struct CocoCategory {
 }
 
 /// 导出COCO格式
+/// options: 扫描选项，递归扫描时file_name为相对于图片文件夹的路径
+/// filter: 按类别和属性筛选导出的标注
 #[tauri::command]
-pub async fn export_coco(image_folder: String, export_path: String) -> Result<String, AppError> {
+pub async fn export_coco(
+    image_folder: String,
+    export_path: String,
+    options: Option<ScanOptions>,
+    filter: Option<AnnotationFilter>,
+) -> Result<String, AppError> {
+    let filter = filter.unwrap_or_default();
     // println!("Exporting COCO format from folder: {}", image_folder);
 
     let folder_path = Path::new(&image_folder);
@@ -61,143 +81,182 @@ pub async fn export_coco(image_folder: String, export_path: String) -> Result<St
         });
     }
 
+    // 定义了类别时按类别ID导出（COCO类别ID从1开始，为类别ID加1），未定义的标签排在后面
+    let classes = resolve_classes(&image_folder)?;
+    let aliases = alias_map(&classes);
+    let mut categories_map: HashMap<String, u32> = classes
+        .iter()
+        .map(|class| (class.name.clone(), class.id + 1))
+        .collect();
+    let supercategories: HashMap<String, String> = classes
+        .iter()
+        .filter_map(|c| c.supercategory.clone().map(|s| (c.name.clone(), s)))
+        .collect();
+
     let mut coco_images = Vec::new();
     let mut coco_annotations = Vec::new();
-    let mut categories_map: HashMap<String, u32> = HashMap::new();
     let mut image_id_counter = 1u32;
     let mut annotation_id_counter = 1u32;
-    let mut category_id_counter = 1u32;
+    let mut category_id_counter = categories_map.values().max().map_or(1, |max| max + 1);
     let mut has_rotated = false;
 
-    let entries = std::fs::read_dir(folder_path)?;
-    let mut _total_images = 0;
+    let images = scan_images(folder_path, &options.unwrap_or_default())?;
+    let _total_images = images.len();
     let mut _processed_images = 0;
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file() && is_image_file(&path) {
-            _total_images += 1;
-            let image_path_str = path.to_string_lossy().to_string();
-            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+    for path in images {
+        let image_path_str = path.to_string_lossy().to_string();
+        // 子目录中的图片使用相对路径
+        let file_name = relative_path(folder_path, &path);
 
-            // println!("Processing image: {}", file_name);
+        // println!("Processing image: {}", file_name);
 
-            let image_bytes = match std::fs::read(&path) {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    println!("Failed to read image {}: {}", file_name, e);
-                    continue;
-                }
-            };
-
-            let img = match image::load_from_memory(&image_bytes) {
-                Ok(img) => img,
-                Err(e) => {
-                    println!("Failed to load image {}: {}", file_name, e);
-                    continue;
-                }
-            };
+        let image_bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                println!("Failed to read image {}: {}", file_name, e);
+                continue;
+            }
+        };
 
-            let (img_width, img_height) = img.dimensions();
+        let img = match image::load_from_memory(&image_bytes) {
+            Ok(img) => img,
+            Err(e) => {
+                println!("Failed to load image {}: {}", file_name, e);
+                continue;
+            }
+        };
 
-            let annotations = match load_annotations_internal(&image_path_str) {
-                Ok(anns) => anns,
-                Err(e) => {
-                    println!(
-                        "Failed to load annotations for {}: {}",
-                        file_name, e.message
-                    );
-                    continue;
-                }
-            };
+        let (img_width, img_height) = img.dimensions();
 
-            if annotations.is_empty() {
-                // println!("No annotations found for: {}", file_name);
+        let annotations = match load_annotations_internal(&image_path_str) {
+            Ok(anns) => anns
+                .into_iter()
+                .filter(|annotation| filter.matches(annotation))
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                println!(
+                    "Failed to load annotations for {}: {}",
+                    file_name, e.message
+                );
                 continue;
             }
+        };
+
+        if annotations.is_empty() {
+            // println!("No annotations found for: {}", file_name);
+            continue;
+        }
 
-            //             println!("Found {} annotations for: {}", annotations.len(), file_name);
-            _processed_images += 1;
+        //             println!("Found {} annotations for: {}", annotations.len(), file_name);
+        _processed_images += 1;
 
-            for ann in &annotations {
-                if ann.annotation_type == "rotated-rectangle" {
-                    has_rotated = true;
-                    break;
-                }
+        for ann in &annotations {
+            if ann.annotation_type == "rotated-rectangle" {
+                has_rotated = true;
+                break;
             }
+        }
 
-            coco_images.push(CocoImage {
-                id: image_id_counter,
-                file_name: file_name.clone(),
-                width: img_width,
-                height: img_height,
-            });
+        coco_images.push(CocoImage {
+            id: image_id_counter,
+            file_name: file_name.clone(),
+            width: img_width,
+            height: img_height,
+        });
 
-            for annotation in annotations {
-                let category_id = if let Some(&id) = categories_map.get(&annotation.label) {
-                    id
-                } else {
-                    let id = category_id_counter;
-                    categories_map.insert(annotation.label.clone(), id);
-                    category_id_counter += 1;
-                    id
-                };
-
-                let bbox = vec![
-                    annotation.x,
-                    annotation.y,
-                    annotation.width,
-                    annotation.height,
-                ];
-
-                let area = annotation.width * annotation.height;
-
-                // 统一使用空的segmentation
-                let segmentation = vec![];
-
-                // 计算旋转角度（弧度转为度）
-                let rotation_degrees = if annotation.annotation_type == "rotated-rectangle" {
-                    let rot = annotation.rotation.unwrap_or(0.0);
-                    rot * 180.0 / std::f64::consts::PI
-                } else {
-                    0.0
-                };
-
-                let attributes = CocoAttributes {
-                    occluded: false,
-                    rotation: rotation_degrees,
-                };
-
-                coco_annotations.push(CocoAnnotation {
-                    id: annotation_id_counter,
-                    image_id: image_id_counter,
-                    category_id,
-                    segmentation,
-                    area,
-                    bbox,
-                    iscrowd: 0,
-                    attributes,
-                });
+        // 标注ID到COCO标注ID的映射，用于导出父标注引用
+        let coco_ids: HashMap<String, u32> = annotations
+            .iter()
+            .enumerate()
+            .map(|(index, annotation)| {
+                (annotation.id.clone(), annotation_id_counter + index as u32)
+            })
+            .collect();
+
+        for annotation in annotations {
+            let label = aliases.get(&annotation.label).unwrap_or(&annotation.label);
+            let category_id = if let Some(&id) = categories_map.get(label) {
+                id
+            } else {
+                let id = category_id_counter;
+                categories_map.insert(label.clone(), id);
+                category_id_counter += 1;
+                id
+            };
 
-                annotation_id_counter += 1;
-            }
+            let bbox = vec![
+                annotation.x,
+                annotation.y,
+                annotation.width,
+                annotation.height,
+            ];
+
+            // 多边形导出顶点和多边形面积，其他类型使用空的segmentation
+            let (segmentation, area) = match &annotation.points {
+                Some(points) if annotation.annotation_type == "polygon" => (
+                    vec![points.iter().flat_map(|p| [p[0], p[1]]).collect()],
+                    polygon_area(points),
+                ),
+                _ => (vec![], annotation.width * annotation.height),
+            };
+
+            // 计算旋转角度（弧度转为度）
+            let rotation_degrees = if annotation.annotation_type == "rotated-rectangle" {
+                let rot = annotation.rotation.unwrap_or(0.0);
+                rot * 180.0 / std::f64::consts::PI
+            } else {
+                0.0
+            };
+
+            // occluded为COCO的固定属性，其余属性原样写入（保留字段由标注本身的字段写出）
+            let mut custom = annotation.attributes.clone();
+            let occluded = custom
+                .remove("occluded")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false);
+            custom.retain(|name, _| !RESERVED_ATTRIBUTE_NAMES.contains(&name.as_str()));
+            let attributes = CocoAttributes {
+                occluded,
+                rotation: rotation_degrees,
+                track_id: annotation.track_id,
+                group_id: annotation.group_id,
+                parent_id: annotation
+                    .parent_id
+                    .as_ref()
+                    .and_then(|parent| coco_ids.get(parent).copied()),
+                custom,
+            };
+
+            coco_annotations.push(CocoAnnotation {
+                id: annotation_id_counter,
+                image_id: image_id_counter,
+                category_id,
+                segmentation,
+                area,
+                bbox,
+                iscrowd: 0,
+                attributes,
+            });
 
-            image_id_counter += 1;
+            annotation_id_counter += 1;
         }
+
+        image_id_counter += 1;
     }
 
     let mut categories: Vec<CocoCategory> = categories_map
         .into_iter()
         .map(|(name, id)| CocoCategory {
             id,
-            name: name.clone(),
-            supercategory: if has_rotated {
-                "rotated-object".to_string()
-            } else {
-                "object".to_string()
-            },
+            supercategory: supercategories.get(&name).cloned().unwrap_or_else(|| {
+                if has_rotated {
+                    "rotated-object".to_string()
+                } else {
+                    "object".to_string()
+                }
+            }),
+            name,
         })
         .collect();
 
@@ -235,7 +294,9 @@ pub async fn export_coco(image_folder: String, export_path: String) -> Result<St
 }
 
 /// 导入COCO格式
-/// import_type: "auto"（自动判断）、"rectangle"（强制矩形框）、"rotated-rectangle"（强制旋转框）
+/// import_type: "auto"（自动判断）、"rectangle"（强制矩形框）、"rotated-rectangle"（强制旋转框）、
+/// "polygon"（有segmentation时导入为多边形）
+/// 类别名称为 .oria-classes.json 或项目类别中的别名时映射为对应的类别
 #[tauri::command]
 pub async fn import_coco(
     coco_file_path: String,
@@ -247,16 +308,19 @@ pub async fn import_coco(
     let coco_content = std::fs::read_to_string(&coco_file_path)?;
     let coco_data: CocoFormat = serde_json::from_str(&coco_content)?;
 
+    // 类别名称为已定义类别的别名时导入为该类别
+    let aliases = alias_map(&resolve_classes(&image_folder)?);
     let category_map: HashMap<u32, String> = coco_data
         .categories
         .iter()
-        .map(|c| (c.id, c.name.clone()))
+        .map(|c| (c.id, aliases.get(&c.name).unwrap_or(&c.name).clone()))
         .collect();
 
     let image_map: HashMap<u32, &CocoImage> =
         coco_data.images.iter().map(|img| (img.id, img)).collect();
 
     let folder_path = Path::new(&image_folder);
+    let mut image_locator = ImageLocator::new(folder_path);
     let mut imported_count = 0;
 
     let mut annotations_by_image: HashMap<u32, Vec<&CocoAnnotation>> = HashMap::new();
@@ -270,6 +334,7 @@ pub async fn import_coco(
     let mut total_annotations = 0;
     let mut rotated_count = 0;
     let mut rectangle_count = 0;
+    let mut polygon_count = 0;
     let mut _scaled_images = 0;
     let mut debug_info = Vec::new();
 
@@ -279,11 +344,14 @@ pub async fn import_coco(
             None => continue,
         };
 
-        let image_path = folder_path.join(&image_info.file_name);
-        if !image_path.exists() {
-            // println!("Image not found: {:?}", image_path);
-            continue;
-        }
+        // file_name可以是子目录中的相对路径，找不到时按文件名在子目录中查找
+        let image_path = match image_locator.resolve(&image_info.file_name) {
+            Some(path) => path,
+            None => {
+                // println!("Image not found: {:?}", image_info.file_name);
+                continue;
+            }
+        };
 
         // 读取实际图片尺寸，用于坐标缩放
         let actual_dimensions = match image::open(&image_path) {
@@ -344,21 +412,42 @@ pub async fn import_coco(
             // 获取旋转角度（从attributes.rotation）
             let rotation_degrees = Some(ann.attributes.rotation);
 
+            // 多边形顶点（取第一个多边形，至少3个点）
+            let polygon: Option<Vec<[f64; 2]>> = ann
+                .segmentation
+                .first()
+                .filter(|coords| coords.len() >= 6)
+                .map(|coords| {
+                    coords
+                        .chunks_exact(2)
+                        .map(|c| [c[0] * scale_x, c[1] * scale_y])
+                        .collect()
+                });
+
             // 根据用户选择的导入类型决定标注类型
             let annotation_type = match import_type.as_str() {
                 "rectangle" => "rectangle",
                 "rotated-rectangle" => "rotated-rectangle",
+                "polygon" if polygon.is_some() => "polygon",
+                "polygon" => "rectangle",
                 "auto" | _ => {
-                    // 自动判断：如果有旋转角度（非0），则为旋转框
+                    // 自动判断：如果有旋转角度（非0），则为旋转框；有segmentation则为多边形
                     let has_rotation =
                         rotation_degrees.is_some() && rotation_degrees.unwrap().abs() > 0.001;
                     if has_rotation {
                         "rotated-rectangle"
+                    } else if polygon.is_some() {
+                        "polygon"
                     } else {
                         "rectangle"
                     }
                 }
             };
+            let points = if annotation_type == "polygon" {
+                polygon
+            } else {
+                None
+            };
 
             // 处理旋转角度（转换为弧度）
             let rotation = if annotation_type == "rotated-rectangle" {
@@ -376,6 +465,8 @@ pub async fn import_coco(
             // 统计类型
             if annotation_type == "rotated-rectangle" {
                 rotated_count += 1;
+            } else if annotation_type == "polygon" {
+                polygon_count += 1;
             } else {
                 rectangle_count += 1;
             }
@@ -402,6 +493,13 @@ pub async fn import_coco(
                 ));
             }
 
+            // 遮挡只在为true时保存为属性，项目定义了属性时只导入已定义的属性
+            let mut attributes = ann.attributes.custom.clone();
+            if ann.attributes.occluded {
+                attributes.insert("occluded".to_string(), serde_json::Value::Bool(true));
+            }
+            retain_defined(&mut attributes, &category_name);
+
             let annotation_data = AnnotationData {
                 id: format!("{}", ann.id),
                 annotation_type: annotation_type.to_string(),
@@ -410,8 +508,17 @@ pub async fn import_coco(
                 width: scaled_width,
                 height: scaled_height,
                 rotation,
+                points,
                 label: category_name,
+                track_id: ann.attributes.track_id,
+                interpolated: false,
+                group_id: ann.attributes.group_id,
+                parent_id: ann.attributes.parent_id.map(|id| id.to_string()),
+                classification: None,
+                attributes,
                 created: chrono::Utc::now().to_rfc3339(),
+                modified: String::new(),
+                author: String::new(),
                 visible: true,
             };
 
@@ -429,8 +536,8 @@ pub async fn import_coco(
     }
 
     let result_msg = format!(
-        "成功导入 {} 张图片，共 {} 个标注\n矩形框: {}, 旋转框: {}",
-        imported_count, total_annotations, rectangle_count, rotated_count
+        "成功导入 {} 张图片，共 {} 个标注\n矩形框: {}, 旋转框: {}, 多边形: {}",
+        imported_count, total_annotations, rectangle_count, rotated_count, polygon_count
     );
 
     Ok(result_msg)
@@ -1,2 +1,5 @@
+pub mod classification;
 pub mod coco;
+pub mod mot;
+pub mod split;
 pub mod yolo;
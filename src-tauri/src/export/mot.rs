@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::annotation::{load_annotation_file, save_annotations_internal};
+use crate::core::geometry::annotation_bounds;
+use crate::core::labels::read_label_lines;
+use crate::core::scan::{scan_images, ScanOptions};
+use crate::core::taxonomy::{alias_map, resolve_classes};
+use crate::error::AppError;
+use crate::models::AnnotationData;
+
+/// 文件夹中的图片（按相对路径自然排序，第i张对应MOT的第i+1帧）
+fn collect_frames(image_folder: &str, options: &ScanOptions) -> Result<Vec<PathBuf>, AppError> {
+    scan_images(Path::new(image_folder), options)
+}
+
+/// 把帧硬链接到导出目录，跨文件系统等无法链接时复制；覆盖上次导出的同名文件
+fn link_or_copy(from: &Path, to: &Path) -> Result<(), AppError> {
+    if to.exists() {
+        std::fs::remove_file(to)?;
+    }
+    if std::fs::hard_link(from, to).is_err() {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// 导出MOTChallenge格式
+/// 输出 gt/gt.txt（frame,id,bb_left,bb_top,bb_width,bb_height,conf,class,visibility）、
+/// gt/labels.txt（第n行为类别n的名称）、seqinfo.ini 和按帧号命名的 img1/ 图片；
+/// 类别编号为类别ID加1（与COCO导出一致，不同序列的编号相同），未定义的标签编号排在已定义类别之后；
+/// 旋转框和多边形按外接框导出，没有跟踪ID的标注跳过
+/// options: 扫描选项，导入时需使用相同的选项以保持帧顺序一致
+#[tauri::command]
+pub async fn export_mot(
+    image_folder: String,
+    export_path: String,
+    options: Option<ScanOptions>,
+) -> Result<String, AppError> {
+    let frames = collect_frames(&image_folder, &options.unwrap_or_default())?;
+
+    let classes = resolve_classes(&image_folder)?;
+    let aliases = alias_map(&classes);
+    let mut class_ids: HashMap<String, usize> = classes
+        .iter()
+        .map(|class| (class.name.clone(), class.id as usize + 1))
+        .collect();
+    let mut next_class_id = class_ids.values().max().map_or(1, |max| max + 1);
+
+    let mut lines = Vec::new();
+    let mut skipped_count = 0;
+    let mut image_size = (0, 0);
+
+    for (index, path) in frames.iter().enumerate() {
+        let file = match load_annotation_file(&path.to_string_lossy()) {
+            Ok(Some(file)) => file,
+            Ok(None) => continue,
+            Err(e) => {
+                println!(
+                    "Failed to load annotations for {}: {}",
+                    path.display(),
+                    e.message
+                );
+                continue;
+            }
+        };
+        if image_size == (0, 0) {
+            image_size = (file.image_width, file.image_height);
+        }
+
+        for annotation in &file.annotations {
+            let Some(track_id) = annotation.track_id else {
+                skipped_count += 1;
+                continue;
+            };
+            let label = aliases.get(&annotation.label).unwrap_or(&annotation.label);
+            let class_id = *class_ids.entry(label.clone()).or_insert_with(|| {
+                next_class_id += 1;
+                next_class_id - 1
+            });
+            let (x_min, y_min, x_max, y_max) = annotation_bounds(annotation);
+            lines.push(format!(
+                "{},{},{:.2},{:.2},{:.2},{:.2},1,{},1",
+                index + 1,
+                track_id,
+                x_min,
+                y_min,
+                x_max - x_min,
+                y_max - y_min,
+                class_id
+            ));
+        }
+    }
+
+    let export_dir = Path::new(&export_path);
+    let gt_dir = export_dir.join("gt");
+    std::fs::create_dir_all(&gt_dir)?;
+
+    let gt_file = gt_dir.join("gt.txt");
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    std::fs::write(&gt_file, content)?;
+    // 类别ID不连续时用 class_<n> 占位，保证第n行对应类别n
+    let names: HashMap<usize, &String> = class_ids.iter().map(|(name, id)| (*id, name)).collect();
+    let labels: Vec<String> = (1..next_class_id)
+        .map(|id| {
+            names
+                .get(&id)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("class_{}", id))
+        })
+        .collect();
+    std::fs::write(gt_dir.join("labels.txt"), labels.join("\n"))?;
+
+    // MOTChallenge序列的图片位于 img1/，按帧号命名（000001.jpg）
+    let image_dir = export_dir.join("img1");
+    std::fs::create_dir_all(&image_dir)?;
+    for (index, path) in frames.iter().enumerate() {
+        let mut file_name = format!("{:06}", index + 1);
+        if let Some(ext) = path.extension() {
+            file_name.push('.');
+            file_name.push_str(&ext.to_string_lossy());
+        }
+        link_or_copy(path, &image_dir.join(file_name))?;
+    }
+
+    let sequence_name = Path::new(&image_folder)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "sequence".to_string());
+    let image_ext = frames
+        .first()
+        .and_then(|p| p.extension())
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_else(|| ".jpg".to_string());
+    let seqinfo = format!(
+        "[Sequence]\nname={}\nimDir=img1\nseqLength={}\nimWidth={}\nimHeight={}\nimExt={}\n",
+        sequence_name,
+        frames.len(),
+        image_size.0,
+        image_size.1,
+        image_ext
+    );
+    std::fs::write(export_dir.join("seqinfo.ini"), seqinfo)?;
+
+    if skipped_count > 0 {
+        println!("Skipped {} annotations without track id", skipped_count);
+    }
+
+    Ok(gt_file.to_string_lossy().to_string())
+}
+
+/// 导入MOTChallenge格式的gt.txt
+/// 第n帧对应文件夹中自然排序的第n张图片；同目录存在labels.txt时按其解析类别名称（别名映射为对应的类别）；
+/// conf为0的行（评测时忽略的目标）不导入
+#[tauri::command]
+pub async fn import_mot(
+    gt_file_path: String,
+    image_folder: String,
+    options: Option<ScanOptions>,
+) -> Result<String, AppError> {
+    let frames = collect_frames(&image_folder, &options.unwrap_or_default())?;
+    let content = std::fs::read_to_string(&gt_file_path)?;
+
+    let labels_path = Path::new(&gt_file_path).with_file_name("labels.txt");
+    let labels = if labels_path.exists() {
+        read_label_lines(&labels_path)?
+    } else {
+        Vec::new()
+    };
+    let aliases = alias_map(&resolve_classes(&image_folder)?);
+
+    let mut annotations_by_frame: BTreeMap<usize, Vec<AnnotationData>> = BTreeMap::new();
+    let mut invalid_lines = 0;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let parsed = (
+            fields.first().and_then(|f| f.parse::<usize>().ok()),
+            fields.get(1).and_then(|f| f.parse::<f64>().ok()),
+            fields.get(2..6).map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.parse::<f64>().ok())
+                    .collect::<Vec<_>>()
+            }),
+        );
+        let (Some(frame), Some(track_id), Some(bbox)) = parsed else {
+            invalid_lines += 1;
+            continue;
+        };
+        if bbox.len() != 4 || frame == 0 || track_id < 0.0 {
+            invalid_lines += 1;
+            continue;
+        }
+        let conf = fields
+            .get(6)
+            .and_then(|f| f.parse::<f64>().ok())
+            .unwrap_or(1.0);
+        if conf == 0.0 {
+            continue;
+        }
+        let class_id = fields
+            .get(7)
+            .and_then(|f| f.parse::<usize>().ok())
+            .unwrap_or(1);
+        let label = labels
+            .get(class_id.wrapping_sub(1))
+            .map(|label| aliases.get(label).unwrap_or(label).clone())
+            .unwrap_or_else(|| format!("class_{}", class_id));
+
+        annotations_by_frame
+            .entry(frame)
+            .or_default()
+            .push(AnnotationData {
+                id: uuid::Uuid::new_v4().to_string(),
+                annotation_type: "rectangle".to_string(),
+                x: bbox[0],
+                y: bbox[1],
+                width: bbox[2],
+                height: bbox[3],
+                rotation: None,
+                points: None,
+                label,
+                track_id: Some(track_id as u64),
+                interpolated: false,
+                group_id: None,
+                parent_id: None,
+                classification: None,
+                attributes: Default::default(),
+                created: chrono::Utc::now().to_rfc3339(),
+                modified: String::new(),
+                author: String::new(),
+                visible: true,
+            });
+    }
+
+    let mut imported_count = 0;
+    let mut total_annotations = 0;
+    let mut missing_frames = 0;
+    let mut track_ids = HashSet::new();
+    for (frame, annotations) in annotations_by_frame {
+        let Some(image_path) = frames.get(frame - 1) else {
+            missing_frames += 1;
+            continue;
+        };
+        let count = annotations.len();
+        track_ids.extend(annotations.iter().filter_map(|a| a.track_id));
+
+        let image_path_str = image_path.to_string_lossy().to_string();
+        match save_annotations_internal(&image_path_str, annotations) {
+            Ok(_) => {
+                imported_count += 1;
+                total_annotations += count;
+            }
+            Err(e) => println!(
+                "Failed to save annotations for {:?}: {}",
+                image_path, e.message
+            ),
+        }
+    }
+
+    let mut result_msg = format!(
+        "成功导入 {} 帧，共 {} 个标注，{} 条轨迹",
+        imported_count,
+        total_annotations,
+        track_ids.len()
+    );
+    if missing_frames > 0 {
+        result_msg.push_str(&format!("\n{} 帧超出图片数量，已跳过", missing_frames));
+    }
+    if invalid_lines > 0 {
+        result_msg.push_str(&format!("\n{} 行格式无效，已跳过", invalid_lines));
+    }
+
+    Ok(result_msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotation::save_annotation_file;
+    use crate::models::AnnotationFile;
+
+    #[test]
+    fn export_links_frames_into_img1() {
+        let root = std::env::temp_dir().join(format!("oria-mot-{}", uuid::Uuid::new_v4()));
+        let folder = root.join("seq-a");
+        let export_dir = root.join("export");
+        std::fs::create_dir_all(&folder).unwrap();
+        for (name, track_id) in [("frame_2.jpg", 7), ("frame_10.jpg", 8)] {
+            let image_path = folder.join(name);
+            std::fs::write(&image_path, name.as_bytes()).unwrap();
+            let mut file = AnnotationFile::new(name.to_string(), 10, 10);
+            file.annotations.push(
+                serde_json::from_value(serde_json::json!({
+                    "id": uuid::Uuid::new_v4().to_string(),
+                    "type": "rectangle",
+                    "x": 1.0,
+                    "y": 1.0,
+                    "width": 4.0,
+                    "height": 4.0,
+                    "label": "car",
+                    "track_id": track_id,
+                }))
+                .unwrap(),
+            );
+            save_annotation_file(&image_path.to_string_lossy(), &file).unwrap();
+        }
+
+        tauri::async_runtime::block_on(export_mot(
+            folder.to_string_lossy().to_string(),
+            export_dir.to_string_lossy().to_string(),
+            None,
+        ))
+        .unwrap();
+
+        // 帧按自然顺序编号，seqinfo.ini中的imDir指向实际存在的图片
+        let seqinfo = std::fs::read_to_string(export_dir.join("seqinfo.ini")).unwrap();
+        assert!(seqinfo.contains("imDir=img1\n"), "{}", seqinfo);
+        assert!(seqinfo.contains("seqLength=2\n"), "{}", seqinfo);
+        assert_eq!(
+            std::fs::read(export_dir.join("img1/000001.jpg")).unwrap(),
+            b"frame_2.jpg"
+        );
+        assert_eq!(
+            std::fs::read(export_dir.join("img1/000002.jpg")).unwrap(),
+            b"frame_10.jpg"
+        );
+        let gt = std::fs::read_to_string(export_dir.join("gt/gt.txt")).unwrap();
+        assert!(gt.starts_with("1,7,"), "{}", gt);
+        assert!(gt.contains("\n2,8,"), "{}", gt);
+
+        // 重复导出覆盖已有的帧
+        tauri::async_runtime::block_on(export_mot(
+            folder.to_string_lossy().to_string(),
+            export_dir.to_string_lossy().to_string(),
+            None,
+        ))
+        .unwrap();
+        assert!(export_dir.join("img1/000002.jpg").exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
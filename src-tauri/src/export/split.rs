@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::annotation::load_annotation_file;
+use crate::core::scan::{scan_images, ScanOptions};
+use crate::error::AppError;
+
+/// 数据集划分配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SplitConfig {
+    pub export_path: String, // 输出train.txt/val.txt/test.txt的目录
+    pub train_ratio: f64,
+    pub val_ratio: f64, // 其余划入测试集
+    #[serde(default)]
+    pub seed: u64, // 随机种子，相同种子和数据得到相同划分
+}
+
+/// 数据集划分结果
+#[derive(Debug, Serialize)]
+pub struct SplitResult {
+    pub train_count: usize,
+    pub val_count: usize,
+    pub test_count: usize,
+    pub group_count: usize,       // 划分单元数（每个视频一组，其他图片各自一组）
+    pub video_group_count: usize, // 来自视频的分组数
+}
+
+/// FNV-1a哈希，保证不同版本和平台上划分结果一致
+fn fnv1a(seed: u64, key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325 ^ seed;
+    for byte in key.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// 划分数据集
+/// 同一视频抽取的帧作为一组整体划入同一子集，避免相邻帧同时出现在训练集和验证集
+#[tauri::command]
+pub async fn export_dataset_split(
+    image_folder: String,
+    config: SplitConfig,
+    options: Option<ScanOptions>,
+) -> Result<SplitResult, AppError> {
+    let folder_path = Path::new(&image_folder);
+    if !folder_path.exists() {
+        return Err(AppError {
+            message: format!("Folder does not exist: {}", image_folder),
+        });
+    }
+    if config.train_ratio < 0.0
+        || config.val_ratio < 0.0
+        || config.train_ratio + config.val_ratio > 1.0
+    {
+        return Err(AppError {
+            message: "划分比例无效：训练集与验证集比例之和应在0到1之间".to_string(),
+        });
+    }
+
+    let image_paths = scan_images(folder_path, &options.unwrap_or_default())?;
+
+    // 按来源视频分组（BTreeMap保证分组顺序稳定）
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut video_group_count = 0;
+    for path in &image_paths {
+        let path_str = path.to_string_lossy().to_string();
+        let group_key = match load_annotation_file(&path_str) {
+            Ok(Some(file)) => file.source_video.map(|source| source.video_path),
+            _ => None,
+        };
+        let group_key = match group_key {
+            Some(video_path) => format!("video:{}", video_path),
+            None => format!("image:{}", path_str),
+        };
+        if group_key.starts_with("video:") && !groups.contains_key(&group_key) {
+            video_group_count += 1;
+        }
+        groups.entry(group_key).or_default().push(path_str);
+    }
+
+    // 分组按哈希打乱后依次填充训练集、验证集，其余为测试集
+    let mut ordered: Vec<(&String, &Vec<String>)> = groups.iter().collect();
+    ordered.sort_by_key(|(key, _)| fnv1a(config.seed, key));
+
+    let total = image_paths.len() as f64;
+    let train_target = total * config.train_ratio;
+    let val_target = total * (config.train_ratio + config.val_ratio);
+
+    let mut train = Vec::new();
+    let mut val = Vec::new();
+    let mut test = Vec::new();
+    let mut assigned = 0usize;
+    for (_, paths) in ordered {
+        let split = if (assigned as f64) < train_target {
+            &mut train
+        } else if (assigned as f64) < val_target {
+            &mut val
+        } else {
+            &mut test
+        };
+        split.extend(paths.iter().cloned());
+        assigned += paths.len();
+    }
+
+    let export_dir = Path::new(&config.export_path);
+    std::fs::create_dir_all(export_dir)?;
+    for (name, paths) in [("train.txt", &train), ("val.txt", &val), ("test.txt", &test)] {
+        let mut content = paths.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        std::fs::write(export_dir.join(name), content)?;
+    }
+
+    Ok(SplitResult {
+        train_count: train.len(),
+        val_count: val.len(),
+        test_count: test.len(),
+        group_count: groups.len(),
+        video_group_count,
+    })
+}
@@ -4,15 +4,18 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use crate::annotation::load_annotations_internal;
+use crate::core::attributes::AnnotationFilter;
+use crate::core::scan::{scan_images, ScanOptions};
 use crate::error::AppError;
 use crate::models::AnnotationData;
-use crate::utils::is_image_file;
 
 /// YOLO导出数据结构
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct YoloExportConfig {
     pub class_map: HashMap<String, u32>, // 类别名称到ID的映射
     pub export_path: String,             // 导出目录路径
+    #[serde(default)]
+    pub filter: AnnotationFilter, // 按类别和属性筛选导出的标注
 }
 
 /// 导出为YOLO格式
@@ -37,7 +40,7 @@ pub async fn export_yolo(
 
     let mut yolo_lines = Vec::new();
 
-    for annotation in &annotations {
+    for annotation in annotations.iter().filter(|a| config.filter.matches(a)) {
         let class_id = config.class_map.get(&annotation.label).unwrap_or(&0);
 
         let center_x = (annotation.x + annotation.width / 2.0) / img_width as f64;
@@ -46,7 +49,8 @@ pub async fn export_yolo(
         let norm_height = annotation.height / img_height as f64;
 
         let yolo_line = match annotation.annotation_type.as_str() {
-            "rectangle" => {
+            // 多边形按外接框导出
+            "rectangle" | "polygon" => {
                 format!(
                     "{} {:.6} {:.6} {:.6} {:.6}",
                     class_id, center_x, center_y, norm_width, norm_height
@@ -98,10 +102,12 @@ pub async fn export_yolo(
 }
 
 /// 批量导出YOLO格式
+/// options: 扫描选项，递归扫描时在导出目录中保留子目录结构
 #[tauri::command]
 pub async fn export_yolo_batch(
     image_folder: String,
     config: YoloExportConfig,
+    options: Option<ScanOptions>,
 ) -> Result<Vec<String>, AppError> {
     // println!("Batch exporting YOLO format from folder: {}", image_folder);
 
@@ -115,39 +121,44 @@ pub async fn export_yolo_batch(
     std::fs::create_dir_all(&config.export_path)?;
 
     let mut exported_files = Vec::new();
-    let entries = std::fs::read_dir(folder_path)?;
-
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file() && is_image_file(&path) {
-            let image_path_str = path.to_string_lossy().to_string();
-
-            match load_annotations_internal(&image_path_str) {
-                Ok(annotations) => {
-                    if !annotations.is_empty() {
-                        match export_yolo(image_path_str, annotations, config.clone()).await {
-                            Ok(output_path) => {
-                                exported_files.push(output_path);
-                            }
-                            Err(e) => {
-                                println!(
-                                    "Failed to export YOLO for {}: {}",
-                                    path.display(),
-                                    e.message
-                                );
-                            }
+    let images = scan_images(folder_path, &options.unwrap_or_default())?;
+
+    for path in images {
+        let image_path_str = path.to_string_lossy().to_string();
+
+        match load_annotations_internal(&image_path_str) {
+            Ok(annotations) => {
+                if !annotations.is_empty() {
+                    // 标签文件放在与图片相同的相对子目录中
+                    let mut image_config = config.clone();
+                    if let Some(parent) =
+                        path.parent().and_then(|p| p.strip_prefix(folder_path).ok())
+                    {
+                        let output_dir = Path::new(&config.export_path).join(parent);
+                        std::fs::create_dir_all(&output_dir)?;
+                        image_config.export_path = output_dir.to_string_lossy().to_string();
+                    }
+
+                    match export_yolo(image_path_str, annotations, image_config).await {
+                        Ok(output_path) => {
+                            exported_files.push(output_path);
+                        }
+                        Err(e) => {
+                            println!(
+                                "Failed to export YOLO for {}: {}",
+                                path.display(),
+                                e.message
+                            );
                         }
                     }
                 }
-                Err(e) => {
-                    println!(
-                        "Failed to load annotations for {}: {}",
-                        path.display(),
-                        e.message
-                    );
-                }
+            }
+            Err(e) => {
+                println!(
+                    "Failed to load annotations for {}: {}",
+                    path.display(),
+                    e.message
+                );
             }
         }
     }
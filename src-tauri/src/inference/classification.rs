@@ -0,0 +1,259 @@
+//! 图片分类推理
+//!
+//! 使用YOLOv8-cls或通用分类ONNX模型，为图片预填top-k图片级标签，
+//! 或作为检测后的二级分类器细化检测框的类别
+
+#[cfg(feature = "onnx")]
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "onnx")]
+use crate::models::{AnnotationData, ImageTag};
+
+/// 输入归一化方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// 仅缩放到[0,1]（YOLOv8-cls）
+    #[default]
+    None,
+    /// ImageNet均值/标准差归一化（torchvision等通用分类模型）
+    Imagenet,
+}
+
+/// 分类模型配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifierConfig {
+    pub model_path: String,
+    #[serde(default)]
+    pub use_gpu: bool,
+    /// 显式指定的类别名称
+    #[serde(default)]
+    pub class_names: Vec<String>,
+    /// 类别名称文件（每行一个类别）
+    #[serde(default)]
+    pub class_names_file: Option<String>,
+    /// 动态输入尺寸模型的输入尺寸 [width, height]
+    #[serde(default)]
+    pub input_size: Option<[u32; 2]>,
+    #[serde(default)]
+    pub normalization: Normalization,
+}
+
+/// 图片分类配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageClassificationConfig {
+    #[serde(flatten)]
+    pub classifier: ClassifierConfig,
+    /// 保留的标签数量
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// 最低置信度，低于该值的预测不写入
+    #[serde(default)]
+    pub min_score: f32,
+    /// 是否覆盖已有的图片级标签
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+fn default_top_k() -> usize {
+    1
+}
+
+/// 二级分类结果的使用方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CascadeAction {
+    /// 用分类器的类别替换检测类别
+    #[default]
+    Replace,
+    /// 保留检测类别，仅记录分类器的类别和置信度
+    Annotate,
+}
+
+/// 二级分类规则（按检测类别配置）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CascadeRule {
+    /// 检测类别，"*" 匹配所有类别
+    pub detector_class: String,
+    #[serde(default)]
+    pub action: CascadeAction,
+    /// 替换类别所需的最低置信度，低于该值时保留检测类别
+    #[serde(default)]
+    pub min_score: f32,
+}
+
+/// 检测后的二级分类配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CascadeConfig {
+    #[serde(flatten)]
+    pub classifier: ClassifierConfig,
+    /// 按顺序匹配，第一条匹配的规则生效；未匹配的检测框不做二级分类
+    pub rules: Vec<CascadeRule>,
+}
+
+impl CascadeConfig {
+    /// 查找检测类别对应的规则
+    #[cfg(feature = "onnx")]
+    fn rule_for(&self, label: &str) -> Option<&CascadeRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.detector_class == label || rule.detector_class == "*")
+    }
+}
+
+/// 单张图片的分类结果
+#[cfg(feature = "onnx")]
+#[derive(Debug, Serialize)]
+pub struct ClassificationResult {
+    pub image_path: String,
+    pub tags: Vec<ImageTag>,
+    pub skipped: bool, // 已有标签且未开启覆盖时跳过
+}
+
+/// 批量分类结果
+#[cfg(feature = "onnx")]
+#[derive(Debug, Serialize)]
+pub struct BatchClassificationResult {
+    pub results: Vec<ClassificationResult>,
+    pub total_time_ms: f32,
+    pub success_count: usize,
+    pub error_count: usize,
+}
+
+/// 对图片进行分类并将top-k结果写入图片级标签
+#[cfg(feature = "onnx")]
+pub fn classify_images(
+    image_paths: &[String],
+    config: &ImageClassificationConfig,
+) -> Result<BatchClassificationResult> {
+    use crate::core::annotation::{load_annotation_file, save_image_tags_internal};
+
+    let classifier = load_classifier(&config.classifier)?;
+    let start_time = std::time::Instant::now();
+    let mut results = Vec::new();
+    let mut success_count = 0;
+    let mut error_count = 0;
+
+    for image_path in image_paths {
+        let existing_tags = load_annotation_file(image_path)
+            .ok()
+            .flatten()
+            .map(|file| file.tags)
+            .unwrap_or_default();
+        if !config.overwrite && !existing_tags.is_empty() {
+            results.push(ClassificationResult {
+                image_path: image_path.clone(),
+                tags: existing_tags,
+                skipped: true,
+            });
+            continue;
+        }
+
+        let classified = image::open(image_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|img| classifier.classify(&img, config.top_k));
+        match classified {
+            Ok(predictions) => {
+                let tags: Vec<ImageTag> = predictions
+                    .into_iter()
+                    .filter(|p| p.score >= config.min_score)
+                    .map(|p| ImageTag {
+                        label: p.class_name,
+                        score: Some(p.score),
+                    })
+                    .collect();
+
+                match save_image_tags_internal(image_path, tags.clone()) {
+                    Ok(_) => {
+                        success_count += 1;
+                        results.push(ClassificationResult {
+                            image_path: image_path.clone(),
+                            tags,
+                            skipped: false,
+                        });
+                    }
+                    Err(e) => {
+                        eprintln!("保存图片标签失败 {}: {}", image_path, e.message);
+                        error_count += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("分类失败 {}: {}", image_path, e);
+                error_count += 1;
+            }
+        }
+    }
+
+    Ok(BatchClassificationResult {
+        results,
+        total_time_ms: start_time.elapsed().as_millis() as f32,
+        success_count,
+        error_count,
+    })
+}
+
+/// 对检测结果做二级分类：裁剪每个检测框（旋转框摆正），
+/// 按规则替换类别或记录分类结果
+#[cfg(feature = "onnx")]
+pub fn apply_cascade(
+    image: &image::DynamicImage,
+    annotations: &mut [AnnotationData],
+    config: &CascadeConfig,
+) -> Result<()> {
+    use crate::core::geometry::crop_annotation;
+
+    if !annotations
+        .iter()
+        .any(|annotation| config.rule_for(&annotation.label).is_some())
+    {
+        return Ok(());
+    }
+
+    let classifier = load_classifier(&config.classifier)?;
+
+    for annotation in annotations.iter_mut() {
+        let Some(rule) = config.rule_for(&annotation.label) else {
+            continue;
+        };
+        let Some(crop) = crop_annotation(image, annotation) else {
+            continue;
+        };
+
+        let Some(top) = classifier.classify(&crop, 1)?.into_iter().next() else {
+            continue;
+        };
+
+        if rule.action == CascadeAction::Replace && top.score >= rule.min_score {
+            annotation.label = top.class_name.clone();
+        }
+        annotation.classification = Some(ImageTag {
+            label: top.class_name,
+            score: Some(top.score),
+        });
+    }
+
+    Ok(())
+}
+
+/// 从注册表获取分类器
+#[cfg(feature = "onnx")]
+pub fn load_classifier(
+    config: &ClassifierConfig,
+) -> Result<std::sync::Arc<super::onnx_classifier::OnnxClassifier>> {
+    use super::engine_registry::ONNX_ENGINES;
+    use super::model_inference::resolve_class_names;
+    use super::onnx_classifier::ClassifierOptions;
+
+    let options = ClassifierOptions {
+        class_names: resolve_class_names(
+            &config.class_names,
+            config.class_names_file.as_deref(),
+        )?,
+        input_size: config.input_size.map(|[w, h]| (w, h)),
+        normalization: config.normalization,
+    };
+
+    ONNX_ENGINES.get_classifier(&config.model_path, config.use_gpu, options)
+}
@@ -0,0 +1,296 @@
+//! ONNX推理引擎注册表
+//!
+//! 按模型身份（路径、GPU配置、加载选项）缓存已加载的检测、分类和交互式分割引擎，
+//! 阈值在每次推理时传入；超出内存预算时按LRU淘汰，模型文件更新后自动重新加载
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::onnx_classifier::{ClassifierOptions, OnnxClassifier};
+use super::onnx_inference::{OnnxInferenceEngine, OnnxModelOptions};
+use super::segment_anything::SamEngine;
+
+/// 默认内存预算：2 GiB
+const DEFAULT_MEMORY_BUDGET: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 已加载的引擎
+#[derive(Clone)]
+enum LoadedEngine {
+    Detector(Arc<OnnxInferenceEngine>),
+    Classifier(Arc<OnnxClassifier>),
+    Segmenter(Arc<SamEngine>),
+}
+
+impl LoadedEngine {
+    fn kind(&self) -> &'static str {
+        match self {
+            LoadedEngine::Detector(_) => "detector",
+            LoadedEngine::Classifier(_) => "classifier",
+            LoadedEngine::Segmenter(_) => "segmenter",
+        }
+    }
+}
+
+/// 注册表项
+struct RegistryEntry {
+    engine: LoadedEngine,
+    model_paths: Vec<String>, // 引擎使用的模型文件（分割引擎为编码器和解码器）
+    use_gpu: bool,
+    modified: Vec<Option<SystemTime>>, // 加载时各模型文件的修改时间
+    memory_bytes: u64,            // 估算的内存占用（模型文件大小的2倍）
+    loaded_at: String,
+    last_used: String,
+    last_used_tick: u64, // 用于LRU排序的单调计数
+}
+
+/// 已加载模型信息
+#[derive(Debug, Serialize)]
+pub struct LoadedModelInfo {
+    pub model_path: String,
+    /// 同一引擎使用的其他模型文件（如分割模型的解码器）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub companion_paths: Vec<String>,
+    pub kind: String, // "detector"、"classifier" 或 "segmenter"
+    pub use_gpu: bool,
+    pub memory_bytes: u64,
+    pub loaded_at: String,
+    pub last_used: String,
+}
+
+/// 注册表状态
+struct RegistryState {
+    entries: HashMap<String, RegistryEntry>,
+    memory_budget: u64,
+    tick: u64,
+}
+
+/// 推理引擎注册表
+pub struct EngineRegistry {
+    state: Mutex<RegistryState>,
+}
+
+impl EngineRegistry {
+    pub fn new(memory_budget: u64) -> Self {
+        Self {
+            state: Mutex::new(RegistryState {
+                entries: HashMap::new(),
+                memory_budget,
+                tick: 0,
+            }),
+        }
+    }
+
+    /// 估算引擎内存占用
+    /// 权重常驻内存，加上运行时的中间张量，按模型文件大小的2倍估算。
+    /// 这只是经验值：GPU会话的显存和大输入尺寸的中间张量可能远超该值，预算只用于决定何时淘汰
+    fn estimate_memory(model_paths: &[&str]) -> u64 {
+        model_paths
+            .iter()
+            .map(|path| {
+                std::fs::metadata(path)
+                    .map(|m| m.len().saturating_mul(2))
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// 获取模型文件修改时间
+    fn model_modified(model_path: &str) -> Option<SystemTime> {
+        std::fs::metadata(model_path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    /// 获取检测引擎
+    pub fn get_detector(
+        &self,
+        model_path: &str,
+        use_gpu: bool,
+        options: OnnxModelOptions,
+    ) -> Result<Arc<OnnxInferenceEngine>> {
+        // 模型身份key（不包含阈值）
+        let key = format!(
+            "detector:{}:{}:{:?}:{:?}",
+            model_path, use_gpu, options.class_names, options.input_size
+        );
+        let engine = self.get_or_load(key, &[model_path], use_gpu, || {
+            OnnxInferenceEngine::with_options(model_path, use_gpu, options)
+                .map(|engine| LoadedEngine::Detector(Arc::new(engine)))
+                .context("无法创建ONNX推理器")
+        })?;
+
+        match engine {
+            LoadedEngine::Detector(engine) => Ok(engine),
+            other => anyhow::bail!("模型已作为{}加载", other.kind()),
+        }
+    }
+
+    /// 获取分类引擎
+    pub fn get_classifier(
+        &self,
+        model_path: &str,
+        use_gpu: bool,
+        options: ClassifierOptions,
+    ) -> Result<Arc<OnnxClassifier>> {
+        let key = format!(
+            "classifier:{}:{}:{:?}:{:?}:{:?}",
+            model_path, use_gpu, options.class_names, options.input_size, options.normalization
+        );
+        let engine = self.get_or_load(key, &[model_path], use_gpu, || {
+            OnnxClassifier::with_options(model_path, use_gpu, options)
+                .map(|engine| LoadedEngine::Classifier(Arc::new(engine)))
+                .context("无法创建ONNX分类器")
+        })?;
+
+        match engine {
+            LoadedEngine::Classifier(engine) => Ok(engine),
+            other => anyhow::bail!("模型已作为{}加载", other.kind()),
+        }
+    }
+
+    /// 获取交互式分割引擎（编码器和解码器作为一个引擎缓存）
+    pub fn get_segmenter(
+        &self,
+        encoder_path: &str,
+        decoder_path: &str,
+        use_gpu: bool,
+    ) -> Result<Arc<SamEngine>> {
+        let key = format!("segmenter:{}:{}:{}", encoder_path, decoder_path, use_gpu);
+        let engine = self.get_or_load(key, &[encoder_path, decoder_path], use_gpu, || {
+            SamEngine::new(encoder_path, decoder_path, use_gpu)
+                .map(|engine| LoadedEngine::Segmenter(Arc::new(engine)))
+                .context("无法创建分割引擎")
+        })?;
+
+        match engine {
+            LoadedEngine::Segmenter(engine) => Ok(engine),
+            other => anyhow::bail!("模型已作为{}加载", other.kind()),
+        }
+    }
+
+    /// 获取引擎（已加载且模型文件未变化时复用，否则重新加载）
+    fn get_or_load(
+        &self,
+        key: String,
+        model_paths: &[&str],
+        use_gpu: bool,
+        load: impl FnOnce() -> Result<LoadedEngine>,
+    ) -> Result<LoadedEngine> {
+        for model_path in model_paths {
+            if !Path::new(model_path).exists() {
+                anyhow::bail!("模型文件不存在: {}", model_path);
+            }
+        }
+
+        let modified: Vec<Option<SystemTime>> = model_paths
+            .iter()
+            .map(|path| Self::model_modified(path))
+            .collect();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.tick += 1;
+            let tick = state.tick;
+            if let Some(entry) = state.entries.get_mut(&key) {
+                if entry.modified == modified {
+                    entry.last_used_tick = tick;
+                    entry.last_used = chrono::Utc::now().to_rfc3339();
+                    return Ok(entry.engine.clone());
+                }
+            }
+            // 模型文件已变化，丢弃旧引擎
+            state.entries.remove(&key);
+        }
+
+        // 加载模型较慢，不持有锁
+        let engine = load()?;
+        let memory_bytes = Self::estimate_memory(model_paths);
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let mut state = self.state.lock().unwrap();
+        Self::evict_for(&mut state, memory_bytes);
+        state.tick += 1;
+        let tick = state.tick;
+        state.entries.insert(
+            key,
+            RegistryEntry {
+                engine: engine.clone(),
+                model_paths: model_paths.iter().map(|path| path.to_string()).collect(),
+                use_gpu,
+                modified,
+                memory_bytes,
+                loaded_at: now.clone(),
+                last_used: now,
+                last_used_tick: tick,
+            },
+        );
+
+        Ok(engine)
+    }
+
+    /// 按LRU淘汰引擎，直到能容纳新的引擎
+    /// 正在使用中的引擎由Arc持有，淘汰只会移出注册表，不会中断推理
+    fn evict_for(state: &mut RegistryState, incoming: u64) {
+        loop {
+            let used: u64 = state.entries.values().map(|e| e.memory_bytes).sum();
+            if state.entries.is_empty() || used.saturating_add(incoming) <= state.memory_budget {
+                break;
+            }
+
+            let oldest_key = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used_tick)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest_key {
+                state.entries.remove(&key);
+            }
+        }
+    }
+
+    /// 列出已加载的模型
+    pub fn list(&self) -> Vec<LoadedModelInfo> {
+        let state = self.state.lock().unwrap();
+        let mut entries: Vec<&RegistryEntry> = state.entries.values().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used_tick));
+
+        entries
+            .into_iter()
+            .map(|entry| LoadedModelInfo {
+                model_path: entry.model_paths[0].clone(),
+                companion_paths: entry.model_paths[1..].to_vec(),
+                kind: entry.engine.kind().to_string(),
+                use_gpu: entry.use_gpu,
+                memory_bytes: entry.memory_bytes,
+                loaded_at: entry.loaded_at.clone(),
+                last_used: entry.last_used.clone(),
+            })
+            .collect()
+    }
+
+    /// 卸载指定模型的所有引擎，返回卸载数量
+    pub fn unload(&self, model_path: &str) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let before = state.entries.len();
+        state
+            .entries
+            .retain(|_, entry| !entry.model_paths.iter().any(|path| path == model_path));
+        before - state.entries.len()
+    }
+
+    /// 设置内存预算（超出时立即淘汰）
+    pub fn set_memory_budget(&self, memory_budget: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.memory_budget = memory_budget;
+        Self::evict_for(&mut state, 0);
+    }
+}
+
+// 全局推理引擎注册表
+lazy_static::lazy_static! {
+    pub static ref ONNX_ENGINES: EngineRegistry = EngineRegistry::new(DEFAULT_MEMORY_BUDGET);
+}
@@ -0,0 +1,522 @@
+//! KServe v2 / Triton 推理协议客户端
+//!
+//! 通过HTTP/REST协议调用远程模型服务，预处理和后处理在本地完成
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use super::api_client::Detection;
+use super::yolo;
+
+/// 张量描述（模型元数据中的输入/输出）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TensorMetadata {
+    pub name: String,
+    pub datatype: String,
+    pub shape: Vec<i64>,
+}
+
+/// 模型元数据
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KServeModelMetadata {
+    pub name: String,
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub platform: String,
+    pub inputs: Vec<TensorMetadata>,
+    pub outputs: Vec<TensorMetadata>,
+}
+
+/// 推理请求中的输入张量
+#[derive(Debug, Serialize)]
+struct InferInputTensor<'a> {
+    name: &'a str,
+    shape: Vec<usize>,
+    datatype: &'static str,
+    data: &'a [f32],
+}
+
+/// 推理请求中指定的输出
+#[derive(Debug, Serialize)]
+struct InferRequestedOutput<'a> {
+    name: &'a str,
+}
+
+/// 推理请求
+#[derive(Debug, Serialize)]
+struct InferRequest<'a> {
+    inputs: Vec<InferInputTensor<'a>>,
+    outputs: Vec<InferRequestedOutput<'a>>,
+}
+
+/// 推理响应中的输出张量
+#[derive(Debug)]
+pub struct InferOutputTensor {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+/// 推理响应中未解析数据的输出张量，先检查datatype再解析data
+#[derive(Debug, Deserialize)]
+struct RawOutputTensor {
+    name: String,
+    datatype: String,
+    shape: Vec<usize>,
+    data: serde_json::Value,
+}
+
+/// 推理响应
+#[derive(Debug, Deserialize)]
+struct InferResponse {
+    outputs: Vec<RawOutputTensor>,
+}
+
+/// KServe v2 客户端
+pub struct KServeClient {
+    base_url: String,
+    model_name: String,
+    model_version: Option<String>,
+    client: reqwest::Client,
+    metadata: tokio::sync::OnceCell<KServeModelMetadata>, // 推理时使用的模型元数据，首次推理时获取
+}
+
+impl KServeClient {
+    /// 创建新的KServe客户端
+    pub fn new(base_url: String, model_name: String, model_version: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model_name,
+            model_version: model_version.filter(|v| !v.is_empty()),
+            client: reqwest::Client::new(),
+            metadata: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// 模型路径：/v2/models/{name}[/versions/{version}]
+    fn model_url(&self) -> String {
+        match &self.model_version {
+            Some(version) => format!(
+                "{}/v2/models/{}/versions/{}",
+                self.base_url, self.model_name, version
+            ),
+            None => format!("{}/v2/models/{}", self.base_url, self.model_name),
+        }
+    }
+
+    /// 检查服务和模型是否就绪
+    pub async fn check_ready(&self) -> Result<bool> {
+        let server_ready = self
+            .client
+            .get(format!("{}/v2/health/ready", self.base_url))
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .context("无法连接到KServe服务")?;
+
+        if !server_ready.status().is_success() {
+            return Ok(false);
+        }
+
+        let model_ready = self
+            .client
+            .get(format!("{}/ready", self.model_url()))
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .context("无法连接到KServe服务")?;
+
+        Ok(model_ready.status().is_success())
+    }
+
+    /// 获取模型元数据
+    pub async fn get_metadata(&self) -> Result<KServeModelMetadata> {
+        let response = self
+            .client
+            .get(self.model_url())
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .context("获取模型元数据失败")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("KServe返回错误 {}: {}", status, error_text);
+        }
+
+        response
+            .json::<KServeModelMetadata>()
+            .await
+            .context("解析模型元数据失败")
+    }
+
+    /// 获取模型元数据，同一客户端只请求一次
+    async fn cached_metadata(&self) -> Result<&KServeModelMetadata> {
+        self.metadata.get_or_try_init(|| self.get_metadata()).await
+    }
+
+    /// 发送原始张量并返回第一个输出
+    pub async fn infer(
+        &self,
+        input: &TensorMetadata,
+        shape: Vec<usize>,
+        data: &[f32],
+        output: &TensorMetadata,
+    ) -> Result<InferOutputTensor> {
+        let request = InferRequest {
+            inputs: vec![InferInputTensor {
+                name: &input.name,
+                shape,
+                datatype: "FP32",
+                data,
+            }],
+            outputs: vec![InferRequestedOutput { name: &output.name }],
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/infer", self.model_url()))
+            .json(&request)
+            .timeout(std::time::Duration::from_secs(60))
+            .send()
+            .await
+            .context("KServe推理请求失败")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("KServe返回错误 {}: {}", status, error_text);
+        }
+
+        let result = response
+            .json::<InferResponse>()
+            .await
+            .context("解析KServe推理响应失败")?;
+
+        let tensor = result
+            .outputs
+            .into_iter()
+            .find(|o| o.name == output.name)
+            .context("KServe响应中缺少输出张量")?;
+
+        // 后处理只支持FP32输出，其他类型（FP16、INT8、BYTES等）明确报错
+        if tensor.datatype != "FP32" {
+            anyhow::bail!(
+                "不支持的输出张量数据类型 {}（{}），仅支持FP32",
+                tensor.datatype,
+                tensor.name
+            );
+        }
+        let data = serde_json::from_value::<Vec<f32>>(tensor.data)
+            .context("解析KServe输出张量数据失败")?;
+
+        Ok(InferOutputTensor {
+            shape: tensor.shape,
+            data,
+        })
+    }
+
+    /// 对单张图片进行YOLO检测推理
+    /// 预处理（letterbox）和后处理（解码+NMS）均在本地完成；批量推理时复用同一客户端以避免重复获取元数据
+    pub async fn detect(
+        &self,
+        image: &DynamicImage,
+        class_names: &[String],
+        conf_threshold: f32,
+        iou_threshold: f32,
+    ) -> Result<(Vec<Detection>, f32)> {
+        let metadata = self.cached_metadata().await?;
+        let input = metadata.inputs.first().context("模型没有输入张量")?;
+        let output = metadata.outputs.first().context("模型没有输出张量")?;
+
+        // 输入形状为 [N, 3, H, W]，动态维度(-1)默认使用640
+        let dim = |index: usize| {
+            input
+                .shape
+                .get(index)
+                .copied()
+                .filter(|v| *v > 0)
+                .map(|v| v as u32)
+                .unwrap_or(640)
+        };
+        let (input_height, input_width) = (dim(2), dim(3));
+
+        let (data, ratio, padding) = yolo::letterbox(image, input_width, input_height);
+        let shape = vec![1, 3, input_height as usize, input_width as usize];
+
+        let start_time = std::time::Instant::now();
+        let result = self.infer(input, shape, &data, output).await?;
+        let inference_time = start_time.elapsed().as_secs_f32() * 1000.0;
+
+        let detections = yolo::postprocess(
+            &result.data,
+            &result.shape,
+            class_names,
+            conf_threshold,
+            iou_threshold,
+            ratio,
+            padding,
+            (image.width(), image.height()),
+        )?;
+
+        Ok((detections, inference_time))
+    }
+}
+
+/// 客户端键：(服务地址, 模型名称, 模型版本)
+type ClientKey = (String, String, Option<String>);
+
+// 全局KServe客户端，按服务地址和模型复用，模型元数据在多次推理调用之间保持缓存
+lazy_static::lazy_static! {
+    static ref KSERVE_CLIENTS: Mutex<HashMap<ClientKey, Arc<KServeClient>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// 获取（或创建）指定服务地址和模型的共享客户端
+pub fn shared_client(
+    base_url: &str,
+    model_name: &str,
+    model_version: Option<&str>,
+) -> Arc<KServeClient> {
+    let client = KServeClient::new(
+        base_url.to_string(),
+        model_name.to_string(),
+        model_version.map(str::to_string),
+    );
+    let key = (
+        client.base_url.clone(),
+        client.model_name.clone(),
+        client.model_version.clone(),
+    );
+    KSERVE_CLIENTS
+        .lock()
+        .unwrap()
+        .entry(key)
+        .or_insert_with(|| Arc::new(client))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// 模拟的KServe v2服务：按 "方法 路径" 返回固定的状态码和JSON，并记录每个路由的请求次数
+    struct MockServer {
+        base_url: String,
+        hits: Arc<Mutex<HashMap<String, usize>>>,
+    }
+
+    impl MockServer {
+        async fn start(routes: Vec<(&'static str, u16, &'static str)>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let base_url = format!("http://{}", listener.local_addr().unwrap());
+            let hits = Arc::new(Mutex::new(HashMap::new()));
+            let counter = hits.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(respond(stream, routes.clone(), counter.clone()));
+                }
+            });
+            MockServer { base_url, hits }
+        }
+
+        fn hits(&self, route: &str) -> usize {
+            self.hits.lock().unwrap().get(route).copied().unwrap_or(0)
+        }
+    }
+
+    /// 读取一个HTTP请求（请求头和Content-Length长度的请求体）并返回路由对应的响应
+    async fn respond(
+        mut stream: TcpStream,
+        routes: Vec<(&'static str, u16, &'static str)>,
+        hits: Arc<Mutex<HashMap<String, usize>>>,
+    ) {
+        let mut request = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let (head, content_length) = loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            if n == 0 {
+                return;
+            }
+            request.extend_from_slice(&chunk[..n]);
+            if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&request[..end]).to_string();
+                let content_length = head
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                request.drain(..end + 4);
+                break (head, content_length);
+            }
+        };
+        while request.len() < content_length {
+            let n = stream.read(&mut chunk).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&chunk[..n]);
+        }
+
+        let route: String = head
+            .lines()
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .take(2)
+            .collect::<Vec<_>>()
+            .join(" ");
+        *hits.lock().unwrap().entry(route.clone()).or_insert(0) += 1;
+        let (status, body) = routes
+            .iter()
+            .find(|(path, _, _)| *path == route)
+            .map(|(_, status, body)| (*status, *body))
+            .unwrap_or((404, r#"{"error": "not found"}"#));
+        let response = format!(
+            "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+    }
+
+    const METADATA: &str = r#"{
+        "name": "yolo",
+        "versions": ["2"],
+        "platform": "onnxruntime_onnx",
+        "inputs": [{"name": "images", "datatype": "FP32", "shape": [1, 3, 64, 64]}],
+        "outputs": [{"name": "output0", "datatype": "FP32", "shape": [1, 5, 1]}]
+    }"#;
+
+    /// 一个锚点、一个类别的YOLO输出：中心(32, 32)、宽高16、置信度0.9
+    const INFER_RESPONSE: &str = r#"{
+        "model_name": "yolo",
+        "outputs": [{"name": "output0", "datatype": "FP32", "shape": [1, 5, 1], "data": [32, 32, 16, 16, 0.9]}]
+    }"#;
+
+    #[tokio::test]
+    async fn check_ready_reports_server_and_model_state() {
+        let server = MockServer::start(vec![
+            ("GET /v2/health/ready", 200, "{}"),
+            ("GET /v2/models/yolo/ready", 200, "{}"),
+        ])
+        .await;
+
+        let ready = KServeClient::new(server.base_url.clone(), "yolo".to_string(), None);
+        assert!(ready.check_ready().await.unwrap());
+        let missing = KServeClient::new(server.base_url.clone(), "missing".to_string(), None);
+        assert!(!missing.check_ready().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn detect_runs_inference_and_fetches_metadata_once() {
+        let server = MockServer::start(vec![
+            ("GET /v2/models/yolo/versions/2", 200, METADATA),
+            ("POST /v2/models/yolo/versions/2/infer", 200, INFER_RESPONSE),
+        ])
+        .await;
+        let client = KServeClient::new(
+            format!("{}/", server.base_url),
+            "yolo".to_string(),
+            Some("2".to_string()),
+        );
+
+        let metadata = client.get_metadata().await.unwrap();
+        assert_eq!(metadata.inputs[0].name, "images");
+        assert_eq!(metadata.outputs[0].shape, [1, 5, 1]);
+
+        let image = DynamicImage::new_rgb8(64, 64);
+        let class_names = vec!["car".to_string()];
+        for _ in 0..2 {
+            let (detections, _) = client
+                .detect(&image, &class_names, 0.5, 0.45)
+                .await
+                .unwrap();
+            assert_eq!(detections.len(), 1);
+            assert_eq!(detections[0].class_name, "car");
+            assert!((detections[0].confidence - 0.9).abs() < 1e-6);
+            assert_eq!(detections[0].bbox, [24.0, 24.0, 40.0, 40.0]);
+        }
+        // get_metadata一次，detect两次只获取一次元数据
+        assert_eq!(server.hits("GET /v2/models/yolo/versions/2"), 2);
+        assert_eq!(server.hits("POST /v2/models/yolo/versions/2/infer"), 2);
+    }
+
+    #[tokio::test]
+    async fn error_response_is_reported() {
+        let server = MockServer::start(vec![
+            ("GET /v2/models/yolo", 200, METADATA),
+            (
+                "POST /v2/models/yolo/infer",
+                400,
+                r#"{"error": "unexpected shape for input 'images'"}"#,
+            ),
+        ])
+        .await;
+        let client = KServeClient::new(server.base_url.clone(), "yolo".to_string(), None);
+
+        let image = DynamicImage::new_rgb8(64, 64);
+        let error = client.detect(&image, &[], 0.5, 0.45).await.unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("400"), "{}", message);
+        assert!(message.contains("unexpected shape"), "{}", message);
+
+        let missing = KServeClient::new(server.base_url.clone(), "missing".to_string(), None);
+        assert!(missing
+            .get_metadata()
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("404"));
+    }
+
+    #[tokio::test]
+    async fn non_fp32_output_is_rejected() {
+        let server = MockServer::start(vec![
+            ("GET /v2/models/yolo", 200, METADATA),
+            (
+                "POST /v2/models/yolo/infer",
+                200,
+                r#"{"outputs": [{"name": "output0", "datatype": "BYTES", "shape": [1], "data": ["a"]}]}"#,
+            ),
+        ])
+        .await;
+        let client = KServeClient::new(server.base_url.clone(), "yolo".to_string(), None);
+
+        let image = DynamicImage::new_rgb8(64, 64);
+        let message = client
+            .detect(&image, &[], 0.5, 0.45)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("BYTES"), "{}", message);
+        assert!(message.contains("FP32"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn shared_client_is_reused_per_endpoint_and_model() {
+        let server = MockServer::start(vec![("GET /v2/models/yolo", 200, METADATA)]).await;
+
+        let first = shared_client(&server.base_url, "yolo", None);
+        let second = shared_client(&format!("{}/", server.base_url), "yolo", Some(""));
+        let other = shared_client(&server.base_url, "other", None);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+
+        // 元数据缓存在共享客户端上，跨调用只请求一次
+        first.cached_metadata().await.unwrap();
+        second.cached_metadata().await.unwrap();
+        assert_eq!(server.hits("GET /v2/models/yolo"), 1);
+    }
+}
@@ -1,10 +1,24 @@
 //! 推理模块
 //!
-//! 包含API推理和ONNX推理相关功能
+//! 包含API推理、KServe推理、ONNX推理、图片分类和交互式分割相关功能
 
 pub mod api_client;
+pub mod classification;
+pub mod kserve_client;
+pub mod segmentation;
 pub mod model_inference;
+pub mod yolo;
 
+#[cfg(feature = "onnx")]
+pub mod engine_registry;
+#[cfg(feature = "onnx")]
+pub mod model_report;
+#[cfg(feature = "onnx")]
+pub mod onnx_classifier;
 #[cfg(feature = "onnx")]
 pub mod onnx_inference;
+#[cfg(feature = "onnx")]
+pub mod onnx_proto;
+#[cfg(feature = "onnx")]
+pub mod segment_anything;
 
@@ -1,23 +1,16 @@
+use std::sync::Arc;
+
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use image::{DynamicImage, GenericImageView};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 
 use super::api_client::{ApiClient, Detection};
+use super::classification::CascadeConfig;
+use super::kserve_client::{self, KServeClient};
+use crate::core::labels::read_label_lines;
 use crate::models::AnnotationData;
 
-#[cfg(feature = "onnx")]
-use lazy_static::lazy_static;
-
-// ONNX 推理引擎缓存
-#[cfg(feature = "onnx")]
-lazy_static! {
-    static ref ONNX_ENGINE_CACHE: Mutex<HashMap<String, Arc<super::onnx_inference::OnnxInferenceEngine>>> =
-        Mutex::new(HashMap::new());
-}
-
 /// 推理模式
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -28,6 +21,21 @@ pub enum InferenceMode {
         conf_threshold: f32,
         iou_threshold: f32,
     },
+    /// 使用KServe v2 / Triton协议推理（预处理和后处理在本地完成）
+    Kserve {
+        base_url: String,
+        model_name: String,
+        #[serde(default)]
+        model_version: Option<String>,
+        /// 显式指定的类别名称
+        #[serde(default)]
+        class_names: Vec<String>,
+        /// 类别名称文件（每行一个类别，如classes.txt）
+        #[serde(default)]
+        class_names_file: Option<String>,
+        conf_threshold: f32,
+        iou_threshold: f32,
+    },
     /// 使用ONNX推理 (需要feature)
     #[cfg(feature = "onnx")]
     Onnx {
@@ -35,6 +43,15 @@ pub enum InferenceMode {
         conf_threshold: f32,
         iou_threshold: f32,
         use_gpu: bool,
+        /// 显式指定的类别名称（模型缺少names元数据时使用）
+        #[serde(default)]
+        class_names: Vec<String>,
+        /// 类别名称文件（每行一个类别，如classes.txt）
+        #[serde(default)]
+        class_names_file: Option<String>,
+        /// 动态输入尺寸模型的输入尺寸 [width, height]
+        #[serde(default)]
+        input_size: Option<[u32; 2]>,
     },
 }
 
@@ -43,6 +60,9 @@ pub enum InferenceMode {
 pub struct InferenceConfig {
     pub mode: InferenceMode,
     pub count: InferenceCount,
+    /// 检测后的二级分类（可选）
+    #[serde(default)]
+    pub cascade: Option<CascadeConfig>,
 }
 
 /// 推理数量配置
@@ -72,18 +92,68 @@ pub struct BatchInferenceResult {
     pub error_count: usize,
 }
 
+/// 解析类别名称：显式列表优先，其次为类别名称文件
+/// 两者都未指定时返回None，由推理后端自行决定
+pub fn resolve_class_names(
+    class_names: &[String],
+    class_names_file: Option<&str>,
+) -> Result<Option<Vec<String>>> {
+    if !class_names.is_empty() {
+        return Ok(Some(class_names.to_vec()));
+    }
+
+    match class_names_file.filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let names = read_label_lines(std::path::Path::new(path))
+                .map_err(|e| anyhow::anyhow!("无法读取类别名称文件 {}: {}", path, e.message))?;
+            if names.is_empty() {
+                anyhow::bail!("类别名称文件为空: {}", path);
+            }
+            Ok(Some(names))
+        }
+        None => Ok(None),
+    }
+}
+
 /// 模型推理管理器
 pub struct InferenceManager {
     config: InferenceConfig,
+    kserve: Option<Arc<KServeClient>>, // KServe模式的共享客户端（缓存模型元数据）
 }
 
 impl InferenceManager {
     pub fn new(config: InferenceConfig) -> Self {
-        Self { config }
+        let kserve = match &config.mode {
+            InferenceMode::Kserve {
+                base_url,
+                model_name,
+                model_version,
+                ..
+            } => Some(kserve_client::shared_client(
+                base_url,
+                model_name,
+                model_version.as_deref(),
+            )),
+            _ => None,
+        };
+        Self { config, kserve }
     }
 
-    /// 推理单张图片
+    /// 推理单张图片并保存标注
     pub async fn inference_single(&self, image_path: &str) -> Result<InferenceResult> {
+        let result = self.detect(image_path).await?;
+
+        // 保存标注到文件
+        crate::core::annotation::save_annotations_internal(image_path, result.annotations.clone())
+            .map_err(|e| anyhow::anyhow!("无法保存标注: {}", e.message))?;
+
+        // println!("标注已保存: {}", image_path);
+
+        Ok(result)
+    }
+
+    /// 推理单张图片（不保存标注）
+    pub async fn detect(&self, image_path: &str) -> Result<InferenceResult> {
         // 加载图片
         let img = image::open(image_path).context("无法打开图片")?;
         let (width, height) = img.dimensions();
@@ -98,39 +168,63 @@ impl InferenceManager {
                 self.inference_with_api(&img, base_url, *conf_threshold, *iou_threshold)
                     .await?
             }
+            InferenceMode::Kserve {
+                class_names,
+                class_names_file,
+                conf_threshold,
+                iou_threshold,
+                ..
+            } => {
+                let class_names =
+                    resolve_class_names(class_names, class_names_file.as_deref())?
+                        .unwrap_or_default();
+                let client = self.kserve.as_ref().context("KServe客户端未初始化")?;
+                client
+                    .detect(&img, &class_names, *conf_threshold, *iou_threshold)
+                    .await?
+            }
             #[cfg(feature = "onnx")]
             InferenceMode::Onnx {
                 model_path,
                 conf_threshold,
                 iou_threshold,
                 use_gpu,
-            } => self.inference_with_onnx(
-                &img,
-                model_path,
-                *conf_threshold,
-                *iou_threshold,
-                *use_gpu,
-            )?,
+                class_names,
+                class_names_file,
+                input_size,
+            } => {
+                let options = super::onnx_inference::OnnxModelOptions {
+                    class_names: resolve_class_names(class_names, class_names_file.as_deref())?,
+                    input_size: input_size.map(|[w, h]| (w, h)),
+                };
+                self.inference_with_onnx(
+                    &img,
+                    model_path,
+                    *conf_threshold,
+                    *iou_threshold,
+                    *use_gpu,
+                    options,
+                )?
+            }
         };
 
         // 转换为标注数据
-        let annotations = detections
+        let mut annotations = detections
             .into_iter()
             .map(|det| self.detection_to_annotation(det, width, height))
             .collect::<Vec<_>>();
 
+        // 二级分类
+        if let Some(cascade) = &self.config.cascade {
+            self.apply_cascade(&img, &mut annotations, cascade)?;
+        }
+
         // println!(
         //     "推理完成: {} - 检测到 {} 个目标",
         //     image_path,
         //     annotations.len()
         // );
 
-        // 保存标注到文件
-        crate::core::annotation::save_annotations_internal(image_path, annotations.clone())
-            .map_err(|e| anyhow::anyhow!("无法保存标注: {}", e.message))?;
-
-        // println!("标注已保存: {}", image_path);
-
         Ok(InferenceResult {
             image_path: image_path.to_string(),
             annotations,
@@ -206,38 +300,44 @@ impl InferenceManager {
         conf_threshold: f32,
         iou_threshold: f32,
         use_gpu: bool,
+        options: super::onnx_inference::OnnxModelOptions,
     ) -> Result<(Vec<Detection>, f32)> {
-        use super::onnx_inference::OnnxInferenceEngine;
-
-        // 生成缓存key：包含模型路径和GPU配置
-        let cache_key = format!("{}:{}:{}", model_path, use_gpu, conf_threshold);
-
-        // 尝试从缓存获取引擎
-        let engine = {
-            let mut cache = ONNX_ENGINE_CACHE.lock().unwrap();
-
-            if let Some(cached_engine) = cache.get(&cache_key) {
-                // println!("♻️  复用已加载的ONNX推理引擎");
-                Arc::clone(cached_engine)
-            } else {
-                // println!("🆕 创建新的ONNX推理引擎并缓存");
-                let new_engine = Arc::new(
-                    OnnxInferenceEngine::new(model_path, conf_threshold, iou_threshold, use_gpu)
-                        .context("无法创建ONNX推理器")?,
-                );
-                cache.insert(cache_key.clone(), Arc::clone(&new_engine));
-                new_engine
-            }
-        };
+        use super::engine_registry::ONNX_ENGINES;
+
+        // 从注册表获取引擎（按模型身份复用，阈值每次传入）
+        let engine = ONNX_ENGINES.get_detector(model_path, use_gpu, options)?;
 
         // 推理（只计时实际推理部分）
         let start_time = std::time::Instant::now();
-        let detections = engine.inference(img).context("ONNX推理失败")?;
+        let detections = engine
+            .inference(img, conf_threshold, iou_threshold)
+            .context("ONNX推理失败")?;
         let inference_time = start_time.elapsed().as_secs_f32() * 1000.0;
 
         Ok((detections, inference_time))
     }
 
+    /// 二级分类 (需要feature)
+    #[cfg(feature = "onnx")]
+    fn apply_cascade(
+        &self,
+        img: &DynamicImage,
+        annotations: &mut [AnnotationData],
+        cascade: &CascadeConfig,
+    ) -> Result<()> {
+        super::classification::apply_cascade(img, annotations, cascade).context("二级分类失败")
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn apply_cascade(
+        &self,
+        _img: &DynamicImage,
+        _annotations: &mut [AnnotationData],
+        _cascade: &CascadeConfig,
+    ) -> Result<()> {
+        anyhow::bail!("二级分类需要ONNX功能，请使用--features onnx编译")
+    }
+
     /// 将图片转换为base64
     fn image_to_base64(&self, img: &DynamicImage) -> Result<String> {
         let mut buffer = Vec::new();
@@ -280,8 +380,17 @@ impl InferenceManager {
                 width: width as f64,
                 height: height as f64,
                 rotation: Some(angle_rad as f64),
+                points: None,
                 label: detection.class_name,
+                track_id: None,
+                interpolated: false,
+                group_id: None,
+                parent_id: None,
+                classification: None,
+                attributes: Default::default(),
                 created: chrono::Utc::now().to_rfc3339(),
+                modified: String::new(),
+                author: String::new(),
                 visible: true,
             }
         } else {
@@ -304,8 +413,17 @@ impl InferenceManager {
                 width: width as f64,
                 height: height as f64,
                 rotation: None,
+                points: None,
                 label: detection.class_name,
+                track_id: None,
+                interpolated: false,
+                group_id: None,
+                parent_id: None,
+                classification: None,
+                attributes: Default::default(),
                 created: chrono::Utc::now().to_rfc3339(),
+                modified: String::new(),
+                author: String::new(),
                 visible: true,
             }
         }
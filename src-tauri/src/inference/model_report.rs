@@ -0,0 +1,226 @@
+//! ONNX模型检查报告
+//!
+//! 汇总模型的输入输出、任务类型、opset、自定义元数据、文件哈希，
+//! 并用合成图片做一次计时推理，便于在批量推理前确认模型
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use super::onnx_inference::{create_session, parse_names_metadata, warm_up_session};
+use super::onnx_proto::parse_model_header;
+
+/// 模型任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelTask {
+    Detect,
+    Obb,
+    Seg,
+    Pose,
+    Classify,
+    Unknown,
+}
+
+/// 张量信息
+#[derive(Debug, Serialize)]
+pub struct TensorInfo {
+    pub name: String,
+    pub element_type: String,
+    pub shape: Vec<i64>, // 动态维度为 -1
+}
+
+/// 模型检查报告
+#[derive(Debug, Serialize)]
+pub struct ModelReport {
+    pub model_path: String,
+    pub file_size: u64,
+    pub sha256: String,
+    pub ir_version: Option<i64>,
+    pub opset: Option<i64>,
+    pub producer: String,
+    pub model_version: Option<i64>,
+    pub description: String,
+    pub task: ModelTask,
+    pub inputs: Vec<TensorInfo>,
+    pub outputs: Vec<TensorInfo>,
+    pub input_size: Vec<u32>, // [width, height]
+    pub class_names: Vec<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub warmup_ms: Option<f32>,    // 首次推理耗时（含初始化）
+    pub inference_ms: Option<f32>, // 预热后的推理耗时
+    pub warmup_error: Option<String>,
+}
+
+/// 解析形如 "[640, 640]" 的整数列表元数据
+fn parse_int_list(value: &str) -> Vec<i64> {
+    value
+        .split(|c: char| !c.is_ascii_digit() && c != '-')
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// 根据元数据和输出形状判断任务类型
+fn detect_task(
+    metadata: &BTreeMap<String, String>,
+    outputs: &[TensorInfo],
+    num_classes: usize,
+) -> ModelTask {
+    // Ultralytics导出的模型带有task元数据
+    if let Some(task) = metadata.get("task") {
+        match task.trim() {
+            "detect" => return ModelTask::Detect,
+            "obb" => return ModelTask::Obb,
+            "segment" => return ModelTask::Seg,
+            "pose" => return ModelTask::Pose,
+            "classify" => return ModelTask::Classify,
+            _ => {}
+        }
+    }
+
+    // 分割模型有第二个4维输出（mask原型）
+    if outputs.len() >= 2 && outputs[1].shape.len() == 4 {
+        return ModelTask::Seg;
+    }
+
+    let Some(output) = outputs.first() else {
+        return ModelTask::Unknown;
+    };
+
+    match output.shape.len() {
+        // 分类模型输出为 (N, num_classes)
+        2 => ModelTask::Classify,
+        3 => {
+            if metadata.contains_key("kpt_shape") {
+                return ModelTask::Pose;
+            }
+            let num_features = output.shape[1];
+            if num_classes == 0 || num_features < 0 {
+                ModelTask::Unknown
+            } else if num_features as usize == 4 + num_classes {
+                ModelTask::Detect
+            } else if num_features as usize == 5 + num_classes {
+                ModelTask::Obb
+            } else {
+                ModelTask::Unknown
+            }
+        }
+        _ => ModelTask::Unknown,
+    }
+}
+
+/// 生成模型检查报告
+/// input_size: 动态输入尺寸模型的输入尺寸 (width, height)
+pub fn inspect_model(
+    model_path: &str,
+    use_gpu: bool,
+    input_size: Option<(u32, u32)>,
+) -> Result<ModelReport> {
+    let model_bytes = std::fs::read(model_path).context("无法读取模型文件")?;
+    let sha256 = Sha256::digest(&model_bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    let header = parse_model_header(&model_bytes).context("无法解析ONNX模型文件")?;
+    let file_size = model_bytes.len() as u64;
+    drop(model_bytes);
+
+    let (_environment, session) = create_session(model_path, use_gpu)?;
+
+    let tensor_info = |name: &str, element_type: String, dims: Vec<Option<usize>>| TensorInfo {
+        name: name.to_string(),
+        element_type,
+        shape: dims
+            .into_iter()
+            .map(|d| d.map(|v| v as i64).unwrap_or(-1))
+            .collect(),
+    };
+    let inputs: Vec<TensorInfo> = session
+        .inputs
+        .iter()
+        .map(|input| {
+            tensor_info(
+                &input.name,
+                format!("{:?}", input.input_type),
+                input.dimensions().collect(),
+            )
+        })
+        .collect();
+    let outputs: Vec<TensorInfo> = session
+        .outputs
+        .iter()
+        .map(|output| {
+            tensor_info(
+                &output.name,
+                format!("{:?}", output.output_type),
+                output.dimensions().collect(),
+            )
+        })
+        .collect();
+
+    let class_names = header
+        .metadata
+        .get("names")
+        .map(|names| parse_names_metadata(names))
+        .unwrap_or_default();
+    let task = detect_task(&header.metadata, &outputs, class_names.len());
+
+    // 输入尺寸：固定维度 > 指定尺寸 > imgsz元数据 ([h, w]) > 640
+    let imgsz = header
+        .metadata
+        .get("imgsz")
+        .map(|v| parse_int_list(v))
+        .unwrap_or_default();
+    let fixed_dim = |index: usize| {
+        inputs
+            .first()
+            .and_then(|input| input.shape.get(index))
+            .copied()
+            .filter(|v| *v > 0)
+            .map(|v| v as u32)
+    };
+    let imgsz_dim = |index: usize| imgsz.get(index).filter(|v| **v > 0).map(|v| *v as u32);
+    let input_width = fixed_dim(3)
+        .or(input_size.map(|(w, _)| w))
+        .or(imgsz_dim(1))
+        .unwrap_or(640);
+    let input_height = fixed_dim(2)
+        .or(input_size.map(|(_, h)| h))
+        .or(imgsz_dim(0))
+        .unwrap_or(640);
+
+    // 计时推理：第一次包含初始化开销，第二次为稳定耗时
+    let (warmup_ms, inference_ms, warmup_error) =
+        match warm_up_session(&session, input_width, input_height) {
+            Ok(warmup_ms) => match warm_up_session(&session, input_width, input_height) {
+                Ok(inference_ms) => (Some(warmup_ms), Some(inference_ms), None),
+                Err(e) => (Some(warmup_ms), None, Some(e.to_string())),
+            },
+            Err(e) => (None, None, Some(e.to_string())),
+        };
+
+    let producer = format!("{} {}", header.producer_name, header.producer_version)
+        .trim()
+        .to_string();
+
+    Ok(ModelReport {
+        model_path: model_path.to_string(),
+        file_size,
+        sha256,
+        ir_version: header.ir_version,
+        opset: header.opset,
+        producer,
+        model_version: header.model_version,
+        description: header.doc_string,
+        task,
+        inputs,
+        outputs,
+        input_size: vec![input_width, input_height],
+        class_names,
+        metadata: header.metadata,
+        warmup_ms,
+        inference_ms,
+        warmup_error,
+    })
+}
@@ -0,0 +1,207 @@
+//! ONNX图片分类推理
+//!
+//! 支持YOLOv8-cls和通用分类模型（输出形状为 (1, num_classes)）
+
+use anyhow::{Context, Result};
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use ndarray::{Array, CowArray, IxDyn};
+use ort::{Environment, Session, Value};
+use std::sync::{Arc, Mutex};
+
+use super::classification::Normalization;
+use super::onnx_inference::{create_session, parse_names_metadata};
+use crate::core::labels::read_label_lines;
+
+/// ImageNet 均值和标准差
+const IMAGENET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const IMAGENET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+/// 分类模型加载选项
+#[derive(Debug, Clone, Default)]
+pub struct ClassifierOptions {
+    /// 显式指定的类别名称，优先于模型元数据
+    pub class_names: Option<Vec<String>>,
+    /// 动态输入尺寸模型使用的输入尺寸 (width, height)
+    pub input_size: Option<(u32, u32)>,
+    pub normalization: Normalization,
+}
+
+/// 单个分类预测
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub class_name: String,
+    pub score: f32,
+}
+
+/// ONNX分类器
+pub struct OnnxClassifier {
+    session: Mutex<Session>,
+    #[allow(dead_code)]
+    environment: Arc<Environment>,
+    input_width: u32,
+    input_height: u32,
+    class_names: Vec<String>,
+    normalization: Normalization,
+}
+
+impl OnnxClassifier {
+    /// 创建ONNX分类器
+    pub fn with_options(model_path: &str, use_gpu: bool, options: ClassifierOptions) -> Result<Self> {
+        let (environment, session) = create_session(model_path, use_gpu)?;
+
+        // 输入尺寸：固定维度 > 指定尺寸 > 224
+        let input_shape = session.inputs[0].dimensions().collect::<Vec<_>>();
+        let fixed_dim = |index: usize| {
+            input_shape
+                .get(index)
+                .and_then(|d| d.map(|v| v as u32))
+                .filter(|v| *v > 0)
+        };
+        let input_width = fixed_dim(3)
+            .or(options.input_size.map(|(w, _)| w))
+            .unwrap_or(224);
+        let input_height = fixed_dim(2)
+            .or(options.input_size.map(|(_, h)| h))
+            .unwrap_or(224);
+
+        // 输出形状应为 (1, num_classes)
+        let output_shape = session.outputs[0].dimensions().collect::<Vec<_>>();
+        if output_shape.len() != 2 {
+            anyhow::bail!(
+                "不是分类模型：输出形状应为 (1, num_classes)，实际为 {:?}",
+                output_shape
+            );
+        }
+        let num_outputs = output_shape[1];
+
+        // 类别名称：显式指定 > 模型元数据 > 模型同目录的classes.txt > class_{id}
+        let class_names = match options.class_names {
+            Some(names) if !names.is_empty() => names,
+            _ => session
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.custom("names").ok().flatten())
+                .map(|names| parse_names_metadata(&names))
+                .filter(|names| !names.is_empty())
+                .or_else(|| {
+                    let classes_path = std::path::Path::new(model_path)
+                        .parent()?
+                        .join("classes.txt");
+                    read_label_lines(&classes_path)
+                        .ok()
+                        .filter(|names| !names.is_empty())
+                })
+                .unwrap_or_else(|| {
+                    (0..num_outputs.unwrap_or(0))
+                        .map(|id| format!("class_{}", id))
+                        .collect()
+                }),
+        };
+
+        if let Some(num_outputs) = num_outputs {
+            if num_outputs != class_names.len() {
+                anyhow::bail!(
+                    "模型输出类别数 {} 与类别名称数量 {} 不匹配，请指定正确的类别名称",
+                    num_outputs,
+                    class_names.len()
+                );
+            }
+        }
+
+        Ok(Self {
+            session: Mutex::new(session),
+            environment,
+            input_width,
+            input_height,
+            class_names,
+            normalization: options.normalization,
+        })
+    }
+
+    /// 分类单张图片，返回按置信度降序的前top_k个结果
+    pub fn classify(&self, image: &DynamicImage, top_k: usize) -> Result<Vec<Classification>> {
+        let input_array = self.preprocess(image)?;
+
+        let session_lock = self.session.lock().unwrap();
+        let allocator = session_lock.allocator();
+        let cow_array: CowArray<f32, IxDyn> = CowArray::from(input_array.view());
+        let input_tensor = Value::from_array(allocator, &cow_array)?;
+
+        let outputs = session_lock
+            .run(vec![input_tensor])
+            .context("ONNX推理失败")?;
+        let output_tensor = outputs[0]
+            .try_extract::<f32>()
+            .context("无法提取输出张量")?;
+        let tensor_view = output_tensor.view();
+        let scores = tensor_view.as_slice().context("无法获取输出数据")?;
+
+        let scores = Self::to_probabilities(scores);
+        let mut predictions: Vec<Classification> = scores
+            .into_iter()
+            .enumerate()
+            .map(|(class_id, score)| Classification {
+                class_name: self
+                    .class_names
+                    .get(class_id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("class_{}", class_id)),
+                score,
+            })
+            .collect();
+
+        predictions.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        predictions.truncate(top_k.max(1));
+
+        Ok(predictions)
+    }
+
+    /// 预处理：短边缩放后中心裁剪到输入尺寸，转换为 (1, 3, H, W)
+    fn preprocess(&self, image: &DynamicImage) -> Result<Array<f32, IxDyn>> {
+        let (img_width, img_height) = image.dimensions();
+        let scale = (self.input_width as f32 / img_width as f32)
+            .max(self.input_height as f32 / img_height as f32);
+        let resized_width = ((img_width as f32 * scale).round() as u32).max(self.input_width);
+        let resized_height = ((img_height as f32 * scale).round() as u32).max(self.input_height);
+
+        let resized = image.resize_exact(resized_width, resized_height, FilterType::Triangle);
+        let left = (resized_width - self.input_width) / 2;
+        let top = (resized_height - self.input_height) / 2;
+        let cropped = resized
+            .crop_imm(left, top, self.input_width, self.input_height)
+            .to_rgb8();
+
+        let mut input_array =
+            Array::zeros((1, 3, self.input_height as usize, self.input_width as usize)).into_dyn();
+        for (x, y, pixel) in cropped.enumerate_pixels() {
+            for c in 0..3 {
+                let value = pixel[c] as f32 / 255.0;
+                input_array[[0, c, y as usize, x as usize]] = match self.normalization {
+                    Normalization::None => value,
+                    Normalization::Imagenet => (value - IMAGENET_MEAN[c]) / IMAGENET_STD[c],
+                };
+            }
+        }
+
+        Ok(input_array)
+    }
+
+    /// 将模型输出转换为概率
+    /// YOLOv8-cls已输出softmax概率，通用模型输出logits时需要softmax
+    fn to_probabilities(scores: &[f32]) -> Vec<f32> {
+        let sum: f32 = scores.iter().sum();
+        let is_probability = scores.iter().all(|s| (0.0..=1.0).contains(s)) && (sum - 1.0).abs() < 1e-3;
+        if is_probability {
+            return scores.to_vec();
+        }
+
+        let max = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let exps: Vec<f32> = scores.iter().map(|s| (s - max).exp()).collect();
+        let exp_sum: f32 = exps.iter().sum();
+        exps.into_iter().map(|e| e / exp_sum).collect()
+    }
+}
@@ -0,0 +1,174 @@
+//! ONNX模型文件头解析
+//!
+//! 只解析 ModelProto 顶层字段（opset、生产者、自定义元数据），
+//! 计算图等大字段直接跳过，不依赖protobuf库
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+/// ModelProto 顶层信息
+#[derive(Debug, Default)]
+pub struct OnnxModelHeader {
+    pub ir_version: Option<i64>,
+    pub opset: Option<i64>, // 默认域 (ai.onnx) 的 opset 版本
+    pub producer_name: String,
+    pub producer_version: String,
+    pub model_version: Option<i64>,
+    pub doc_string: String,
+    pub metadata: BTreeMap<String, String>, // metadata_props 中的全部自定义元数据
+}
+
+/// protobuf 字段值
+enum FieldValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// 最小化的 protobuf 读取器
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).context("protobuf数据不完整")?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("protobuf varint过长")
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        // 损坏的模型可能给出极大的长度，相加溢出时同样按数据不完整处理
+        match self.pos.checked_add(len) {
+            Some(end) if end <= self.buf.len() => {
+                self.pos = end;
+                Ok(())
+            }
+            _ => anyhow::bail!("protobuf数据不完整"),
+        }
+    }
+
+    /// 读取下一个字段，返回 (字段号, 值)
+    fn next_field(&mut self) -> Result<Option<(u64, FieldValue<'a>)>> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+
+        let key = self.read_varint()?;
+        let field = key >> 3;
+        let value = match key & 0x7 {
+            0 => FieldValue::Varint(self.read_varint()?),
+            1 => {
+                self.skip(8)?;
+                FieldValue::Fixed
+            }
+            2 => {
+                let len = usize::try_from(self.read_varint()?).context("protobuf数据不完整")?;
+                let start = self.pos;
+                self.skip(len)?;
+                FieldValue::Bytes(&self.buf[start..start + len])
+            }
+            5 => {
+                self.skip(4)?;
+                FieldValue::Fixed
+            }
+            wire_type => anyhow::bail!("不支持的protobuf wire type: {}", wire_type),
+        };
+
+        Ok(Some((field, value)))
+    }
+}
+
+/// 解析 OperatorSetIdProto，返回 (domain, version)
+fn parse_opset(buf: &[u8]) -> Result<(String, i64)> {
+    let mut reader = ProtoReader::new(buf);
+    let mut domain = String::new();
+    let mut version = 0;
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, FieldValue::Bytes(bytes)) => domain = String::from_utf8_lossy(bytes).to_string(),
+            (2, FieldValue::Varint(v)) => version = v as i64,
+            _ => {}
+        }
+    }
+    Ok((domain, version))
+}
+
+/// 解析 StringStringEntryProto，返回 (key, value)
+fn parse_entry(buf: &[u8]) -> Result<(String, String)> {
+    let mut reader = ProtoReader::new(buf);
+    let mut key = String::new();
+    let mut value_str = String::new();
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, FieldValue::Bytes(bytes)) => key = String::from_utf8_lossy(bytes).to_string(),
+            (2, FieldValue::Bytes(bytes)) => {
+                value_str = String::from_utf8_lossy(bytes).to_string()
+            }
+            _ => {}
+        }
+    }
+    Ok((key, value_str))
+}
+
+/// 解析ONNX模型文件内容
+pub fn parse_model_header(buf: &[u8]) -> Result<OnnxModelHeader> {
+    let mut header = OnnxModelHeader::default();
+    let mut reader = ProtoReader::new(buf);
+
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, FieldValue::Varint(v)) => header.ir_version = Some(v as i64),
+            (2, FieldValue::Bytes(bytes)) => {
+                header.producer_name = String::from_utf8_lossy(bytes).to_string()
+            }
+            (3, FieldValue::Bytes(bytes)) => {
+                header.producer_version = String::from_utf8_lossy(bytes).to_string()
+            }
+            (5, FieldValue::Varint(v)) => header.model_version = Some(v as i64),
+            (6, FieldValue::Bytes(bytes)) => {
+                header.doc_string = String::from_utf8_lossy(bytes).to_string()
+            }
+            (8, FieldValue::Bytes(bytes)) => {
+                let (domain, version) = parse_opset(bytes)?;
+                if domain.is_empty() || domain == "ai.onnx" {
+                    header.opset = Some(version);
+                }
+            }
+            (14, FieldValue::Bytes(bytes)) => {
+                let (key, value) = parse_entry(bytes)?;
+                header.metadata.insert(key, value);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_length_is_a_parse_error() {
+        // 字段1、wire type 2，长度为 u64::MAX
+        let mut buf = vec![0x0a];
+        buf.extend_from_slice(&[0xff; 9]);
+        buf.push(0x01);
+        buf.push(0x00);
+        assert!(parse_model_header(&buf).is_err());
+    }
+}
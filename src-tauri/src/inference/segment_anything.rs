@@ -0,0 +1,347 @@
+//! Segment Anything 风格的ONNX分割引擎
+//!
+//! 编码器把图片编码为图像嵌入（较慢，每张图片只需一次，结果缓存），
+//! 解码器根据点击/框提示生成掩码（很快，可随每次点击重新运行）
+
+use anyhow::{Context, Result};
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use ndarray::{Array, CowArray, IxDyn};
+use ort::{Environment, Session, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::onnx_inference::create_session;
+use super::segmentation::SegmentPrompt;
+use crate::core::geometry::BinaryMask;
+
+/// SAM 输入归一化参数（0-255 像素值）
+const PIXEL_MEAN: [f32; 3] = [123.675, 116.28, 103.53];
+const PIXEL_STD: [f32; 3] = [58.395, 57.12, 57.375];
+
+/// 默认编码器输入尺寸（最长边）
+const DEFAULT_INPUT_SIZE: u32 = 1024;
+
+/// 图像嵌入
+pub struct ImageEmbedding {
+    data: Array<f32, IxDyn>, // 编码器输出 (1, C, H, W)
+    image_width: u32,
+    image_height: u32,
+    scale: f32, // 原图到编码器输入的缩放比例
+}
+
+/// SAM 分割引擎
+pub struct SamEngine {
+    encoder: Mutex<Session>,
+    decoder: Mutex<Session>,
+    #[allow(dead_code)]
+    environments: Vec<Arc<Environment>>,
+    encoder_path: String,
+    input_size: u32,
+}
+
+impl SamEngine {
+    /// 加载编码器和解码器
+    pub fn new(encoder_path: &str, decoder_path: &str, use_gpu: bool) -> Result<Self> {
+        let (encoder_environment, encoder) =
+            create_session(encoder_path, use_gpu).context("无法加载编码器")?;
+        let (decoder_environment, decoder) =
+            create_session(decoder_path, use_gpu).context("无法加载解码器")?;
+
+        // 编码器输入应为 (1, 3, S, S)
+        let encoder_shape = encoder.inputs[0].dimensions().collect::<Vec<_>>();
+        if encoder_shape.len() != 4 {
+            anyhow::bail!(
+                "不支持的编码器输入形状 {:?}，应为 (1, 3, H, W)",
+                encoder_shape
+            );
+        }
+        let input_size = encoder_shape[2]
+            .filter(|v| *v > 0)
+            .map(|v| v as u32)
+            .unwrap_or(DEFAULT_INPUT_SIZE);
+
+        for required in ["image_embeddings", "point_coords", "point_labels"] {
+            if !decoder.inputs.iter().any(|input| input.name == required) {
+                anyhow::bail!("解码器缺少输入 {}，不是Segment Anything解码器", required);
+            }
+        }
+
+        Ok(Self {
+            encoder: Mutex::new(encoder),
+            decoder: Mutex::new(decoder),
+            environments: vec![encoder_environment, decoder_environment],
+            encoder_path: encoder_path.to_string(),
+            input_size,
+        })
+    }
+
+    /// 编码图片：最长边缩放到输入尺寸，归一化后在右下方补零
+    pub fn encode(&self, image: &DynamicImage) -> Result<ImageEmbedding> {
+        let (image_width, image_height) = image.dimensions();
+        let scale = self.input_size as f32 / image_width.max(image_height) as f32;
+        let resized_width = ((image_width as f32 * scale).round() as u32).clamp(1, self.input_size);
+        let resized_height =
+            ((image_height as f32 * scale).round() as u32).clamp(1, self.input_size);
+        let resized = image
+            .resize_exact(resized_width, resized_height, FilterType::Triangle)
+            .to_rgb8();
+
+        let size = self.input_size as usize;
+        let mut input_array = Array::zeros((1, 3, size, size)).into_dyn();
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            for c in 0..3 {
+                input_array[[0, c, y as usize, x as usize]] =
+                    (pixel[c] as f32 - PIXEL_MEAN[c]) / PIXEL_STD[c];
+            }
+        }
+
+        let session_lock = self.encoder.lock().unwrap();
+        let allocator = session_lock.allocator();
+        let cow_array: CowArray<f32, IxDyn> = CowArray::from(input_array.view());
+        let input_tensor = Value::from_array(allocator, &cow_array)?;
+
+        let outputs = session_lock
+            .run(vec![input_tensor])
+            .context("编码器推理失败")?;
+        let output_tensor = outputs[0]
+            .try_extract::<f32>()
+            .context("无法提取图像嵌入")?;
+        let data = output_tensor.view().to_owned();
+        if data.ndim() != 4 {
+            anyhow::bail!("不支持的图像嵌入形状 {:?}", data.shape());
+        }
+
+        Ok(ImageEmbedding {
+            data,
+            image_width,
+            image_height,
+            scale,
+        })
+    }
+
+    /// 根据提示解码掩码，多掩码输出时取预测IoU最高的一个
+    /// 返回原图尺寸的二值掩码和预测IoU
+    pub fn decode(
+        &self,
+        embedding: &ImageEmbedding,
+        prompt: &SegmentPrompt,
+    ) -> Result<(BinaryMask, f32)> {
+        // 提示坐标转换到编码器输入坐标系
+        // 标签：1前景点，0背景点，2/3框的左上/右下角，-1填充点
+        let mut coords: Vec<f32> = Vec::new();
+        let mut labels: Vec<f32> = Vec::new();
+        for point in &prompt.points {
+            coords.push(point.x as f32 * embedding.scale);
+            coords.push(point.y as f32 * embedding.scale);
+            labels.push(if point.positive { 1.0 } else { 0.0 });
+        }
+        match prompt.bbox {
+            Some([x_min, y_min, x_max, y_max]) => {
+                coords.extend([x_min as f32, y_min as f32].map(|v| v * embedding.scale));
+                coords.extend([x_max as f32, y_max as f32].map(|v| v * embedding.scale));
+                labels.extend([2.0, 3.0]);
+            }
+            None => {
+                coords.extend([0.0, 0.0]);
+                labels.push(-1.0);
+            }
+        }
+        let num_points = labels.len();
+
+        let session_lock = self.decoder.lock().unwrap();
+
+        // 按解码器输入顺序准备张量
+        let embedding_shape = embedding.data.shape();
+        let mask_input_size = (embedding_shape[2] * 4, embedding_shape[3] * 4);
+        let mut arrays: Vec<CowArray<f32, IxDyn>> = Vec::new();
+        for input in &session_lock.inputs {
+            let array = match input.name.as_str() {
+                "image_embeddings" => CowArray::from(embedding.data.view()),
+                "point_coords" => CowArray::from(
+                    Array::from_shape_vec((1, num_points, 2), coords.clone())?.into_dyn(),
+                ),
+                "point_labels" => CowArray::from(
+                    Array::from_shape_vec((1, num_points), labels.clone())?.into_dyn(),
+                ),
+                "mask_input" => CowArray::from(
+                    Array::<f32, _>::zeros((1, 1, mask_input_size.0, mask_input_size.1))
+                        .into_dyn(),
+                ),
+                "has_mask_input" => CowArray::from(Array::from_vec(vec![0.0f32]).into_dyn()),
+                "orig_im_size" => CowArray::from(
+                    Array::from_vec(vec![
+                        embedding.image_height as f32,
+                        embedding.image_width as f32,
+                    ])
+                    .into_dyn(),
+                ),
+                other => anyhow::bail!("不支持的解码器输入: {}", other),
+            };
+            arrays.push(array);
+        }
+
+        let allocator = session_lock.allocator();
+        let input_tensors = arrays
+            .iter()
+            .map(|array| Value::from_array(allocator, array))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let outputs = session_lock
+            .run(input_tensors)
+            .context("解码器推理失败")?;
+
+        let output_index = |name: &str, fallback: usize| {
+            session_lock
+                .outputs
+                .iter()
+                .position(|output| output.name == name)
+                .unwrap_or(fallback)
+        };
+        let masks_tensor = outputs[output_index("masks", 0)]
+            .try_extract::<f32>()
+            .context("无法提取掩码")?;
+        let masks = masks_tensor.view();
+        if masks.ndim() != 4 {
+            anyhow::bail!("不支持的掩码形状 {:?}", masks.shape());
+        }
+
+        // 多掩码输出时选择预测IoU最高的掩码
+        let num_masks = masks.shape()[1];
+        let iou_index = output_index("iou_predictions", 1);
+        let scores: Vec<f32> = match outputs.get(iou_index) {
+            Some(value) if num_masks > 0 => {
+                let iou_tensor = value.try_extract::<f32>().context("无法提取IoU预测")?;
+                let iou_view = iou_tensor.view();
+                iou_view.iter().copied().take(num_masks).collect()
+            }
+            _ => vec![0.0; num_masks],
+        };
+        let (best_index, best_score) = scores
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f32::MIN), |best, item| if item.1 > best.1 { item } else { best });
+
+        let (mask_height, mask_width) = (masks.shape()[2], masks.shape()[3]);
+        let mut mask = BinaryMask::new(embedding.image_width, embedding.image_height);
+        if mask_width == embedding.image_width as usize
+            && mask_height == embedding.image_height as usize
+        {
+            // 原图尺寸的掩码
+            for y in 0..mask_height {
+                for x in 0..mask_width {
+                    if masks[[0, best_index, y, x]] > 0.0 {
+                        mask.set(x as u32, y as u32, true);
+                    }
+                }
+            }
+        } else {
+            // 低分辨率掩码对应补零后的编码器输入，按原图像素采样
+            let mask_scale = mask_width as f32 / self.input_size as f32;
+            for y in 0..embedding.image_height {
+                for x in 0..embedding.image_width {
+                    let mx = ((x as f32 + 0.5) * embedding.scale * mask_scale) as usize;
+                    let my = ((y as f32 + 0.5) * embedding.scale * mask_scale) as usize;
+                    if mx < mask_width && my < mask_height && masks[[0, best_index, my, mx]] > 0.0
+                    {
+                        mask.set(x, y, true);
+                    }
+                }
+            }
+        }
+
+        Ok((mask, best_score.max(0.0)))
+    }
+}
+
+/// 图像嵌入缓存项
+struct EmbeddingItem {
+    embedding: Arc<ImageEmbedding>,
+    modified: Option<SystemTime>, // 编码时图片文件的修改时间
+    len: u64,                     // 编码时图片文件的大小
+    timestamp: u128,              // 缓存时间戳（毫秒）
+}
+
+/// 图像嵌入缓存，按图片路径（加编码器路径）区分；图片文件的修改时间或大小变化后重新编码
+pub struct EmbeddingCache {
+    cache: Mutex<HashMap<String, EmbeddingItem>>,
+    max_items: usize,
+}
+
+impl EmbeddingCache {
+    pub fn new(max_items: usize) -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+            max_items,
+        }
+    }
+
+    fn now() -> u128 {
+        SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    }
+
+    /// 获取图像嵌入（从缓存或编码），返回 (嵌入, 是否命中缓存)
+    pub fn get_or_encode(
+        &self,
+        image_path: &str,
+        engine: &SamEngine,
+    ) -> Result<(Arc<ImageEmbedding>, bool)> {
+        let cache_key = format!("{}_{}", image_path, engine.encoder_path);
+        let metadata = std::fs::metadata(image_path).context("无法读取图片文件信息")?;
+        let modified = metadata.modified().ok();
+
+        // 先检查缓存（图片被覆盖后同一路径的旧嵌入不再使用）
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(item) = cache
+                .get_mut(&cache_key)
+                .filter(|item| item.modified == modified && item.len == metadata.len())
+            {
+                item.timestamp = Self::now();
+                return Ok((Arc::clone(&item.embedding), true));
+            }
+        }
+
+        // 缓存未命中，编码图片（不持有锁）
+        let image = image::open(image_path).context("无法打开图片")?;
+        let embedding = Arc::new(engine.encode(&image)?);
+
+        let mut cache = self.cache.lock().unwrap();
+        // 如果缓存已满，移除最旧的项（替换同一图片的旧嵌入时不需要）
+        if cache.len() >= self.max_items && !cache.contains_key(&cache_key) {
+            if let Some(oldest_key) = cache
+                .iter()
+                .min_by_key(|(_, item)| item.timestamp)
+                .map(|(key, _)| key.clone())
+            {
+                cache.remove(&oldest_key);
+            }
+        }
+        cache.insert(
+            cache_key,
+            EmbeddingItem {
+                embedding: Arc::clone(&embedding),
+                modified,
+                len: metadata.len(),
+                timestamp: Self::now(),
+            },
+        );
+
+        Ok((embedding, false))
+    }
+
+    /// 清空缓存
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+// 全局图像嵌入缓存
+// 参数：最多缓存8张图片的嵌入（SAM ViT-B 每张约4MB）
+lazy_static::lazy_static! {
+    pub static ref EMBEDDING_CACHE: EmbeddingCache = EmbeddingCache::new(8);
+}
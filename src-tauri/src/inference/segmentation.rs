@@ -0,0 +1,124 @@
+//! 交互式分割
+//!
+//! 根据正/负点击或框提示分割当前绘制的目标，
+//! 返回简化后的多边形、最小面积旋转框或水平框
+
+#[cfg(feature = "onnx")]
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "onnx")]
+use crate::core::geometry::{BinaryMask, ShapeKind};
+
+/// 分割模型配置（Segment Anything 风格的编码器/解码器）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmenterConfig {
+    pub encoder_path: String,
+    pub decoder_path: String,
+    #[serde(default)]
+    pub use_gpu: bool,
+}
+
+/// 点击提示（图片像素坐标）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptPoint {
+    pub x: f64,
+    pub y: f64,
+    /// true为前景点，false为背景点
+    #[serde(default = "default_positive")]
+    pub positive: bool,
+}
+
+fn default_positive() -> bool {
+    true
+}
+
+/// 分割提示
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SegmentPrompt {
+    #[serde(default)]
+    pub points: Vec<PromptPoint>,
+    /// 框提示 [x_min, y_min, x_max, y_max]
+    #[serde(default, rename = "box")]
+    pub bbox: Option<[f64; 4]>,
+}
+
+/// 分割结果（坐标约定与AnnotationData一致）
+#[cfg(feature = "onnx")]
+#[derive(Debug, Serialize)]
+pub struct SegmentationResult {
+    #[serde(rename = "type")]
+    pub annotation_type: String, // "polygon"、"rotated-rectangle" 或 "rectangle"
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<[f64; 2]>, // 多边形顶点，仅polygon使用
+    pub score: f32,       // 解码器预测的掩码IoU
+    pub mask_area: usize, // 掩码像素数
+    pub embedding_cached: bool,
+    pub inference_time_ms: f32,
+}
+
+/// 根据提示分割图片，返回原图尺寸的掩码、预测IoU和图像嵌入是否命中缓存
+#[cfg(feature = "onnx")]
+pub fn prompt_mask(
+    image_path: &str,
+    config: &SegmenterConfig,
+    prompt: &SegmentPrompt,
+) -> anyhow::Result<(BinaryMask, f32, bool)> {
+    use super::engine_registry::ONNX_ENGINES;
+    use super::segment_anything::EMBEDDING_CACHE;
+
+    if prompt.points.is_empty() && prompt.bbox.is_none() {
+        anyhow::bail!("请至少提供一个点击或框提示");
+    }
+
+    let engine =
+        ONNX_ENGINES.get_segmenter(&config.encoder_path, &config.decoder_path, config.use_gpu)?;
+    let (embedding, embedding_cached) = EMBEDDING_CACHE.get_or_encode(image_path, &engine)?;
+    let (mask, score) = engine.decode(&embedding, prompt)?;
+
+    Ok((mask, score, embedding_cached))
+}
+
+/// 根据提示分割图片中的目标
+#[cfg(feature = "onnx")]
+pub fn segment_with_prompt(
+    image_path: &str,
+    config: &SegmenterConfig,
+    prompt: &SegmentPrompt,
+    output: ShapeKind,
+    simplify_tolerance: Option<f64>,
+) -> anyhow::Result<SegmentationResult> {
+    use crate::core::geometry::{fit_contour, DEFAULT_SIMPLIFY_TOLERANCE};
+
+    let start_time = std::time::Instant::now();
+    let (mask, score, embedding_cached) = prompt_mask(image_path, config, prompt)?;
+    let inference_time_ms = start_time.elapsed().as_secs_f32() * 1000.0;
+
+    let component = mask.largest_component();
+    let shape = fit_contour(
+        &component.outer_contour(),
+        output,
+        simplify_tolerance.unwrap_or(DEFAULT_SIMPLIFY_TOLERANCE),
+    )
+    .context("未分割出目标，请调整提示")?;
+
+    Ok(SegmentationResult {
+        annotation_type: shape.annotation_type.to_string(),
+        x: shape.x,
+        y: shape.y,
+        width: shape.width,
+        height: shape.height,
+        rotation: shape.rotation,
+        points: shape.points.unwrap_or_default(),
+        score,
+        mask_area: component.area(),
+        embedding_cached,
+        inference_time_ms,
+    })
+}
@@ -0,0 +1,255 @@
+//! YOLO通用前后处理
+//!
+//! letterbox 预处理与检测输出后处理，供本地ONNX推理和远程KServe推理共用
+
+use anyhow::Result;
+use image::{DynamicImage, GenericImageView};
+
+use super::api_client::Detection;
+
+/// letterbox 预处理
+/// 返回：(CHW排列的归一化数据, 缩放比例, (padding_left, padding_top))
+pub fn letterbox(
+    image: &DynamicImage,
+    input_width: u32,
+    input_height: u32,
+) -> (Vec<f32>, f32, (f32, f32)) {
+    let (img_width, img_height) = image.dimensions();
+    let img_width = img_width as f32;
+    let img_height = img_height as f32;
+
+    // 计算缩放比例（保持宽高比）
+    let ratio = (input_width as f32 / img_width).min(input_height as f32 / img_height);
+
+    let new_width = (img_width * ratio).round() as u32;
+    let new_height = (img_height * ratio).round() as u32;
+
+    // 计算padding（与Python letterbox保持一致）
+    let dw = (input_width as f32 - new_width as f32) / 2.0;
+    let dh = (input_height as f32 - new_height as f32) / 2.0;
+
+    // Python代码中的padding计算：top, bottom = int(round(dh - 0.1)), int(round(dh + 0.1))
+    let pad_left = (dw - 0.1).round() as usize;
+    let pad_top = (dh - 0.1).round() as usize;
+
+    // 缩放图片
+    let resized = image.resize_exact(new_width, new_height, image::imageops::FilterType::Triangle);
+
+    // 创建填充后的数组 (3, H, W)，填充值为 114/255
+    let width = input_width as usize;
+    let height = input_height as usize;
+    let plane = width * height;
+    let mut data = vec![114.0 / 255.0; 3 * plane];
+
+    // 将缩放后的图片复制到数组中（归一化到[0,1]），考虑padding
+    for (x, y, pixel) in resized.to_rgb8().enumerate_pixels() {
+        let x = x as usize + pad_left;
+        let y = y as usize + pad_top;
+        if x >= width || y >= height {
+            continue;
+        }
+        let offset = y * width + x;
+        data[offset] = pixel[0] as f32 / 255.0;
+        data[plane + offset] = pixel[1] as f32 / 255.0;
+        data[2 * plane + offset] = pixel[2] as f32 / 255.0;
+    }
+
+    (data, ratio, (dw, dh))
+}
+
+/// 后处理检测输出
+/// data: 形状为 (1, num_features, num_anchors) 的输出张量数据
+/// class_names 为空时按 num_features - 4 推断类别数
+#[allow(clippy::too_many_arguments)]
+pub fn postprocess(
+    data: &[f32],
+    shape: &[usize],
+    class_names: &[String],
+    conf_threshold: f32,
+    iou_threshold: f32,
+    ratio: f32,
+    padding: (f32, f32),
+    image_size: (u32, u32),
+) -> Result<Vec<Detection>> {
+    if shape.len() != 3 {
+        anyhow::bail!("不支持的输出张量形状: {:?}", shape);
+    }
+
+    // output shape: (1, num_features, num_anchors)
+    let num_features = shape[1];
+    let num_anchors = shape[2];
+    if data.len() < num_features * num_anchors || num_features <= 4 {
+        anyhow::bail!("输出张量数据与形状不匹配: {:?}", shape);
+    }
+
+    // 判断是否是旋转框模型
+    // 普通框: num_features = 4 + num_classes (e.g., 84 for COCO)
+    // 旋转框: num_features = 4 + num_classes + 1 (最后一维是角度)
+    let num_classes = if class_names.is_empty() {
+        num_features - 4
+    } else {
+        check_class_count(num_features, class_names.len())?;
+        class_names.len()
+    };
+    let is_rotated = num_features == (4 + num_classes + 1);
+    let class_end = (4 + num_classes).min(num_features);
+
+    let (img_width, img_height) = image_size;
+    let (pad_w, pad_h) = padding;
+    let at = |feature: usize, anchor: usize| data[feature * num_anchors + anchor];
+
+    let mut detections = Vec::new();
+
+    // 遍历每个anchor
+    for anchor in 0..num_anchors {
+        // 前4个是bbox坐标 (cx, cy, w, h)
+        let cx = at(0, anchor);
+        let cy = at(1, anchor);
+        let w = at(2, anchor);
+        let h = at(3, anchor);
+
+        // 找到最大置信度和对应的类别
+        let (class_id, confidence) = (4..class_end)
+            .map(|feature| at(feature, anchor))
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .unwrap_or((0, 0.0));
+
+        // 置信度过滤
+        if confidence < conf_threshold {
+            continue;
+        }
+
+        // 获取类别名称
+        let class_name = class_names
+            .get(class_id)
+            .cloned()
+            .unwrap_or_else(|| format!("class_{}", class_id));
+
+        if is_rotated {
+            // 最后一维是角度(弧度)
+            let mut angle_rad = at(num_features - 1, anchor);
+
+            // 角度范围处理，参考Python实现
+            // if 0.5 * math.pi <= angle <= 0.75 * math.pi: angle -= math.pi
+            let pi = std::f32::consts::PI;
+            if (0.5 * pi..=0.75 * pi).contains(&angle_rad) {
+                angle_rad -= pi;
+            }
+
+            // 转换为度，并将负值转换为 [0, 360) 范围
+            let mut angle_deg = angle_rad * 180.0 / pi;
+            if angle_deg < 0.0 {
+                angle_deg += 360.0;
+            }
+
+            // 旋转框：bbox保持为[cx, cy, w, h]格式，参考Python的scale_boxes：先减padding，再除以ratio
+            detections.push(Detection {
+                class_id,
+                class_name,
+                confidence,
+                bbox: vec![
+                    (cx - pad_w) / ratio,
+                    (cy - pad_h) / ratio,
+                    w / ratio,
+                    h / ratio,
+                ],
+                angle: Some(angle_deg),
+            });
+        } else {
+            // 普通框：转换为[x_min, y_min, x_max, y_max]格式
+            let x_min = ((cx - w / 2.0 - pad_w) / ratio).clamp(0.0, img_width as f32);
+            let y_min = ((cy - h / 2.0 - pad_h) / ratio).clamp(0.0, img_height as f32);
+            let x_max = ((cx + w / 2.0 - pad_w) / ratio).clamp(0.0, img_width as f32);
+            let y_max = ((cy + h / 2.0 - pad_h) / ratio).clamp(0.0, img_height as f32);
+
+            detections.push(Detection {
+                class_id,
+                class_name,
+                confidence,
+                bbox: vec![x_min, y_min, x_max, y_max],
+                angle: None,
+            });
+        }
+    }
+
+    Ok(non_max_suppression(detections, iou_threshold))
+}
+
+/// 检查输出通道数与类别数是否匹配
+/// 普通框为 4 + 类别数，旋转框为 5 + 类别数
+pub fn check_class_count(num_features: usize, num_classes: usize) -> Result<()> {
+    if num_features == 4 + num_classes || num_features == 5 + num_classes {
+        return Ok(());
+    }
+
+    anyhow::bail!(
+        "模型输出通道数 {} 与类别数 {} 不匹配（检测模型应为 {}，旋转框模型应为 {}），请指定正确的类别名称",
+        num_features,
+        num_classes,
+        4 + num_classes,
+        5 + num_classes
+    )
+}
+
+/// 非极大值抑制 (NMS)
+pub fn non_max_suppression(mut detections: Vec<Detection>, iou_threshold: f32) -> Vec<Detection> {
+    // 按置信度排序
+    detections.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut keep: Vec<Detection> = Vec::new();
+
+    for det in detections {
+        // 只对同一类别进行NMS
+        let suppressed = keep.iter().any(|kept| {
+            kept.class_id == det.class_id
+                && calculate_iou(&kept.bbox, &det.bbox, kept.angle.is_some()) >= iou_threshold
+        });
+        if !suppressed {
+            keep.push(det);
+        }
+    }
+
+    keep
+}
+
+/// 计算两个bbox的IOU
+/// is_rotated: 如果为true，bbox格式为[cx, cy, w, h]；否则为[x_min, y_min, x_max, y_max]
+pub fn calculate_iou(box1: &[f32], box2: &[f32], is_rotated: bool) -> f32 {
+    let to_corners = |b: &[f32]| {
+        if is_rotated {
+            // 从[cx, cy, w, h]转换为[x_min, y_min, x_max, y_max]
+            (
+                b[0] - b[2] / 2.0,
+                b[1] - b[3] / 2.0,
+                b[0] + b[2] / 2.0,
+                b[1] + b[3] / 2.0,
+            )
+        } else {
+            (b[0], b[1], b[2], b[3])
+        }
+    };
+
+    let (x1_min, y1_min, x1_max, y1_max) = to_corners(box1);
+    let (x2_min, y2_min, x2_max, y2_max) = to_corners(box2);
+
+    // 计算交集
+    let inter_width = (x1_max.min(x2_max) - x1_min.max(x2_min)).max(0.0);
+    let inter_height = (y1_max.min(y2_max) - y1_min.max(y2_min)).max(0.0);
+    let inter_area = inter_width * inter_height;
+
+    // 计算并集
+    let box1_area = (x1_max - x1_min) * (y1_max - y1_min);
+    let box2_area = (x2_max - x2_min) * (y2_max - y2_min);
+    let union_area = box1_area + box2_area - inter_area;
+
+    if union_area > 0.0 {
+        inter_area / union_area
+    } else {
+        0.0
+    }
+}
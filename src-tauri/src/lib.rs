@@ -1,22 +1,36 @@
 // 模块声明
 mod core; // 核心功能（标注、缓存、图片）
 mod error; // 错误处理
-mod export; // 导出功能（COCO、YOLO）
+mod export; // 导出功能（COCO、YOLO、MOT、分类、数据集划分）
 mod inference; // 推理功能（API、ONNX）
 mod models; // 数据模型
 mod ui; // UI交互（文件对话框、图片加载）
 mod utils; // 工具函数
 
 // 重新导出常用模块
-use core::{annotation, cache, image, labels};
-use inference::{api_client, model_inference};
+use core::{
+    annotation, annotation_db, attributes, cache, history, image, labels, lint, orphans, project,
+    refine, relations, scan, schema, statistics, storage, taxonomy, tracking, video,
+};
+use inference::{api_client, kserve_client, model_inference};
 use ui::{file_dialog, image_loader};
 
 // 导入Tauri命令需要的类型
-use annotation::{load_annotations, save_annotations};
+use annotation::{load_annotations, load_image_tags, save_annotations, save_image_tags};
+use annotation_db::{annotation_label_counts, query_annotations};
+use attributes::{get_attribute_schema, set_attribute_schema};
+use history::{
+    get_annotation_author, load_annotation_history, set_annotation_author, set_change_log_enabled,
+};
+use export::classification::{export_classification, export_tags_csv};
 use export::coco::{export_coco, import_coco};
+use export::mot::{export_mot, import_mot};
+use export::split::export_dataset_split;
 use export::yolo::{export_yolo, export_yolo_batch};
-use file_dialog::{open_folder, select_coco_file, select_save_folder, select_labels_file, read_labels_file};
+use file_dialog::{
+    open_folder, read_labels_file, select_coco_file, select_labels_file, select_save_folder,
+    select_project_file, select_video_file,
+};
 use image::load_image;
 use image_loader::{
     clear_image_cache, generate_thumbnails, get_cache_stats, get_thumbnail, load_image_cached,
@@ -24,9 +38,21 @@ use image_loader::{
 };
 use labels::{
     read_classes_file, write_classes_file, append_class_label,
-    extract_labels_from_folder, classes_file_exists,
+    extract_labels_from_folder, classes_file_exists, apply_label_operation,
 };
+use lint::{fix_lint_issues, lint_dataset};
 use model_inference::{InferenceConfig, InferenceManager};
+use orphans::{reconcile_annotations, repair_annotations};
+use project::{close_project, create_project, open_project, save_project, validate_project};
+use refine::refine_annotation;
+use relations::{group_annotations, set_annotation_parent};
+use scan::scan_folder;
+use schema::migrate_annotation_schema;
+use statistics::{dataset_statistics, export_dataset_statistics};
+use storage::{get_annotation_storage, migrate_annotation_storage, set_annotation_storage};
+use taxonomy::{load_class_taxonomy, save_class_taxonomy};
+use tracking::{interpolate_keyframes, propagate_annotations};
+use video::extract_video_frames;
 
 // 旧的greet函数，保持兼容性
 #[tauri::command]
@@ -50,6 +76,28 @@ async fn get_api_model_info(base_url: String) -> Result<api_client::ModelInfo, S
     client.get_model_info().await.map_err(|e| e.to_string())
 }
 
+/// 检查KServe服务及模型是否就绪
+#[tauri::command]
+async fn check_kserve_health(
+    base_url: String,
+    model_name: String,
+    model_version: Option<String>,
+) -> Result<bool, String> {
+    let client = kserve_client::KServeClient::new(base_url, model_name, model_version);
+    client.check_ready().await.map_err(|e| e.to_string())
+}
+
+/// 获取KServe模型元数据
+#[tauri::command]
+async fn get_kserve_model_metadata(
+    base_url: String,
+    model_name: String,
+    model_version: Option<String>,
+) -> Result<kserve_client::KServeModelMetadata, String> {
+    let client = kserve_client::KServeClient::new(base_url, model_name, model_version);
+    client.get_metadata().await.map_err(|e| e.to_string())
+}
+
 /// 对单张图片进行推理
 #[tauri::command]
 async fn inference_single(
@@ -78,6 +126,68 @@ async fn inference_batch(
         .map_err(|e| e.to_string())
 }
 
+/// 使用分类模型为图片预填图片级标签
+#[cfg(feature = "onnx")]
+#[tauri::command]
+async fn classify_images(
+    image_paths: Vec<String>,
+    config: crate::inference::classification::ImageClassificationConfig,
+) -> Result<crate::inference::classification::BatchClassificationResult, String> {
+    use crate::inference::classification;
+
+    tokio::task::spawn_blocking(move || classification::classify_images(&image_paths, &config))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "onnx"))]
+#[tauri::command]
+async fn classify_images(
+    _image_paths: Vec<String>,
+    _config: crate::inference::classification::ImageClassificationConfig,
+) -> Result<(), String> {
+    Err("ONNX功能未启用，请使用--features onnx编译".to_string())
+}
+
+/// 根据点击/框提示分割目标，返回多边形、旋转框或水平框
+#[cfg(feature = "onnx")]
+#[tauri::command]
+async fn segment_with_prompt(
+    image_path: String,
+    config: crate::inference::segmentation::SegmenterConfig,
+    prompt: crate::inference::segmentation::SegmentPrompt,
+    output: Option<crate::core::geometry::ShapeKind>,
+    simplify_tolerance: Option<f64>,
+) -> Result<crate::inference::segmentation::SegmentationResult, String> {
+    use crate::inference::segmentation;
+
+    tokio::task::spawn_blocking(move || {
+        segmentation::segment_with_prompt(
+            &image_path,
+            &config,
+            &prompt,
+            output.unwrap_or_default(),
+            simplify_tolerance,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "onnx"))]
+#[tauri::command]
+async fn segment_with_prompt(
+    _image_path: String,
+    _config: crate::inference::segmentation::SegmenterConfig,
+    _prompt: crate::inference::segmentation::SegmentPrompt,
+    _output: Option<crate::core::geometry::ShapeKind>,
+    _simplify_tolerance: Option<f64>,
+) -> Result<(), String> {
+    Err("ONNX功能未启用，请使用--features onnx编译".to_string())
+}
+
 /// 选择ONNX模型文件
 #[tauri::command]
 async fn select_onnx_model() -> Result<String, String> {
@@ -99,27 +209,83 @@ async fn select_onnx_model() -> Result<String, String> {
 }
 
 /// 验证ONNX模型（获取模型信息）
+/// 加载选项与推理配置相同（类别名称、类别名称文件、动态输入尺寸），模型通过引擎注册表加载，验证后推理可直接复用；
+/// 没有找到类别名称时仍返回模型信息，class_names为空，class_names_source为"none"
 #[cfg(feature = "onnx")]
 #[tauri::command]
-async fn validate_onnx_model(model_path: String) -> Result<OnnxModelInfo, String> {
-    use crate::inference::onnx_inference::OnnxInferenceEngine;
+async fn validate_onnx_model(
+    model_path: String,
+    use_gpu: Option<bool>,
+    class_names: Option<Vec<String>>,
+    class_names_file: Option<String>,
+    input_size: Option<[u32; 2]>,
+) -> Result<OnnxModelInfo, String> {
+    use crate::inference::engine_registry::ONNX_ENGINES;
+    use crate::inference::model_inference::resolve_class_names;
+    use crate::inference::onnx_inference::OnnxModelOptions;
 
-    let engine = OnnxInferenceEngine::new(&model_path, 0.25, 0.45, false)
-        .map_err(|e| format!("无法加载模型: {}", e))?;
+    let options = OnnxModelOptions {
+        class_names: resolve_class_names(
+            &class_names.unwrap_or_default(),
+            class_names_file.as_deref(),
+        )
+        .map_err(|e| e.to_string())?,
+        input_size: input_size.map(|[w, h]| (w, h)),
+    };
+    let engine = ONNX_ENGINES
+        .get_detector(&model_path, use_gpu.unwrap_or(false), options)
+        .map_err(|e| format!("无法加载模型: {:#}", e))?;
 
     let (width, height) = engine.get_input_size();
-    let class_names = engine.get_class_names().to_vec();
-
     Ok(OnnxModelInfo {
         model_path,
         input_size: vec![width, height],
-        class_names,
+        class_names: engine.get_class_names().to_vec(),
+        class_names_source: engine.class_names_source().as_str().to_string(),
     })
 }
 
 #[cfg(not(feature = "onnx"))]
 #[tauri::command]
-async fn validate_onnx_model(_model_path: String) -> Result<OnnxModelInfo, String> {
+async fn validate_onnx_model(
+    _model_path: String,
+    _use_gpu: Option<bool>,
+    _class_names: Option<Vec<String>>,
+    _class_names_file: Option<String>,
+    _input_size: Option<[u32; 2]>,
+) -> Result<OnnxModelInfo, String> {
+    Err("ONNX功能未启用，请使用--features onnx编译".to_string())
+}
+
+/// 生成ONNX模型检查报告（输入输出、任务类型、元数据、文件哈希、计时推理）
+#[cfg(feature = "onnx")]
+#[tauri::command]
+async fn inspect_onnx_model(
+    model_path: String,
+    use_gpu: Option<bool>,
+    input_size: Option<[u32; 2]>,
+) -> Result<crate::inference::model_report::ModelReport, String> {
+    use crate::inference::model_report::inspect_model;
+
+    tokio::task::spawn_blocking(move || {
+        inspect_model(
+            &model_path,
+            use_gpu.unwrap_or(false),
+            input_size.map(|[w, h]| (w, h)),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("无法检查模型: {}", e))
+}
+
+#[cfg(not(feature = "onnx"))]
+#[tauri::command]
+async fn inspect_onnx_model(
+    _model_path: String,
+    _use_gpu: Option<bool>,
+    _input_size: Option<[u32; 2]>,
+) -> Result<(), String> {
     Err("ONNX功能未启用，请使用--features onnx编译".to_string())
 }
 
@@ -129,8 +295,65 @@ struct OnnxModelInfo {
     model_path: String,
     input_size: Vec<u32>,
     class_names: Vec<String>,
+    class_names_source: String, // "options"、"metadata"、"classes-file"、"coco" 或 "none"
+}
+
+/// 列出已加载的ONNX模型（memory_bytes为估算值，见set_model_memory_budget）
+#[cfg(feature = "onnx")]
+#[tauri::command]
+async fn list_loaded_models() -> Result<Vec<LoadedModelInfo>, String> {
+    use crate::inference::engine_registry::ONNX_ENGINES;
+
+    Ok(ONNX_ENGINES.list())
+}
+
+#[cfg(not(feature = "onnx"))]
+#[tauri::command]
+async fn list_loaded_models() -> Result<Vec<LoadedModelInfo>, String> {
+    Ok(Vec::new())
+}
+
+/// 卸载ONNX模型，返回卸载的引擎数量
+#[cfg(feature = "onnx")]
+#[tauri::command]
+async fn unload_model(model_path: String) -> Result<usize, String> {
+    use crate::inference::engine_registry::ONNX_ENGINES;
+
+    Ok(ONNX_ENGINES.unload(&model_path))
+}
+
+#[cfg(not(feature = "onnx"))]
+#[tauri::command]
+async fn unload_model(_model_path: String) -> Result<usize, String> {
+    Ok(0)
+}
+
+/// 设置ONNX模型内存预算（MB）
+/// 每个引擎的占用按模型文件大小的2倍粗略估算（见EngineRegistry::estimate_memory），
+/// 不包含GPU显存和大输入尺寸的中间张量，实际占用可能明显更高，预算应留出余量
+#[cfg(feature = "onnx")]
+#[tauri::command]
+async fn set_model_memory_budget(budget_mb: u64) -> Result<(), String> {
+    use crate::inference::engine_registry::ONNX_ENGINES;
+
+    ONNX_ENGINES.set_memory_budget(budget_mb.saturating_mul(1024 * 1024));
+    Ok(())
 }
 
+#[cfg(not(feature = "onnx"))]
+#[tauri::command]
+async fn set_model_memory_budget(_budget_mb: u64) -> Result<(), String> {
+    Err("ONNX功能未启用，请使用--features onnx编译".to_string())
+}
+
+#[cfg(feature = "onnx")]
+use crate::inference::engine_registry::LoadedModelInfo;
+
+/// 已加载模型信息（未启用ONNX时不会有已加载模型）
+#[cfg(not(feature = "onnx"))]
+#[derive(serde::Serialize)]
+struct LoadedModelInfo {}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -138,9 +361,39 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             open_folder,
+            scan_folder,
+            get_annotation_storage,
+            set_annotation_storage,
+            migrate_annotation_storage,
+            migrate_annotation_schema,
+            query_annotations,
+            annotation_label_counts,
+            get_annotation_author,
+            set_annotation_author,
+            set_change_log_enabled,
+            load_annotation_history,
+            get_attribute_schema,
+            set_attribute_schema,
+            group_annotations,
+            set_annotation_parent,
+            load_class_taxonomy,
+            save_class_taxonomy,
+            dataset_statistics,
+            export_dataset_statistics,
+            lint_dataset,
+            fix_lint_issues,
+            reconcile_annotations,
+            repair_annotations,
             select_save_folder,
             select_coco_file,
             select_labels_file,
+            select_video_file,
+            select_project_file,
+            create_project,
+            open_project,
+            close_project,
+            save_project,
+            validate_project,
             read_labels_file,
             load_image,
             load_image_cached,
@@ -151,21 +404,41 @@ pub fn run() {
             get_cache_stats,
             save_annotations,
             load_annotations,
+            load_image_tags,
+            save_image_tags,
+            refine_annotation,
+            extract_video_frames,
+            propagate_annotations,
+            interpolate_keyframes,
             export_yolo,
             export_yolo_batch,
             export_coco,
             import_coco,
+            export_mot,
+            import_mot,
+            export_dataset_split,
+            export_classification,
+            export_tags_csv,
             read_classes_file,
             write_classes_file,
             append_class_label,
             extract_labels_from_folder,
             classes_file_exists,
+            apply_label_operation,
             check_api_health,
             get_api_model_info,
+            check_kserve_health,
+            get_kserve_model_metadata,
             inference_single,
             inference_batch,
+            classify_images,
+            segment_with_prompt,
             select_onnx_model,
-            validate_onnx_model
+            validate_onnx_model,
+            inspect_onnx_model,
+            list_loaded_models,
+            unload_model,
+            set_model_memory_budget
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,4 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::core::relations::AnnotationRelation;
+use crate::core::schema::ANNOTATION_SCHEMA_VERSION;
 
 /// 图片数据结构
 #[derive(Debug, Serialize)]
@@ -14,16 +18,34 @@ pub struct ImageData {
 pub struct AnnotationData {
     pub id: String,
     #[serde(rename = "type")]
-    pub annotation_type: String, // "rectangle" or "rotated-rectangle"
+    pub annotation_type: String, // "rectangle", "rotated-rectangle" or "polygon"
     pub x: f64,
     pub y: f64,
     pub width: f64,
     pub height: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rotation: Option<f64>, // 旋转角度，仅旋转矩形使用
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub points: Option<Vec<[f64; 2]>>, // 多边形顶点，仅多边形使用（x, y, width, height 为其外接框）
     pub label: String,
-    #[serde(skip_serializing, default)]
-    pub created: String, // ISO时间戳（不保存到JSON）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub track_id: Option<u64>, // 跨帧的目标跟踪ID
+    #[serde(skip_serializing_if = "is_false", default)]
+    pub interpolated: bool, // 由关键帧插值生成，待人工审核
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub group_id: Option<u64>, // 分组ID（同一图片中同组的标注属于同一对象，如骑手和自行车）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub parent_id: Option<String>, // 父标注ID（如车牌所属的车辆）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub classification: Option<ImageTag>, // 二级分类器的预测结果
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub attributes: BTreeMap<String, serde_json::Value>, // 项目定义的属性值（见attributes模块）
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub created: String, // 创建时间（ISO时间戳）
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub modified: String, // 最后修改时间（ISO时间戳）
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub author: String, // 最后修改者
     #[serde(skip_serializing, default = "default_visible")]
     pub visible: bool, // 是否可见（不保存到JSON）
 }
@@ -33,14 +55,81 @@ fn default_visible() -> bool {
     true
 }
 
+/// 为false时不写入JSON
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// 图片级标签（天气、场景、有无缺陷等）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageTag {
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub score: Option<f32>, // 模型预测的置信度，手动标注为空
+}
+
+/// 视频帧来源
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoSource {
+    pub video_path: String,
+    pub frame_index: u64,
+    pub timestamp_ms: f64, // 帧在视频中的时间戳（毫秒）
+}
+
+/// 标注变更记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChangeRecord {
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    pub action: String, // "create"、"update"、"delete" 或 "tags"（图片级标签变化）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation_id: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>, // 修改的字段
+}
+
 /// 标注文件数据结构
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AnnotationFile {
     pub version: String,
     pub image_path: String,
     pub image_width: u32,
     pub image_height: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub image_hash: String, // 图片的感知哈希，图片改名或移动后用于重新关联标注
     pub annotations: Vec<AnnotationData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<ImageTag>, // 图片级标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_video: Option<VideoSource>, // 从视频抽取的帧记录来源
     pub created: String,
     pub modified: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relations: Vec<AnnotationRelation>, // 由标注的分组和父标注生成，供下游工具使用
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<ChangeRecord>, // 变更日志（只追加）
+}
+
+impl AnnotationFile {
+    /// 创建空的标注文件
+    pub fn new(image_path: String, image_width: u32, image_height: u32) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            version: ANNOTATION_SCHEMA_VERSION.to_string(),
+            image_path,
+            image_width,
+            image_height,
+            image_hash: String::new(),
+            annotations: Vec::new(),
+            tags: Vec::new(),
+            source_video: None,
+            created: now.clone(),
+            modified: now,
+            relations: Vec::new(),
+            history: Vec::new(),
+        }
+    }
 }
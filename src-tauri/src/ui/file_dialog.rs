@@ -1,5 +1,8 @@
+use crate::core::labels::read_label_lines;
+use crate::core::project::{reset_project_state, PROJECT_EXTENSION};
+use crate::core::scan::{scan_images, ScanOptions};
 use crate::error::AppError;
-use crate::utils::is_image_file;
+use crate::utils::SUPPORTED_VIDEO_EXTENSIONS;
 
 /// 选择保存文件夹
 #[tauri::command]
@@ -19,9 +22,13 @@ pub async fn select_save_folder(_window: tauri::Window) -> Result<String, AppErr
     Ok(folder_path_str)
 }
 
-/// 打开文件夹并获取图片文件列表
+/// 打开文件夹并获取图片文件列表（同时关闭已打开的项目，见reset_project_state）
+/// options: 扫描选项（递归、包含/排除glob、符号链接），默认只扫描文件夹本身
 #[tauri::command]
-pub async fn open_folder(_window: tauri::Window) -> Result<Vec<String>, AppError> {
+pub async fn open_folder(
+    _window: tauri::Window,
+    options: Option<ScanOptions>,
+) -> Result<Vec<String>, AppError> {
     let folder_path = rfd::AsyncFileDialog::new()
         .set_title("选择包含图片的文件夹")
         .pick_folder()
@@ -33,24 +40,13 @@ pub async fn open_folder(_window: tauri::Window) -> Result<Vec<String>, AppError
 
     let folder_path = folder_path.path();
     // println!("Selected folder: {:?}", folder_path);
+    reset_project_state();
 
-    let mut image_files = Vec::new();
-    let entries = std::fs::read_dir(folder_path)?;
-
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file() && is_image_file(&path) {
-            if let Some(path_str) = path.to_str() {
-                image_files.push(path_str.to_string());
-                // println!("Found image: {}", path_str);
-            }
-        }
-    }
-
-    // 按文件名自然排序
-    image_files.sort_by(|a, b| natord::compare(a, b));
+    // 按相对路径自然排序
+    let image_files: Vec<String> = scan_images(folder_path, &options.unwrap_or_default())?
+        .into_iter()
+        .filter_map(|path| path.to_str().map(|s| s.to_string()))
+        .collect();
 
     // println!("Total {} image files found", image_files.len());
     Ok(image_files)
@@ -75,6 +71,39 @@ pub async fn select_coco_file(_window: tauri::Window) -> Result<String, AppError
     Ok(file_path_str)
 }
 
+/// 选择视频文件
+#[tauri::command]
+pub async fn select_video_file(_window: tauri::Window) -> Result<String, AppError> {
+    let file_path = rfd::AsyncFileDialog::new()
+        .set_title("选择视频文件")
+        .add_filter("视频文件", SUPPORTED_VIDEO_EXTENSIONS)
+        .add_filter("GIF动图", &["gif"])
+        .pick_file()
+        .await;
+
+    let file_path = file_path.ok_or_else(|| AppError {
+        message: "No file selected".to_string(),
+    })?;
+
+    Ok(file_path.path().to_string_lossy().to_string())
+}
+
+/// 选择项目文件（.oria）
+#[tauri::command]
+pub async fn select_project_file(_window: tauri::Window) -> Result<String, AppError> {
+    let file_path = rfd::AsyncFileDialog::new()
+        .set_title("选择项目文件")
+        .add_filter("Oria项目", &[PROJECT_EXTENSION])
+        .pick_file()
+        .await;
+
+    let file_path = file_path.ok_or_else(|| AppError {
+        message: "No file selected".to_string(),
+    })?;
+
+    Ok(file_path.path().to_string_lossy().to_string())
+}
+
 /// 选择标签文件（txt格式）
 #[tauri::command]
 pub async fn select_labels_file(_window: tauri::Window) -> Result<String, AppError> {
@@ -96,13 +125,5 @@ pub async fn select_labels_file(_window: tauri::Window) -> Result<String, AppErr
 /// 读取标签文件内容（每行一个类别）
 #[tauri::command]
 pub async fn read_labels_file(file_path: String) -> Result<Vec<String>, AppError> {
-    let content = std::fs::read_to_string(&file_path)?;
-    
-    let labels: Vec<String> = content
-        .lines()
-        .map(|line| line.trim().to_string())
-        .filter(|line| !line.is_empty()) // 过滤空行
-        .collect();
-
-    Ok(labels)
+    read_label_lines(std::path::Path::new(&file_path))
 }
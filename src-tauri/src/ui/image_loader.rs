@@ -121,6 +121,8 @@ pub async fn preload_images(
 #[tauri::command]
 pub async fn clear_image_cache() -> Result<(), AppError> {
     IMAGE_CACHE.clear();
+    #[cfg(feature = "onnx")]
+    crate::inference::segment_anything::EMBEDDING_CACHE.clear();
     Ok(())
 }
 
@@ -1,4 +1,7 @@
-use std::path::Path;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// 支持的图片格式
 pub const SUPPORTED_IMAGE_EXTENSIONS: &[&str] =
@@ -14,6 +17,37 @@ pub fn is_image_file(path: &Path) -> bool {
     false
 }
 
+/// 支持抽帧的视频格式（GIF属于图片格式，只在选择视频文件时额外允许，见is_frame_source_file）
+pub const SUPPORTED_VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "avi", "mkv", "webm"];
+
+/// 检查文件是否为支持的视频格式
+pub fn is_video_file(path: &Path) -> bool {
+    if let Some(extension) = path.extension() {
+        if let Some(ext_str) = extension.to_str() {
+            return SUPPORTED_VIDEO_EXTENSIONS.contains(&ext_str.to_lowercase().as_str());
+        }
+    }
+    false
+}
+
+/// 检查文件是否可以抽帧（视频或GIF动图）
+pub fn is_frame_source_file(path: &Path) -> bool {
+    is_video_file(path)
+        || path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("gif"))
+}
+
+/// CSV字段转义
+pub fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 /// 获取标注文件路径
 pub fn get_annotation_file_path(image_path: &str) -> String {
     let image_path = Path::new(image_path);
@@ -25,3 +59,115 @@ pub fn get_annotation_file_path(image_path: &str) -> String {
         .to_string_lossy()
         .to_string()
 }
+
+/// 备份文件路径：<文件名>.bak
+pub fn backup_file_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".bak");
+    path.with_file_name(file_name)
+}
+
+// 临时文件序号，与进程ID一起保证同时写入同一文件时临时文件不冲突
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 同目录下唯一的临时文件路径：.<文件名>.<进程ID>.<序号>.tmp
+fn unique_temp_path(dir: &Path, file_name: &str) -> PathBuf {
+    let counter = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        counter
+    ))
+}
+
+/// 原子写入文件：先写入同目录下的临时文件并同步到磁盘，再重命名覆盖目标文件，
+/// 写入中途崩溃或磁盘写满时原文件保持不变。keep_backup为true时先把原文件保存为 .bak
+/// 每次写入使用不同的临时文件，同时保存同一文件时不会发布另一次写入未完成的内容
+pub fn write_file_atomic(path: &Path, contents: &[u8], keep_backup: bool) -> std::io::Result<()> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid file path"))?
+        .to_string_lossy()
+        .to_string();
+    let temp_path = unique_temp_path(dir, &file_name);
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+
+        // 备份同样先写临时文件再重命名，任何时刻都有完整的 .bak
+        if keep_backup && path.exists() {
+            let backup_temp_path = unique_temp_path(dir, &format!("{}.bak", file_name));
+            let copied = std::fs::copy(path, &backup_temp_path)
+                .and_then(|_| File::open(&backup_temp_path)?.sync_all())
+                .and_then(|_| std::fs::rename(&backup_temp_path, backup_file_path(path)));
+            if copied.is_err() {
+                let _ = std::fs::remove_file(&backup_temp_path);
+            }
+            copied?;
+        }
+
+        std::fs::rename(&temp_path, path)?;
+        sync_dir(dir);
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// 同步目录，使重命名操作落盘（仅Unix支持打开目录）
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_atomic_writes_publish_complete_files() {
+        let dir = std::env::temp_dir().join(format!("oria-atomic-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.json");
+        let contents: Vec<Vec<u8>> = (0..8u8).map(|n| vec![b'a' + n; 256 * 1024]).collect();
+
+        std::thread::scope(|scope| {
+            for content in &contents {
+                let path = &path;
+                scope.spawn(move || write_file_atomic(path, content, true).unwrap());
+            }
+        });
+
+        assert!(contents.contains(&std::fs::read(&path).unwrap()));
+        assert!(contents.contains(&std::fs::read(backup_file_path(&path)).unwrap()));
+        let leftovers = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}